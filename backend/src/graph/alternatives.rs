@@ -0,0 +1,288 @@
+use std::collections::{BinaryHeap, HashSet};
+
+use crate::graph::{Edge, Graph};
+use crate::graph::router::RouterNode;
+use crate::osm::options::{Routing, Transport};
+
+// max. number of routes which are calculated at once, including the shortest one
+pub const MAX_ALTERNATIVES: usize = 5;
+// max. cost of an alternative relative to the cost of the shortest route
+pub const MAX_STRETCH: f64 = 1.25;
+// max. share of the cost of an alternative on edges of the shortest route or other alternatives
+pub const MAX_OVERLAP: f64 = 0.6;
+// max. number of candidate via nodes whose routes are checked
+const MAX_CANDIDATES: usize = 1000;
+// parent of nodes without parent in search tree
+const NONE: usize = usize::MAX;
+
+pub struct AlternativeRouter<'a> {
+    graph: &'a Graph,
+    mode: Transport,
+    routing: Routing,
+}
+
+impl<'a> AlternativeRouter<'a> {
+    /**
+    Create new router for alternative routes.
+    *
+    @param graph: graph as base for routing
+    @param mode: transportation mode (car/bike)
+    @param routing: routing mode (distance/time)
+    *
+    @return Self: new alternative router
+    */
+    pub fn new(graph: &'a Graph, mode: Transport, routing: Routing) -> Self {
+        Self {
+            graph,
+            mode,
+            routing,
+        }
+    }
+
+    /**
+    Find via nodes of alternative routes. The alternative through a via node consists of the
+    shortest routes from start to via node and from via node to goal. Alternatives are selected in
+    order of their cost, if their cost is within a max. stretch of the shortest route, they do not
+    visit a node twice, and only a limited share of them is on edges of better routes.
+    *
+    @param self: alternative router
+    @param start_index: index of start node
+    @param goal_index: index of goal node
+    @param count: max. number of alternatives
+    *
+    @return indices of via nodes of alternatives, best alternative first
+    */
+    pub fn via_nodes(&self, start_index: usize, goal_index: usize, count: usize) -> Vec<usize> {
+        if count == 0 || start_index == goal_index {
+            return Vec::new();
+        }
+        let forward = self.tree(start_index, goal_index, true);
+        let best = forward.cost[goal_index];
+        if best == u32::MAX {
+            return Vec::new();
+        }
+        let backward = self.tree(goal_index, start_index, false);
+        let bound = f64::from(best) * MAX_STRETCH;
+        let mut candidates: Vec<(u64, usize)> = (0..self.graph.nodes.len())
+            .filter(|index| forward.cost[*index] != u32::MAX && backward.cost[*index] != u32::MAX)
+            .map(|index| (u64::from(forward.cost[index]) + u64::from(backward.cost[index]), index))
+            .filter(|(cost, _)| *cost as f64 <= bound)
+            .collect();
+        candidates.sort_unstable();
+
+        // edges of shortest route and selected alternatives
+        let mut used: HashSet<(usize, usize)> = route(&forward, &backward, goal_index).unwrap_or_default()
+            .into_iter()
+            .map(|(edge, _)| edge)
+            .collect();
+        let mut via = Vec::new();
+        let mut checked = 0;
+        for (cost, index) in candidates {
+            if via.len() == count || checked == MAX_CANDIDATES {
+                break;
+            }
+            // nodes of better routes are only via nodes of routes with high overlap
+            let parent = forward.parent[index];
+            if parent == NONE || used.contains(&(parent, index)) {
+                continue;
+            }
+            checked += 1;
+            let edges = match route(&forward, &backward, index) {
+                Some(edges) => edges,
+                None => continue
+            };
+            let shared: u64 = edges.iter()
+                .filter(|(edge, _)| used.contains(edge))
+                .map(|(_, cost)| u64::from(*cost))
+                .sum();
+            if shared as f64 > cost as f64 * MAX_OVERLAP {
+                continue;
+            }
+            used.extend(edges.into_iter().map(|(edge, _)| edge));
+            via.push(index);
+        }
+        via
+    }
+
+    /**
+    Check if a route differs enough from better routes to be an alternative, i.e. it does not visit
+    a node twice and only a limited share of its cost is on edges of better routes.
+    *
+    @param self: alternative router
+    @param edges: positions of edges of route
+    @param used: positions of edges of better routes
+    *
+    @return true if route is an alternative to better routes
+    */
+    pub fn distinct(&self, edges: &[usize], used: &HashSet<usize>) -> bool {
+        // each node of a route without cycles is left and entered at most once
+        let (mut left, mut entered) = (HashSet::new(), HashSet::new());
+        let mut total = 0;
+        let mut shared = 0;
+        for position in edges {
+            let edge = self.graph.edge(*position);
+            if !left.insert(edge.source_index) || !entered.insert(edge.target_index) {
+                return false;
+            }
+            let cost = u64::from(edge.cost(self.mode, self.routing));
+            total += cost;
+            if used.contains(position) {
+                shared += cost;
+            }
+        }
+        shared as f64 <= total as f64 * MAX_OVERLAP
+    }
+
+    /**
+    Search tree of shortest routes from root, or to root in backward direction. The search stops
+    at nodes beyond the max. stretch of the cost of the route between root and target.
+    *
+    @param self: alternative router
+    @param root: index of root node
+    @param target: index of node at the other end of routes
+    @param forward: true for routes from root, false for routes to root
+    *
+    @return search tree with cost and parent of each node
+    */
+    fn tree(&self, root: usize, target: usize, forward: bool) -> Tree {
+        let mut tree = Tree {
+            cost: vec![u32::MAX; self.graph.nodes.len()],
+            parent: vec![NONE; self.graph.nodes.len()],
+        };
+        let mut queue = BinaryHeap::new();
+        tree.cost[root] = 0;
+        queue.push(RouterNode::new(root, 0, 0));
+        while let Some(node) = queue.pop() {
+            // better solution already found
+            if node.cost > tree.cost[node.index] {
+                continue;
+            }
+            // nodes beyond max. stretch are not part of any alternative
+            let best = tree.cost[target];
+            if best != u32::MAX && f64::from(node.cost) > f64::from(best) * MAX_STRETCH {
+                break;
+            }
+            let edges: Vec<(usize, &Edge)> = if forward {
+                self.graph.edges(node.index).iter().map(|edge| (edge.target_index, edge)).collect()
+            } else {
+                self.graph.incoming_edges(node.index).map(|edge| (edge.source_index, edge)).collect()
+            };
+            for (next, edge) in edges {
+                if !edge.transport.contains(self.mode) {
+                    continue;
+                }
+                let cost = node.cost + edge.cost(self.mode, self.routing);
+                if cost < tree.cost[next] {
+                    tree.cost[next] = cost;
+                    tree.parent[next] = node.index;
+                    queue.push(RouterNode::new(next, cost, 0));
+                }
+            }
+        }
+        tree
+    }
+}
+
+/**
+Search tree of shortest routes from or to a root node.
+*/
+struct Tree {
+    cost: Vec<u32>,
+    // previous node on route from root or next node on route to root
+    parent: Vec<usize>,
+}
+
+/**
+Get edges of route from start to goal through via node.
+*
+@param forward: search tree of routes from start
+@param backward: search tree of routes to goal
+@param via: index of via node
+*
+@return (optional) source and target index with cost of each edge, none if route visits a node twice
+*/
+fn route(forward: &Tree, backward: &Tree, via: usize) -> Option<Vec<((usize, usize), u32)>> {
+    let mut edges = Vec::new();
+    let mut visited = HashSet::new();
+    visited.insert(via);
+    let mut current = via;
+    while forward.parent[current] != NONE {
+        let parent = forward.parent[current];
+        edges.push(((parent, current), forward.cost[current] - forward.cost[parent]));
+        visited.insert(parent);
+        current = parent;
+    }
+    current = via;
+    while backward.parent[current] != NONE {
+        let next = backward.parent[current];
+        if !visited.insert(next) {
+            return None;
+        }
+        edges.push(((current, next), backward.cost[current] - backward.cost[next]));
+        current = next;
+    }
+    Some(edges)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::graph::Graph;
+    use crate::graph::alternatives::{AlternativeRouter, MAX_OVERLAP, MAX_STRETCH};
+    use crate::graph::charging::Planner;
+    use crate::graph::router::Router;
+    use crate::graph::router::tests::grid;
+    use crate::osm::options::Planning;
+    use crate::osm::options::Routing::Time;
+    use crate::osm::options::Transport::Car;
+    use crate::vehicle::Vehicle;
+
+    /**
+    Get share of cost of a route on edges of better routes.
+    */
+    fn overlap(graph: &Graph, edges: &[usize], used: &HashSet<usize>) -> f64 {
+        let cost = |position: &usize| f64::from(graph.edge(*position).cost(Car, Time));
+        let shared: f64 = edges.iter().filter(|position| used.contains(position)).map(cost).sum();
+        shared / edges.iter().map(cost).sum::<f64>()
+    }
+
+    #[test]
+    fn via_nodes() {
+        let graph = grid(8);
+        let best = Router::new(&graph, Car, Time).route(0, 63).unwrap();
+        let via = AlternativeRouter::new(&graph, Car, Time).via_nodes(0, 63, 3);
+        assert!(!via.is_empty());
+        let mut used: HashSet<usize> = best.edges.iter().copied().collect();
+        for index in via {
+            let first = Router::new(&graph, Car, Time).route(0, index).unwrap();
+            let second = Router::new(&graph, Car, Time).route(index, 63).unwrap();
+            // alternatives are within max. stretch and differ from better routes
+            assert!(f64::from(first.time + second.time) <= f64::from(best.time) * MAX_STRETCH);
+            let edges = [second.edges, first.edges].concat();
+            assert!(overlap(&graph, &edges, &used) <= MAX_OVERLAP);
+            used.extend(edges);
+        }
+        // no alternatives between identical nodes
+        assert!(AlternativeRouter::new(&graph, Car, Time).via_nodes(5, 5, 3).is_empty());
+    }
+
+    #[test]
+    fn alternatives() {
+        let graph = grid(8);
+        let planner = Planner::new(&graph, Car, Time, Planning::Time);
+        let vehicle = Vehicle::new(100.0, 100.0, 50.0, 100.0);
+        let plans = planner.alternatives(graph.coordinates(0), graph.coordinates(63), &vehicle, 3).unwrap();
+        assert!(plans.len() >= 2 && plans.len() <= 3);
+        // best route comes first, each alternative is within max. stretch and differs from better ones
+        let mut used: HashSet<usize> = plans[0].route.edges.iter().copied().collect();
+        for plan in &plans[1..] {
+            assert!(plan.route.time >= plans[0].route.time);
+            assert!(f64::from(plan.route.time) <= f64::from(plans[0].route.time) * MAX_STRETCH);
+            assert!(overlap(&graph, &plan.route.edges, &used) <= MAX_OVERLAP);
+            assert!(plan.stops.is_empty());
+            used.extend(plan.route.edges.iter().copied());
+        }
+        assert_eq!(1, planner.alternatives(graph.coordinates(0), graph.coordinates(63), &vehicle, 1).unwrap().len());
+    }
+}
@@ -0,0 +1,1330 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use log::debug;
+
+use crate::graph::{Edge, Graph};
+use crate::graph::alternatives::{AlternativeRouter, MAX_ALTERNATIVES, MAX_STRETCH};
+use crate::graph::tree::{Part, Snap};
+use crate::graph::router::{Ends, Route, Router, RouterNode};
+use crate::osm::Coordinates;
+use crate::osm::options::{ChargingOptions, Planning, Routing, Search, Transport};
+use crate::osm::options::Transport::Car;
+use crate::vehicle::Vehicle;
+
+// max. number of intermediate stops of a trip, as each section between waypoints is searched
+pub const MAX_VIA: usize = 25;
+
+// charging stations by id of their node in graph, with index and charging power in kW of charging station
+type Stations = HashMap<i64, (usize, f32)>;
+// settled labels of planning search by id of their node and section, with their key and charge
+type Settled = HashMap<(i64, usize), Vec<((u32, u32), f32)>>;
+// labels and legs of leg searches by index of their source node and section
+type Searches = HashMap<(usize, usize), (Vec<LegLabel>, Vec<Leg>)>;
+
+pub struct Planner<'a> {
+    graph: &'a Graph,
+    mode: Transport,
+    routing: Routing,
+    planning: Planning,
+    // search strategy of routes between stops
+    pub search: Search,
+}
+
+impl<'a> Planner<'a> {
+    /**
+    Create new planner for routes with charging stops.
+    *
+    @param graph: graph as base for routing
+    @param mode: transportation mode (car/bike)
+    @param routing: routing mode (distance/time) of the legs between stops
+    @param planning: planning objective (fewest stops/lowest total time)
+    *
+    @return Self: new planner
+    */
+    pub fn new(graph: &'a Graph, mode: Transport, routing: Routing, planning: Planning) -> Self {
+        Self {
+            graph,
+            mode,
+            routing,
+            planning,
+            search: Search::Unidirectional,
+        }
+    }
+
+    /**
+    Create router for routes between stops.
+    *
+    @param self: planner
+    *
+    @return router with transportation mode, routing mode and search strategy of planner
+    */
+    fn router(&self) -> Router<'a> {
+        let mut router = Router::new(self.graph, self.mode, self.routing);
+        router.search = self.search;
+        router
+    }
+
+    /**
+    Plan route through waypoints in given order with charging stops. The state of charge of the
+    vehicle is a constrained resource: charging stations are the only nodes where it is restored,
+    and no leg may use more energy than available when leaving the previous stop. Time of a charging
+    stop is the time needed to charge the vehicle to its target charge. Charging stops are planned
+    across the whole trip, so the vehicle may pass a waypoint with a low charge as long as it is
+    able to reach a charging station afterwards.
+    *
+    @param self: planner
+    @param waypoints: start, intermediate stops and goal coordinates
+    @param vehicle: electric vehicle with current state of charge
+    *
+    @return Result<Plan, &str>: feasible plan with a section between each two consecutive waypoints,
+    error if goal cannot be reached with the available charging stations
+    */
+    pub fn plan(&self, waypoints: &[Coordinates], vehicle: &Vehicle) -> Result<Plan, &'static str> {
+        if waypoints.len() < 2 {
+            return Err("At least start and goal are required");
+        }
+        if waypoints.len() > MAX_VIA + 2 {
+            return Err("Too many intermediate stops");
+        }
+        // no charging stop required if the direct routes are within reach
+        let direct = waypoints.windows(2)
+            .map(|pair| self.router().shortest_path(&pair[0], &pair[1]))
+            .collect::<Result<Vec<Route>, _>>()?;
+        let energy = direct.iter().map(|route| route.energy).sum();
+        if vehicle.reaches(energy) {
+            let mut vehicle = vehicle.clone();
+            vehicle.drive(energy);
+            let (route, sections) = join(direct, None);
+            return Ok(Plan::new(route, Vec::new(), sections, vehicle.state_of_charge()));
+        }
+
+        // project waypoints onto nearest edge segments, all partial edges from and to them are used
+        let snaps = waypoints.iter()
+            .map(|coordinates| self.graph.snap(coordinates, self.mode))
+            .collect::<Result<Vec<Snap>, _>>()?;
+        let trip = Trip::new(self.graph, &snaps);
+        let goal = trip.targets.len();
+
+        let stations = self.stations(vehicle);
+        debug!("Planning charging stops with {} charging stations", stations.len());
+        // leg searches start with the highest charge of any label, legs are filtered by charge of label
+        let limit = vehicle.charge.max(vehicle.target);
+        let mut searches: Searches = HashMap::new();
+        // key of the best plan which reached the goal so far
+        let mut best: Option<(u32, u32)> = None;
+        // labels of the search, each one refers to the stop it was reached from
+        let mut labels = Vec::new();
+        // settled labels of each node and section which are not dominated by another label
+        let mut settled: Settled = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        // search starts on all partial edges leaving the start
+        for (label, cost) in self.depart(0, None, 0, vehicle.charge, &trip) {
+            labels.push(label);
+            queue.push(self.plan_node(labels.len() - 1, 0, cost, self.estimate(&labels[labels.len() - 1], &trip)));
+        }
+
+        while let Some(node) = queue.pop() {
+            let label = labels[node.label].clone();
+            if label.at_waypoint() {
+                // goal is reached on a partial edge, labels are popped with increasing cost
+                if label.section == goal {
+                    return self.assemble(&labels, node.label, &searches, &stations, vehicle, &trip);
+                }
+                // waypoint is passed, next section starts on partial edges leaving it
+                for (next, cost) in self.depart(label.section, Some(node.label), node.cost, label.charge, &trip) {
+                    if next.section == goal {
+                        let key = self.key(node.stops, cost);
+                        best = Some(best.map_or(key, |best| best.min(key)));
+                    }
+                    labels.push(next);
+                    queue.push(self.plan_node(labels.len() - 1, node.stops, cost, self.estimate(&labels[labels.len() - 1], &trip)));
+                }
+                continue;
+            }
+            let id = self.graph.node(label.index).id;
+            // label with lower cost and at least as much charge already settled
+            if !settle(&mut settled, (id, label.section), self.key(node.stops, node.cost), label.charge) {
+                continue;
+            }
+            if trip.targets[label.section].contains(&id) {
+                // section ends on partial edges from node to waypoint
+                let next = label.section + 1;
+                for (i, entering) in trip.entering[next].iter().enumerate() {
+                    let energy = entering.edge.energy(self.mode);
+                    if entering.index != label.index || energy > label.charge {
+                        continue;
+                    }
+                    let cost = node.cost.saturating_add(self.planned(&entering.edge));
+                    if next == goal {
+                        let key = self.key(node.stops, cost);
+                        best = Some(best.map_or(key, |best| best.min(key)));
+                    }
+                    labels.push(Label::new(label.index, next, Some(node.label), Step::Entering(i), label.charge - energy));
+                    queue.push(self.plan_node(labels.len() - 1, node.stops, cost, self.estimate(&labels[labels.len() - 1], &trip)));
+                }
+            }
+            // legs of the search which reached node without charging also cover paths through it
+            if let Step::Leg(_) = label.step {
+                continue;
+            }
+            // iterate over all stops within reach of current charge, labels of a node are settled with
+            // increasing cost, so the leg search of the first one also covers all later ones
+            let bound = self.bound(best, node.stops, node.cost);
+            let (_, legs) = searches.entry((label.index, label.section))
+                .or_insert_with(|| self.reachable(label.index, label.section, limit, bound, &stations, &trip));
+            for leg in legs.iter().filter(|leg| leg.energy <= label.charge) {
+                let leg_id = self.graph.node(leg.index).id;
+                let charge = label.charge - leg.energy;
+                // times are planned in ms, so they are not underestimated by the heuristic
+                let planned = match self.planning {
+                    Planning::Time => leg.time,
+                    Planning::Stops => leg.cost,
+                };
+                // vehicle arrives at end of section or is charged to target charge at charging station
+                let mut arrivals = Vec::with_capacity(2);
+                if trip.targets[label.section].contains(&leg_id) {
+                    arrivals.push((Step::Leg(leg.label), node.stops, planned, charge));
+                }
+                if let Some((_, power)) = stations.get(&leg_id) {
+                    let mut arriving = vehicle.clone();
+                    arriving.charge = charge;
+                    let charging_time = arriving.charge(*power);
+                    let planned = match self.planning {
+                        Planning::Time => planned.saturating_add(charging_time.saturating_mul(1000)),
+                        Planning::Stops => planned,
+                    };
+                    arrivals.push((Step::Charge(leg.label), node.stops + 1, planned, arriving.charge));
+                }
+                for (step, stops, planned, charge) in arrivals {
+                    let cost = node.cost.saturating_add(planned);
+                    if dominated(&settled, (leg_id, label.section), self.key(stops, cost), charge) {
+                        continue;
+                    }
+                    labels.push(Label::new(leg.index, label.section, Some(node.label), step, charge));
+                    queue.push(self.plan_node(labels.len() - 1, stops, cost, self.heuristic(leg.index, label.section, &trip)));
+                }
+            }
+        }
+        Err("No charging plan found, goal is out of reach of charging stations")
+    }
+
+    /**
+    Plan the route between start and goal together with alternative routes. Each alternative passes
+    a via node found by the alternative router and gets its own charging plan. As the plan may
+    differ from the route through the via node, e.g. by charging stops, stretch and overlap with
+    better plans are checked on the plan itself.
+    *
+    @param self: planner
+    @param start: start coordinates
+    @param goal: goal coordinates
+    @param vehicle: electric vehicle with its initial state of charge
+    @param count: max. number of routes including the best one
+    *
+    @return Result<Vec<Plan>, &str>: plan of best route followed by plans of alternatives
+    */
+    pub fn alternatives(&self, start: &Coordinates, goal: &Coordinates, vehicle: &Vehicle,
+                        count: usize) -> Result<Vec<Plan>, &'static str> {
+        let best = self.plan(&[start.clone(), goal.clone()], vehicle)?;
+        let first = self.cheapest(self.graph.snap(start, self.mode)?.leaving(self.graph))?;
+        let last = self.cheapest(self.graph.snap(goal, self.mode)?.entering(self.graph))?;
+        let router = AlternativeRouter::new(self.graph, self.mode, self.routing);
+        let bound = f64::from(self.route_cost(&best.route)) * MAX_STRETCH;
+        // edges of best plan and selected alternatives
+        let mut used: HashSet<usize> = best.route.edges.iter().copied().collect();
+        let mut plans = vec![best];
+        if count < 2 {
+            return Ok(plans);
+        }
+        // more via nodes than needed are checked, as some plans are no alternatives
+        for index in router.via_nodes(first.index, last.index, MAX_ALTERNATIVES) {
+            let via = self.graph.node(index).coordinates.clone();
+            // alternatives without charging plan are left out
+            let plan = match self.plan(&[start.clone(), via, goal.clone()], vehicle) {
+                Ok(plan) => plan,
+                Err(error) => {
+                    debug!("Alternative via node {} dropped: {}", index, error);
+                    continue;
+                }
+            };
+            if f64::from(self.route_cost(&plan.route)) > bound || !router.distinct(&plan.route.edges, &used) {
+                debug!("Alternative via node {} dropped: plan is too long or too similar", index);
+                continue;
+            }
+            used.extend(plan.route.edges.iter().copied());
+            plans.push(plan);
+            if plans.len() == count {
+                break;
+            }
+        }
+        Ok(plans)
+    }
+
+    /**
+    Get cost of a planned route based on routing.
+    *
+    @param self: planner
+    @param route: route of plan
+    *
+    @return time including charging or distance of route
+    */
+    fn route_cost(&self, route: &Route) -> u32 {
+        match self.routing {
+            Routing::Time => route.time,
+            Routing::Distance => route.distance,
+        }
+    }
+
+    /**
+    Get labels of the partial edges leaving a waypoint, i.e. to the nodes of the edge it is projected
+    onto or directly to the next waypoint if it lies ahead on the same edge.
+    *
+    @param self: planner
+    @param waypoint: index of waypoint
+    @param parent: (optional) index of label at waypoint, none at start
+    @param cost: cost at waypoint
+    @param charge: energy in Wh at waypoint
+    @param trip: projected waypoints of trip
+    *
+    @return labels of partial edges within reach of charge with their cost
+    */
+    fn depart(&self, waypoint: usize, parent: Option<usize>, cost: u32, charge: f32, trip: &Trip) -> Vec<(Label, u32)> {
+        let mut labels = Vec::new();
+        for (i, part) in trip.leaving[waypoint].iter().enumerate() {
+            let energy = part.edge.energy(self.mode);
+            if energy <= charge {
+                let label = Label::new(part.index, waypoint, parent, Step::Leaving(i), charge - energy);
+                labels.push((label, cost.saturating_add(self.planned(&part.edge))));
+            }
+        }
+        for (i, (part, end)) in trip.direct[waypoint].iter().enumerate() {
+            let energy = part.edge.energy(self.mode);
+            if energy <= charge {
+                let label = Label::new(end.index, waypoint + 1, parent, Step::Direct(i), charge - energy);
+                labels.push((label, cost.saturating_add(self.planned(&part.edge))));
+            }
+        }
+        labels
+    }
+
+    /**
+    Get planning cost of a partial edge.
+    *
+    @param self: planner
+    @param edge: partial edge from or to projected coordinates
+    *
+    @return cost of edge based on planning objective
+    */
+    fn planned(&self, edge: &Edge) -> u32 {
+        match self.planning {
+            Planning::Time => edge.millis(self.mode),
+            Planning::Stops => edge.cost(self.mode, self.routing),
+        }
+    }
+
+    /**
+    Get partial edge with lowest cost.
+    *
+    @param self: planner
+    @param parts: partial edges from or to projected coordinates
+    *
+    @return Result<Part, &str>: partial edge with lowest cost
+    */
+    fn cheapest(&self, parts: Vec<Part>) -> Result<Part, &'static str> {
+        parts.into_iter()
+            .min_by_key(|part| part.edge.cost(self.mode, self.routing))
+            .ok_or("No point matching transportation found")
+    }
+
+    /**
+    Get charging stations compatible with transportation mode and sockets of vehicle.
+    *
+    @param self: planner
+    @param vehicle: electric vehicle
+    *
+    @return charging stations by id of their node in graph
+    */
+    fn stations(&self, vehicle: &Vehicle) -> Stations {
+        let required_charging = ChargingOptions::from(self.mode);
+        let mut stations = HashMap::new();
+
+        for (i, charging_node) in self.graph.charging_nodes.iter().enumerate() {
+            if !charging_node.charging_options.contains(required_charging) {
+                continue;
+            }
+            let power = match charging_node.power_for(&vehicle.sockets) {
+                // vehicle is not able to charge without power
+                Some(power) if power.min(vehicle.charging_power) > 0.0 => power,
+                _ => continue
+            };
+            // charging station is reached at nearest node of graph, the most powerful one is used
+            if let Ok(index) = self.graph.nearest_neighbor(&charging_node.coordinates, self.mode) {
+                let station = stations.entry(self.graph.node(index).id).or_insert((i, power));
+                if power > station.1 {
+                    *station = (i, power);
+                }
+            }
+        }
+        stations
+    }
+
+    /**
+    Search for charging stations and end of section within reach of a node. Labels of the search
+    keep cost and energy of paths, so a path which costs more but needs less energy is kept as well.
+    Only paths which do not use more energy than available are expanded, and paths which cannot
+    lead to a plan better than the best one found so far are pruned.
+    *
+    @param self: planner
+    @param source: index of node search starts at
+    @param section: index of section of trip
+    @param budget: energy in Wh available at source
+    @param bound: (optional) max. planning cost of a leg plus lower bound of cost to goal
+    @param stations: charging stations of graph
+    @param trip: projected waypoints of trip
+    *
+    @return labels of search and legs to all charging stations and end of section within reach
+    */
+    fn reachable(&self, source: usize, section: usize, budget: f32, bound: Option<u32>,
+                 stations: &Stations, trip: &Trip) -> (Vec<LegLabel>, Vec<Leg>) {
+        let source_id = self.graph.node(source).id;
+        let targets = &trip.targets[section];
+        let mut legs = Vec::new();
+        let mut labels = vec![LegLabel::new(source, None, 0, 0, 0.0)];
+        // labels of each node which are not dominated by another label of node
+        let mut pareto: HashMap<usize, Vec<usize>> = HashMap::new();
+        pareto.insert(source, vec![0]);
+        // nodes of queue refer to labels instead of nodes of graph
+        let mut queue = BinaryHeap::new();
+        queue.push(RouterNode::new(0, 0, 0));
+
+        while let Some(node) = queue.pop() {
+            let current = labels[node.index];
+            // label was dominated by a label found later
+            if !pareto[&current.index].contains(&node.index) {
+                continue;
+            }
+            let id = self.graph.node(current.index).id;
+            if id != source_id && (targets.contains(&id) || stations.contains_key(&id)) {
+                legs.push(Leg::new(current.index, node.index, current.cost, current.time, current.energy));
+            }
+            for edge in self.graph.edges(current.index) {
+                if !edge.transport.contains(self.mode) {
+                    continue;
+                }
+                let energy = current.energy + edge.energy(self.mode);
+                // vehicle would run out of energy on edge
+                if energy > budget {
+                    continue;
+                }
+                let cost = current.cost + edge.cost(self.mode, self.routing);
+                let time = current.time + edge.millis(self.mode);
+                // plan via edge would cost more than best plan
+                let planned = match self.planning {
+                    Planning::Time => time,
+                    Planning::Stops => cost,
+                };
+                if bound.is_some_and(|bound| planned.saturating_add(self.heuristic(edge.target_index, section, trip)) > bound) {
+                    continue;
+                }
+                let front = pareto.entry(edge.target_index).or_default();
+                if front.iter().any(|i| labels[*i].cost <= cost && labels[*i].energy <= energy) {
+                    continue;
+                }
+                front.retain(|i| labels[*i].cost < cost || labels[*i].energy < energy);
+                front.push(labels.len());
+                let parent = (node.index, self.graph.position(edge));
+                labels.push(LegLabel::new(edge.target_index, Some(parent), cost, time, energy));
+                queue.push(RouterNode::new(labels.len() - 1, cost, 0));
+            }
+        }
+        (labels, legs)
+    }
+
+    /**
+    Get edges of path to a label of a leg search.
+    *
+    @param self: planner
+    @param labels: labels of leg search
+    @param label: index of label at end of path
+    *
+    @return edges of path in order of driving
+    */
+    fn leg_edges(&self, labels: &[LegLabel], label: usize) -> Vec<&'a Edge> {
+        let mut edges = Vec::new();
+        let mut current = label;
+        while let Some((parent, position)) = labels[current].parent {
+            edges.push(self.graph.edge(position));
+            current = parent;
+        }
+        edges.reverse();
+        edges
+    }
+
+    /**
+    Get key of planning labels for dominance based on planning objective.
+    *
+    @param self: planner
+    @param stops: number of charging stops so far
+    @param cost: cost so far
+    *
+    @return key, labels with lower key are better
+    */
+    fn key(&self, stops: u32, cost: u32) -> (u32, u32) {
+        match self.planning {
+            Planning::Stops => (stops, cost),
+            Planning::Time => (0, cost),
+        }
+    }
+
+    /**
+    Get bound of leg searches from a planning label, based on the best plan found so far.
+    *
+    @param self: planner
+    @param best: (optional) key of best plan which reached the goal so far
+    @param stops: number of charging stops of label
+    @param cost: cost of label
+    *
+    @return (optional) max. planning cost of a leg plus lower bound of cost to goal, none if unbounded
+    */
+    fn bound(&self, best: Option<(u32, u32)>, stops: u32, cost: u32) -> Option<u32> {
+        match best {
+            // plans with fewer stops are better regardless of their cost
+            Some((best_stops, _)) if self.planning == Planning::Stops && stops < best_stops => None,
+            Some((_, best_cost)) => Some(best_cost.saturating_sub(cost)),
+            None => None
+        }
+    }
+
+    /**
+    Get lower bound of planning cost from a node to goal.
+    *
+    @param self: planner
+    @param index: index of node in graph
+    @param section: index of current section of trip
+    @param trip: projected waypoints of trip
+    *
+    @return lower bound of cost based on planning objective
+    */
+    fn heuristic(&self, index: usize, section: usize, trip: &Trip) -> u32 {
+        // distance to waypoint at end of section and along further waypoints to goal
+        let target = &trip.waypoints[section + 1];
+        self.lower_bound(self.graph.coordinates(index).distance(target) + trip.remaining[section + 1])
+    }
+
+    /**
+    Get lower bound of planning cost from a label to goal.
+    *
+    @param self: planner
+    @param label: label of planning search
+    @param trip: projected waypoints of trip
+    *
+    @return lower bound of cost based on planning objective
+    */
+    fn estimate(&self, label: &Label, trip: &Trip) -> u32 {
+        if label.at_waypoint() {
+            self.lower_bound(trip.remaining[label.section])
+        } else {
+            self.heuristic(label.index, label.section, trip)
+        }
+    }
+
+    /**
+    Get lower bound of planning cost of a linear distance.
+    *
+    @param self: planner
+    @param distance: linear distance
+    *
+    @return lower bound of cost based on planning objective
+    */
+    fn lower_bound(&self, distance: u32) -> u32 {
+        match self.planning {
+            // no edge can be traveled faster than max. speed of graph
+            Planning::Time => self.graph.min_millis(self.mode, distance),
+            // cost of cars routed by time is time in ms, otherwise distance
+            Planning::Stops if self.mode == Car && self.routing == Routing::Time => self.graph.min_millis(Car, distance),
+            Planning::Stops => distance,
+        }
+    }
+
+    /**
+    Create node of planning queue.
+    *
+    @param self: planner
+    @param label: index of label
+    @param stops: number of charging stops so far
+    @param cost: cost so far
+    @param heuristic: lower bound of cost from label to goal
+    *
+    @return node of planning queue, prioritized based on planning objective
+    */
+    fn plan_node(&self, label: usize, stops: u32, cost: u32, heuristic: u32) -> PlanNode {
+        let priority = match self.planning {
+            Planning::Stops => (stops, cost + heuristic),
+            Planning::Time => (0, cost + heuristic),
+        };
+        PlanNode {
+            label,
+            stops,
+            cost,
+            priority,
+        }
+    }
+
+    /**
+    Create plan from the paths of the legs between the stops of a label.
+    *
+    @param self: planner
+    @param labels: labels of search
+    @param last: index of label at goal
+    @param searches: leg searches of planning search
+    @param stations: charging stations of graph
+    @param vehicle: electric vehicle with state of charge at start
+    @param trip: projected waypoints of trip
+    *
+    @return Result<Plan, &str>: plan with route, sections and charging stops
+    */
+    fn assemble(&self, labels: &[Label], last: usize, searches: &Searches, stations: &Stations,
+                vehicle: &Vehicle, trip: &Trip) -> Result<Plan, &'static str> {
+        // collect stops from goal to start
+        let mut chain = Vec::new();
+        let mut current = Some(last);
+        while let Some(label) = current {
+            chain.push(&labels[label]);
+            current = labels[label].parent;
+        }
+        chain.reverse();
+
+        let mut vehicle = vehicle.clone();
+        let mut sections = Vec::with_capacity(trip.targets.len());
+        let mut stops = Vec::new();
+        // legs of current section and time needed for charging on it
+        let mut legs = Vec::new();
+        let mut charging_time = 0;
+        // partial edge leaving the waypoint at the start of current section
+        let mut first = None;
+        for (i, label) in chain.iter().enumerate() {
+            match label.step {
+                Step::Leaving(part) => {
+                    let part = &trip.leaving[label.section][part];
+                    vehicle.drive(part.edge.energy(self.mode));
+                    first = Some(part);
+                }
+                Step::Leg(leg) | Step::Charge(leg) => {
+                    let from = chain[i - 1];
+                    let (tree, _) = searches.get(&(from.index, from.section)).ok_or("Charging plan is inconsistent")?;
+                    let mut route = self.router().collect_route(&self.leg_edges(tree, leg));
+                    vehicle.drive(route.energy);
+                    if chain.get(i + 1).is_some_and(|next| matches!(next.step, Step::Leg(_) | Step::Charge(_))) {
+                        // remove node from path, as it is the start of the next leg
+                        route.path.remove(0);
+                    }
+                    if let Step::Charge(_) = label.step {
+                        let (station, power) = stations[&self.graph.node(label.index).id];
+                        let station = &self.graph.charging_nodes[station];
+                        let state_of_charge = vehicle.state_of_charge();
+                        let time = vehicle.charge(power);
+                        charging_time += time;
+                        stops.push(Stop::new(station.coordinates.clone(), state_of_charge, time));
+                    }
+                    legs.push(route);
+                }
+                Step::Entering(part) => {
+                    // waypoint is reached on partial edges at the start and the end of the section
+                    let last = &trip.entering[label.section][part];
+                    let first = first.ok_or("Charging plan is inconsistent")?;
+                    let ends = Ends::new(trip.waypoints[label.section - 1].clone(), first.clone(),
+                                         trip.waypoints[label.section].clone(), last.clone());
+                    sections.push(self.section(std::mem::take(&mut legs), charging_time, chain[i - 1].index, &ends));
+                    charging_time = 0;
+                    vehicle.drive(last.edge.energy(self.mode));
+                }
+                Step::Direct(part) => {
+                    // waypoint lies ahead of previous one on the same edge
+                    let (part, end) = &trip.direct[label.section - 1][part];
+                    let ends = Ends::new(trip.waypoints[label.section - 1].clone(), part.clone(),
+                                         trip.waypoints[label.section].clone(), end.clone());
+                    let mut route = Route::new(Vec::new(), 0, 0, 0.0, None);
+                    route.extend(&ends, self.mode);
+                    sections.push(route);
+                    vehicle.drive(part.edge.energy(self.mode));
+                }
+            }
+        }
+
+        let visited_charging = stops.iter()
+            .map(|stop: &Stop| stop.coordinates.clone())
+            .collect();
+        let (route, sections) = join(sections, Some(visited_charging));
+        Ok(Plan::new(route, stops, sections, vehicle.state_of_charge()))
+    }
+
+    /**
+    Create route of a section of a trip from the legs between its stops.
+    *
+    @param self: planner
+    @param legs: routes between stops of section in order of driving
+    @param charging_time: time in seconds needed for charging on section
+    @param index: index of node section ends at, the only node of section if there are no legs
+    @param ends: projected waypoints of section with partial edges
+    *
+    @return route from waypoint to waypoint, its time includes time needed for charging
+    */
+    fn section(&self, legs: Vec<Route>, charging_time: u32, index: usize, ends: &Ends) -> Route {
+        let mut route = Route::new(Vec::new(), charging_time, 0, 0.0, None);
+        // path of route starts at goal
+        for leg in legs.into_iter().rev() {
+            route.time += leg.time;
+            route.distance += leg.distance;
+            route.energy += leg.energy;
+            route.path.extend(leg.path);
+            route.edges.extend(leg.edges);
+        }
+        if route.path.is_empty() {
+            route.path.push(self.graph.coordinates(index).clone());
+        }
+        route.extend(ends, self.mode);
+        route
+    }
+}
+
+/**
+Join routes of consecutive sections of a trip.
+*
+@param routes: routes of sections in order of driving
+@param visited_charging: (optional) list of visited charging stations on trip
+*
+@return route of whole trip and summary of each section
+*/
+pub fn join(routes: Vec<Route>, visited_charging: Option<Vec<Coordinates>>) -> (Route, Vec<Section>) {
+    let sections = routes.iter()
+        .map(|route| Section::new(route.distance, route.time, route.energy))
+        .collect();
+    let mut trip = Route::new(Vec::new(), 0, 0, 0.0, visited_charging);
+    // path of route starts at goal, waypoints between sections are not repeated
+    for route in routes.into_iter().rev() {
+        trip.time += route.time;
+        trip.distance += route.distance;
+        trip.energy += route.energy;
+        let repeated = !trip.path.is_empty() && trip.path.last() == route.path.first();
+        trip.path.extend(route.path.into_iter().skip(repeated as usize));
+        trip.edges.extend(route.edges);
+    }
+    (trip, sections)
+}
+
+/**
+Projected waypoints of a trip with the partial edges of the sections between them.
+*/
+struct Trip {
+    // projected coordinates of each waypoint
+    waypoints: Vec<Coordinates>,
+    // partial edges leaving each waypoint
+    leaving: Vec<Vec<Part>>,
+    // partial edges entering each waypoint
+    entering: Vec<Vec<Part>>,
+    // parts of edges from each waypoint directly to the next one and the empty parts at their end
+    direct: Vec<Vec<(Part, Part)>>,
+    // ids of nodes each section may end at, i.e. the nodes partial edges to next waypoint leave
+    targets: Vec<Vec<i64>>,
+    // distance as the crow flies from each waypoint along further waypoints to goal
+    remaining: Vec<u32>,
+}
+
+impl Trip {
+    /**
+    Create new trip.
+    *
+    @param graph: graph
+    @param snaps: waypoints projected onto graph
+    *
+    @return new trip
+    */
+    fn new(graph: &Graph, snaps: &[Snap]) -> Self {
+        let waypoints: Vec<Coordinates> = snaps.iter()
+            .map(|snap| snap.coordinates.clone())
+            .collect();
+        let leaving: Vec<Vec<Part>> = snaps.iter()
+            .map(|snap| snap.leaving(graph))
+            .collect();
+        let entering: Vec<Vec<Part>> = snaps.iter()
+            .map(|snap| snap.entering(graph))
+            .collect();
+        let mut direct = vec![Vec::new(); snaps.len()];
+        for (section, pair) in snaps.windows(2).enumerate() {
+            for (first, (position, start_fraction, start_split)) in leaving[section].iter().zip(&pair[0].edges) {
+                for (last, (goal_position, goal_fraction, goal_split)) in entering[section + 1].iter().zip(&pair[1].edges) {
+                    // next waypoint is ahead on same edge
+                    if position == goal_position && goal_fraction >= start_fraction {
+                        let (from, to) = ((*start_fraction, *start_split), (*goal_fraction, *goal_split));
+                        direct[section].push((graph.part(*position, first.index, from, to),
+                                              graph.part(*position, last.index, to, to)));
+                    }
+                }
+            }
+        }
+        let targets = entering[1..].iter()
+            .map(|parts| parts.iter().map(|part| graph.node(part.index).id).collect())
+            .collect();
+        let mut remaining = vec![0; snaps.len()];
+        for waypoint in (0..snaps.len() - 1).rev() {
+            remaining[waypoint] = remaining[waypoint + 1] + waypoints[waypoint].distance(&waypoints[waypoint + 1]);
+        }
+        Self {
+            waypoints,
+            leaving,
+            entering,
+            direct,
+            targets,
+            remaining,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Plan {
+    pub route: Route,
+    pub stops: Vec<Stop>,
+    // sections of route between consecutive waypoints
+    pub sections: Vec<Section>,
+    // state of charge in percent on arrival at goal
+    pub state_of_charge: f32,
+}
+
+impl Plan {
+    /**
+    Create new plan.
+    *
+    @param route: route from start to goal including all stops
+    @param stops: charging stops on route
+    @param sections: sections of route between consecutive waypoints
+    @param state_of_charge: state of charge in percent on arrival at goal
+    *
+    @return new plan
+    */
+    pub fn new(route: Route, stops: Vec<Stop>, sections: Vec<Section>, state_of_charge: f32) -> Self {
+        Self {
+            route,
+            stops,
+            sections,
+            state_of_charge,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Section {
+    pub distance: u32,
+    // time including time needed for charging on section
+    pub time: u32,
+    // energy in Wh
+    pub energy: f32,
+}
+
+impl Section {
+    /**
+    Create new section of a route between two consecutive waypoints.
+    *
+    @param distance: distance of section
+    @param time: time needed for section including charging
+    @param energy: energy in Wh needed for section
+    *
+    @return new section
+    */
+    pub fn new(distance: u32, time: u32, energy: f32) -> Self {
+        Self {
+            distance,
+            time,
+            energy,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Stop {
+    pub coordinates: Coordinates,
+    // state of charge in percent on arrival at charging station
+    pub state_of_charge: f32,
+    // time in seconds needed for charging
+    pub charging_time: u32,
+}
+
+impl Stop {
+    /**
+    Create new charging stop.
+    *
+    @param coordinates: coordinates of charging station
+    @param state_of_charge: state of charge in percent on arrival
+    @param charging_time: time in seconds needed for charging
+    *
+    @return new charging stop
+    */
+    pub fn new(coordinates: Coordinates, state_of_charge: f32, charging_time: u32) -> Self {
+        Self {
+            coordinates,
+            state_of_charge,
+            charging_time,
+        }
+    }
+}
+
+/**
+Step by which a label of the planning search is reached from its parent.
+*/
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    // partial edge leaving waypoint at start of section, by its index
+    Leaving(usize),
+    // leg from parent to end of section, by index of label at end of leg in leg search from parent
+    Leg(usize),
+    // leg from parent to charging stop, by index of label at end of leg in leg search from parent
+    Charge(usize),
+    // partial edge entering waypoint, by its index
+    Entering(usize),
+    // part of edge from previous waypoint directly to waypoint, by its index
+    Direct(usize),
+}
+
+#[derive(Debug, Clone)]
+struct Label {
+    // index of node in graph, labels at waypoints keep the node of the partial edge they are reached on
+    index: usize,
+    // index of section of trip, labels at waypoints have the index of the section starting there
+    section: usize,
+    parent: Option<usize>,
+    step: Step,
+    // energy in Wh when leaving node
+    charge: f32,
+}
+
+impl Label {
+    /**
+    Create new label of planning search.
+    *
+    @param index: index of node in graph
+    @param section: index of section of trip
+    @param parent: (optional) index of label of previous stop
+    @param step: step by which label is reached from parent
+    @param charge: energy in Wh when leaving node
+    *
+    @return new label
+    */
+    fn new(index: usize, section: usize, parent: Option<usize>, step: Step, charge: f32) -> Self {
+        Self {
+            index,
+            section,
+            parent,
+            step,
+            charge,
+        }
+    }
+
+    /**
+    Check if label is at a projected waypoint instead of a node of graph.
+    *
+    @param self: label
+    *
+    @return true if label is reached on a partial edge to a waypoint
+    */
+    fn at_waypoint(&self) -> bool {
+        matches!(self.step, Step::Entering(_) | Step::Direct(_))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Leg {
+    index: usize,
+    // index of label at end of leg in leg search
+    label: usize,
+    cost: u32,
+    // time in ms
+    time: u32,
+    // energy in Wh
+    energy: f32,
+}
+
+impl Leg {
+    /**
+    Create new leg to a node.
+    *
+    @param index: index of node in graph
+    @param label: index of label at end of leg in leg search
+    @param cost: cost of leg based on routing
+    @param time: time in ms needed for leg
+    @param energy: energy in Wh needed for leg
+    *
+    @return new leg
+    */
+    fn new(index: usize, label: usize, cost: u32, time: u32, energy: f32) -> Self {
+        Self {
+            index,
+            label,
+            cost,
+            time,
+            energy,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LegLabel {
+    index: usize,
+    // (optional) index of label of previous node and position of edge from it
+    parent: Option<(usize, usize)>,
+    cost: u32,
+    // time in ms
+    time: u32,
+    // energy in Wh
+    energy: f32,
+}
+
+impl LegLabel {
+    /**
+    Create new label of leg search.
+    *
+    @param index: index of node in graph
+    @param parent: (optional) index of label of previous node and position of edge from it
+    @param cost: cost of path based on routing
+    @param time: time in ms needed for path
+    @param energy: energy in Wh needed for path
+    *
+    @return new label
+    */
+    fn new(index: usize, parent: Option<(usize, usize)>, cost: u32, time: u32, energy: f32) -> Self {
+        Self {
+            index,
+            parent,
+            cost,
+            time,
+            energy,
+        }
+    }
+}
+
+/**
+Check if a planning label is dominated by a settled label, i.e. one with a lower or equal key and at
+least as much charge.
+*
+@param settled: settled labels of planning search
+@param node: id of node and index of section of label
+@param key: key of label based on planning objective
+@param charge: energy in Wh of label when leaving node
+*
+@return true if label is dominated
+*/
+fn dominated(settled: &Settled, node: (i64, usize), key: (u32, u32), charge: f32) -> bool {
+    settled.get(&node).is_some_and(|labels| labels.iter()
+        .any(|(other, other_charge)| *other <= key && *other_charge >= charge))
+}
+
+/**
+Settle a planning label at a node, if it is not dominated by a settled label.
+*
+@param settled: settled labels of planning search
+@param node: id of node and index of section of label
+@param key: key of label based on planning objective
+@param charge: energy in Wh of label when leaving node
+*
+@return true if label was settled, false if it is dominated
+*/
+fn settle(settled: &mut Settled, node: (i64, usize), key: (u32, u32), charge: f32) -> bool {
+    if dominated(settled, node, key, charge) {
+        return false;
+    }
+    settled.entry(node).or_default().push((key, charge));
+    true
+}
+
+#[derive(Debug, Eq, PartialEq)]
+struct PlanNode {
+    label: usize,
+    stops: u32,
+    cost: u32,
+    priority: (u32, u32),
+}
+
+impl Ord for PlanNode {
+    /**
+    Absolute ordering for plan nodes.
+    *
+    @param self: plan node a
+    @param other: plan node b
+    *
+    @return ordering for plan nodes a and b based on priority value
+    */
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority.cmp(&self.priority)
+    }
+}
+
+impl PartialOrd for PlanNode {
+    /**
+    Partial ordering for plan nodes
+    *
+    @param self: plan node a
+    @param other: plan node b
+    *
+    @return partial ordering for plan nodes a and b based on priority value
+    */
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::{ChargingNode, Edge, Graph};
+    use crate::graph::charging::{MAX_VIA, Planner, Trip};
+    use crate::graph::router::tests::graph;
+    use crate::osm::charging::{Socket, SocketType};
+    use crate::osm::Coordinates;
+    use crate::osm::highway::Kmh;
+    use crate::osm::options::{ChargingOptions, Planning};
+    use crate::osm::options::Routing::Time;
+    use crate::osm::options::Transport::{All, Car};
+    use crate::vehicle::Vehicle;
+
+    /**
+    Create graph of a road with 7 nodes and 10km between nodes.
+    *
+    @param stations: nodes with charging station and their power in kW
+    *
+    @return graph of road
+    */
+    fn road(stations: &[(usize, f32)]) -> Graph {
+        let coordinates = |i: usize| Coordinates::from(geo::Point::new(9.0 + i as f64 * 0.01, 48.0));
+        let mut edges = Vec::new();
+        for i in 1..7 {
+            edges.push(Edge::new(i - 1, i, All, 10_000, Kmh::new(100)));
+            edges.push(Edge::new(i, i - 1, All, 10_000, Kmh::new(100)));
+        }
+        let charging_nodes = stations.iter()
+            .map(|(i, power)| ChargingNode::new(100 + *i as i64, coordinates(*i), ChargingOptions::CarBike, *power))
+            .collect();
+        graph(&(0..7).map(coordinates).collect::<Vec<_>>(), edges, charging_nodes, Vec::new())
+    }
+
+    #[test]
+    fn fewest_stops() {
+        let graph = road(&[(2, 50.0), (3, 50.0), (4, 50.0)]);
+        let planner = Planner::new(&graph, Car, Time, Planning::Stops);
+        // 1750Wh per edge, vehicle is able to drive 2 edges
+        let vehicle = Vehicle::new(5.0, 100.0, 50.0, 100.0);
+        let waypoints = [graph.coordinates(0).clone(), graph.coordinates(6).clone()];
+
+        let plan = planner.plan(&waypoints, &vehicle).unwrap();
+        assert_eq!(2, plan.stops.len());
+        assert_eq!(0, plan.stops[0].coordinates.distance(graph.coordinates(2)));
+        assert_eq!(0, plan.stops[1].coordinates.distance(graph.coordinates(4)));
+        assert!((plan.stops[0].state_of_charge - 30.0).abs() < 0.1);
+        assert!((plan.state_of_charge - 30.0).abs() < 0.1);
+        assert_eq!(60_000, plan.route.distance);
+        assert_eq!(7, plan.route.path.len());
+        // route time includes charging time
+        let charging_time: u32 = plan.stops.iter().map(|stop| stop.charging_time).sum();
+        assert!(plan.stops[0].charging_time > 0);
+        assert_eq!(6 * Kmh::new(100).time(10_000) + charging_time, plan.route.time);
+    }
+
+    #[test]
+    fn fastest() {
+        // slow charging station at node 3 saves a stop
+        let graph = road(&[(2, 150.0), (3, 3.0), (4, 150.0)]);
+        let vehicle = Vehicle::new(6.0, 100.0, 50.0, 100.0);
+        let waypoints = [graph.coordinates(0).clone(), graph.coordinates(6).clone()];
+
+        let stops = Planner::new(&graph, Car, Time, Planning::Stops).plan(&waypoints, &vehicle).unwrap();
+        assert_eq!(1, stops.stops.len());
+        assert_eq!(0, stops.stops[0].coordinates.distance(graph.coordinates(3)));
+
+        let fastest = Planner::new(&graph, Car, Time, Planning::Time).plan(&waypoints, &vehicle).unwrap();
+        assert_eq!(2, fastest.stops.len());
+        assert!(fastest.route.time < stops.route.time);
+    }
+
+    #[test]
+    fn direct_route() {
+        let graph = road(&[(3, 50.0)]);
+        let planner = Planner::new(&graph, Car, Time, Planning::Time);
+        let vehicle = Vehicle::new(50.0, 50.0, 50.0, 80.0);
+        let waypoints = [graph.coordinates(0).clone(), graph.coordinates(6).clone()];
+        let plan = planner.plan(&waypoints, &vehicle).unwrap();
+        assert!(plan.stops.is_empty());
+        assert!((plan.state_of_charge - 29.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn infeasible() {
+        let graph = road(&[(2, 50.0), (3, 50.0), (4, 50.0)]);
+        let planner = Planner::new(&graph, Car, Time, Planning::Time);
+        // vehicle is not able to reach first charging station
+        let vehicle = Vehicle::new(3.0, 100.0, 50.0, 100.0);
+        let waypoints = [graph.coordinates(0).clone(), graph.coordinates(6).clone()];
+        let plan = planner.plan(&waypoints, &vehicle);
+        assert!(plan.is_err());
+    }
+
+    #[test]
+    fn zero_power() {
+        // charging stations without power are not used as charging stops
+        let graph = road(&[(2, 0.0), (3, 0.0), (4, 0.0)]);
+        let vehicle = Vehicle::new(5.0, 100.0, 50.0, 100.0);
+        let waypoints = [graph.coordinates(0).clone(), graph.coordinates(6).clone()];
+        assert!(Planner::new(&graph, Car, Time, Planning::Time).plan(&waypoints, &vehicle).is_err());
+        // vehicle without charging power is not able to charge either
+        let graph = road(&[(2, 50.0), (3, 50.0), (4, 50.0)]);
+        let vehicle = Vehicle::new(5.0, 100.0, 0.0, 100.0);
+        assert!(Planner::new(&graph, Car, Time, Planning::Time).plan(&waypoints, &vehicle).is_err());
+    }
+
+    #[test]
+    fn trip() {
+        let graph = road(&[(2, 50.0), (4, 50.0)]);
+        let planner = Planner::new(&graph, Car, Time, Planning::Stops);
+        let waypoints = [graph.coordinates(0).clone(), graph.coordinates(3).clone(), graph.coordinates(1).clone()];
+
+        // vehicle reaches goal without charging
+        let vehicle = Vehicle::new(50.0, 100.0, 50.0, 100.0);
+        let plan = planner.plan(&waypoints, &vehicle).unwrap();
+        assert!(plan.stops.is_empty());
+        let distances: Vec<u32> = plan.sections.iter().map(|section| section.distance).collect();
+        assert_eq!(vec![30_000, 20_000], distances);
+        assert_eq!(6, plan.route.path.len());
+        assert_eq!(&plan.route.path[0], graph.coordinates(1));
+        assert_eq!(plan.route.path.last().unwrap(), graph.coordinates(0));
+
+        // vehicle has to charge at node 2 on its way to the intermediate stop and back
+        let vehicle = Vehicle::new(5.0, 100.0, 50.0, 100.0);
+        let plan = planner.plan(&waypoints, &vehicle).unwrap();
+        assert_eq!(2, plan.stops.len());
+        assert!(plan.stops.iter().all(|stop| stop.coordinates == *graph.coordinates(2)));
+        let distances: Vec<u32> = plan.sections.iter().map(|section| section.distance).collect();
+        assert_eq!(vec![30_000, 20_000], distances);
+        assert_eq!(50_000, plan.route.distance);
+        assert_eq!(6, plan.route.path.len());
+        // time of sections includes time needed for charging on them
+        assert_eq!(3 * Kmh::new(100).time(10_000) + plan.stops[0].charging_time, plan.sections[0].time);
+        assert_eq!(2 * Kmh::new(100).time(10_000) + plan.stops[1].charging_time, plan.sections[1].time);
+        assert_eq!(plan.sections[0].time + plan.sections[1].time, plan.route.time);
+        assert!((plan.state_of_charge - 65.0).abs() < 0.1);
+
+        // intermediate stop is out of reach of charging stations
+        let waypoints = [graph.coordinates(0).clone(), graph.coordinates(6).clone(), graph.coordinates(0).clone()];
+        assert!(planner.plan(&waypoints, &vehicle).is_err());
+
+        // number of intermediate stops is limited
+        let vehicle = Vehicle::new(50.0, 100.0, 50.0, 100.0);
+        let mut waypoints = vec![graph.coordinates(0).clone(); MAX_VIA + 2];
+        assert!(planner.plan(&waypoints, &vehicle).is_ok());
+        waypoints.push(graph.coordinates(0).clone());
+        assert!(planner.plan(&waypoints, &vehicle).is_err());
+    }
+
+    #[test]
+    fn dominance() {
+        // motorway from start to node 1 and a road around it, which takes longer but needs less energy
+        let coordinates: Vec<Coordinates> = [(9.0, 48.0), (9.1, 48.0), (9.5, 48.0), (9.05, 48.05)].iter()
+            .map(|(lon, lat)| Coordinates::from(geo::Point::new(*lon, *lat)))
+            .collect();
+        let mut edges = Vec::new();
+        for &(a, b, transport, distance, speed) in &[(0, 1, Car, 20_000, 130), (0, 3, All, 10_000, 50),
+            (3, 1, All, 10_000, 50), (1, 2, All, 50_000, 50)] {
+            edges.push(Edge::new(a, b, transport, distance, Kmh::new(speed)));
+            edges.push(Edge::new(b, a, transport, distance, Kmh::new(speed)));
+        }
+        let station = ChargingNode::new(100, coordinates[1].clone(), ChargingOptions::CarBike, 50.0);
+        let graph = graph(&coordinates, edges, vec![station], Vec::new());
+
+        // vehicle arriving at charging station by motorway is not able to continue to goal
+        let vehicle = Vehicle::new(10.0, 100.0, 50.0, 50.0);
+        let waypoints = [graph.coordinates(0).clone(), graph.coordinates(2).clone()];
+        let plan = Planner::new(&graph, Car, Time, Planning::Time).plan(&waypoints, &vehicle).unwrap();
+        assert_eq!(70_000, plan.route.distance);
+        assert_eq!(2 * Kmh::new(50).time(10_000) + Kmh::new(50).time(50_000), plan.route.time);
+
+        // vehicle without charging power arriving at intermediate stop by motorway is not able to
+        // continue to goal either
+        let vehicle = Vehicle::new(10.0, 100.0, 0.0, 100.0);
+        let waypoints = [graph.coordinates(0).clone(), graph.coordinates(1).clone(), graph.coordinates(2).clone()];
+        let plan = Planner::new(&graph, Car, Time, Planning::Time).plan(&waypoints, &vehicle).unwrap();
+        assert!(plan.stops.is_empty());
+        let distances: Vec<u32> = plan.sections.iter().map(|section| section.distance).collect();
+        assert_eq!(vec![20_000, 50_000], distances);
+        assert_eq!(2 * Kmh::new(50).time(10_000) + Kmh::new(50).time(50_000), plan.route.time);
+        assert_eq!(4, plan.route.path.len());
+        assert!(plan.state_of_charge > 0.0);
+    }
+
+    #[test]
+    fn bounded_legs() {
+        let graph = road(&[(2, 50.0), (3, 50.0), (4, 50.0)]);
+        let planner = Planner::new(&graph, Car, Time, Planning::Time);
+        let vehicle = Vehicle::new(50.0, 100.0, 50.0, 100.0);
+        let stations = planner.stations(&vehicle);
+        let snaps = [graph.snap(graph.coordinates(0), Car).unwrap(), graph.snap(graph.coordinates(6), Car).unwrap()];
+        let trip = Trip::new(&graph, &snaps);
+
+        // legs to charging stations and both nodes of the edge goal is projected onto
+        let (_, legs) = planner.reachable(0, 0, f32::MAX, None, &stations, &trip);
+        assert_eq!(5, legs.len());
+        // legs to charging stations which cannot lead to a plan within bound are pruned
+        let (_, legs) = planner.reachable(0, 0, f32::MAX, Some(Kmh::new(100).millis(30_000)), &stations, &trip);
+        let indices: Vec<usize> = legs.iter().map(|leg| leg.index).collect();
+        assert_eq!(vec![2], indices);
+    }
+
+    #[test]
+    fn behind_start() {
+        let graph = road(&[(2, 50.0), (5, 50.0)]);
+        let planner = Planner::new(&graph, Car, Time, Planning::Time);
+        let vehicle = Vehicle::new(5.0, 100.0, 50.0, 100.0);
+        // start is nearer to node 4, but goal lies behind it in the direction of node 3
+        let start = Coordinates::from(geo::Point::new(9.036, 48.0));
+        let waypoints = [start.clone(), graph.coordinates(0).clone()];
+
+        let plan = planner.plan(&waypoints, &vehicle).unwrap();
+        assert_eq!(1, plan.stops.len());
+        assert_eq!(0, plan.stops[0].coordinates.distance(graph.coordinates(2)));
+        assert_eq!(36_000, plan.route.distance);
+        let path = &plan.route.path;
+        assert_eq!(0, path[path.len() - 1].distance(&start));
+        assert_eq!(path[path.len() - 2], *graph.coordinates(3));
+
+        // intermediate stop behind start on its edge is left and entered in the same direction
+        let behind = Coordinates::from(geo::Point::new(9.034, 48.0));
+        let waypoints = [start.clone(), behind, graph.coordinates(0).clone()];
+        let plan = planner.plan(&waypoints, &vehicle).unwrap();
+        assert_eq!(36_000, plan.route.distance);
+        let distances: Vec<u32> = plan.sections.iter().map(|section| section.distance).collect();
+        assert_eq!(vec![2_000, 34_000], distances);
+    }
+
+    #[test]
+    fn most_powerful_station() {
+        // fast charging station next to a slow one at node 2
+        let graph = road(&[(2, 22.0), (2, 150.0), (4, 22.0)]);
+        let planner = Planner::new(&graph, Car, Time, Planning::Stops);
+        let vehicle = Vehicle::new(5.0, 100.0, 150.0, 100.0);
+        let stations = planner.stations(&vehicle);
+        assert_eq!((1, 150.0), stations[&2]);
+        assert_eq!((2, 22.0), stations[&4]);
+
+        let waypoints = [graph.coordinates(0).clone(), graph.coordinates(6).clone()];
+        let plan = planner.plan(&waypoints, &vehicle).unwrap();
+        let mut slow = vehicle.clone();
+        slow.charge = plan.stops[0].state_of_charge / 100.0 * slow.capacity;
+        assert!(plan.stops[0].charging_time < slow.charge(22.0));
+    }
+
+    #[test]
+    fn compatible_sockets() {
+        let mut graph = road(&[(2, 50.0), (4, 50.0)]);
+        graph.charging_nodes[0].sockets = vec![Socket { kind: SocketType::Chademo, count: 1, output: None }];
+        let planner = Planner::new(&graph, Car, Time, Planning::Stops);
+        let mut vehicle = Vehicle::new(5.0, 100.0, 50.0, 100.0);
+        let waypoints = [graph.coordinates(0).clone(), graph.coordinates(6).clone()];
+        assert!(planner.plan(&waypoints, &vehicle).is_ok());
+        // vehicle is not able to charge at first charging station
+        vehicle.sockets = vec![SocketType::Type2];
+        assert!(planner.plan(&waypoints, &vehicle).is_err());
+    }
+}
@@ -0,0 +1,300 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
+use std::mem;
+use std::ops::Deref;
+use std::os::unix::io::AsRawFd;
+use std::ptr;
+use std::slice;
+use std::sync::Arc;
+
+use crate::graph::{Edge, Node};
+use crate::graph::format::FormatError;
+use crate::osm::Coordinates;
+use crate::osm::options::Transport;
+
+// alignment of tables within flat files
+const ALIGNMENT: usize = 8;
+// size of buffer when writing tables
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/**
+Read-only memory map of a file, which is shared with all processes mapping the same file.
+*/
+pub struct Mmap {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+// memory map is read-only, so it may be shared between threads
+unsafe impl Send for Mmap {}
+
+unsafe impl Sync for Mmap {}
+
+impl Mmap {
+    /**
+    Map file into memory.
+    *
+    @param file: file to be mapped
+    *
+    @return Result<Mmap, FormatError>: memory map of file
+    */
+    pub fn new(file: &File) -> Result<Self, FormatError> {
+        let len = file.metadata()?.len() as usize;
+        if len == 0 {
+            return Err(FormatError::Corrupt("file is empty"));
+        }
+        let ptr = unsafe {
+            libc::mmap(ptr::null_mut(), len, libc::PROT_READ, libc::MAP_SHARED, file.as_raw_fd(), 0)
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error().into());
+        }
+        Ok(Self { ptr, len })
+    }
+
+    /**
+    Get length of memory map.
+    *
+    @param self: memory map
+    *
+    @return length in bytes
+    */
+    pub fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr, self.len);
+        }
+    }
+}
+
+/**
+Table of graph records, either owned after parsing a pbf file or mapped from a flat file.
+*/
+pub enum Table<T> {
+    Owned(Vec<T>),
+    Mapped {
+        map: Arc<Mmap>,
+        offset: usize,
+        len: usize,
+    },
+}
+
+impl<T: Flat> Table<T> {
+    /**
+    Create table mapped from flat file.
+    *
+    @param map: memory map of flat file
+    @param offset: offset of table in bytes, is moved behind the table
+    @param len: number of records of table
+    *
+    @return Result<Table, FormatError>: mapped table, error if table exceeds file or has invalid records
+    */
+    pub fn mapped(map: &Arc<Mmap>, offset: &mut usize, len: usize) -> Result<Self, FormatError> {
+        let start = align(*offset);
+        let end = len.checked_mul(mem::size_of::<T>())
+            .and_then(|size| size.checked_add(start))
+            .filter(|end| *end <= map.len())
+            .ok_or(FormatError::Corrupt("table exceeds file"))?;
+        // records are checked as bytes, invalid records must never be read as values
+        let bytes = unsafe { slice::from_raw_parts((map.ptr as *const u8).add(start), end - start) };
+        if !bytes.chunks_exact(mem::size_of::<T>()).all(T::valid) {
+            return Err(FormatError::Corrupt("table contains invalid records"));
+        }
+        *offset = end;
+        Ok(Table::Mapped { map: map.clone(), offset: start, len })
+    }
+
+    /**
+    Write records of table in their flat layout.
+    *
+    @param self: table
+    @param writer: writer of flat file
+    @param position: position of writer in flat file, table is aligned within file
+    *
+    @return Result<(), FormatError>: error if table could not be written
+    */
+    pub fn write<W: Write>(&self, writer: &mut W, position: &mut usize) -> Result<(), FormatError> {
+        let mut buffer = vec![0; align(*position) - *position];
+        for record in self.iter() {
+            record.write(&mut buffer);
+            if buffer.len() >= BUFFER_SIZE {
+                writer.write_all(&buffer)?;
+                *position += buffer.len();
+                buffer.clear();
+            }
+        }
+        writer.write_all(&buffer)?;
+        *position += buffer.len();
+        Ok(())
+    }
+}
+
+impl<T> Deref for Table<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            Table::Owned(vec) => vec,
+            // mapped tables are aligned and checked to be within the memory map when created
+            Table::Mapped { map, offset, len } => unsafe {
+                slice::from_raw_parts((map.ptr as *const u8).add(*offset) as *const T, *len)
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for Table<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Table::Owned(vec) => write!(f, "Owned({} records)", vec.len()),
+            Table::Mapped { len, .. } => write!(f, "Mapped({} records)", len),
+        }
+    }
+}
+
+/**
+Records which are stored in flat files in the same layout as in memory.
+*
+# Safety
+Implementations must be `#[repr(C)]` and valid for every bit pattern accepted by `valid`.
+*/
+pub unsafe trait Flat: Sized {
+    /**
+    Check if bytes of flat file are a valid record. Records without invalid bit patterns accept all bytes.
+    *
+    @param bytes: bytes of record
+    *
+    @return true if bytes are a valid record
+    */
+    fn valid(_bytes: &[u8]) -> bool {
+        true
+    }
+
+    /**
+    Write record in its in-memory layout. Records without padding bytes are copied.
+    *
+    @param self: record
+    @param out: bytes of flat file
+    */
+    fn write(&self, out: &mut Vec<u8>) {
+        let bytes = unsafe {
+            slice::from_raw_parts(self as *const Self as *const u8, mem::size_of::<Self>())
+        };
+        out.extend_from_slice(bytes);
+    }
+}
+
+unsafe impl Flat for usize {}
+
+unsafe impl Flat for u32 {}
+
+unsafe impl Flat for Node {}
+
+unsafe impl Flat for Coordinates {}
+
+unsafe impl Flat for Edge {
+    fn valid(bytes: &[u8]) -> bool {
+        // transport must be a variant of its enum and destination a bool
+        bytes[mem::offset_of!(Edge, transport)] <= Transport::CarBike as u8
+            && bytes[mem::offset_of!(Edge, destination)] <= 1
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        // transport and destination are followed by padding bytes, which must not be copied
+        out.extend_from_slice(&self.source_index.to_ne_bytes());
+        out.extend_from_slice(&self.target_index.to_ne_bytes());
+        out.extend_from_slice(&[self.transport as u8, self.destination as u8, 0, 0]);
+        out.extend_from_slice(&self.distance.to_ne_bytes());
+        out.extend_from_slice(&self.max_speed.speed.to_ne_bytes());
+        out.extend_from_slice(&[0; 4]);
+    }
+}
+
+/**
+Check if flat files are supported by this platform, i.e. records have the expected layout.
+*
+@return Result<(), FormatError>: error if flat files are not supported
+*/
+pub fn supported() -> Result<(), FormatError> {
+    if cfg!(target_endian = "little") && mem::size_of::<usize>() == 8
+        && mem::size_of::<Node>() == 16 && mem::size_of::<Edge>() == 32 {
+        Ok(())
+    } else {
+        Err(FormatError::Layout)
+    }
+}
+
+/**
+Align offset within flat file.
+*
+@param offset: offset in bytes
+*
+@return next aligned offset
+*/
+pub fn align(offset: usize) -> usize {
+    (offset + ALIGNMENT - 1) & !(ALIGNMENT - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::sync::Arc;
+
+    use crate::graph::{Edge, Node};
+    use crate::graph::flat::{self, align, Mmap, Table};
+    use crate::graph::format::FormatError;
+    use crate::osm::Coordinates;
+    use crate::osm::highway::Kmh;
+    use crate::osm::options::Transport::{Bike, Car};
+
+    #[test]
+    fn mapped_tables() {
+        flat::supported().unwrap();
+        let nodes = Table::Owned(vec![
+            Node::new(1, Coordinates::new(480000000, 90000000)),
+            Node::new(2, Coordinates::new(-480000000, -90000000)),
+        ]);
+        let mut destination = Edge::new(1, 0, Bike, 200, Kmh::new(30));
+        destination.destination = true;
+        let edges = Table::Owned(vec![Edge::new(0, 1, Car, 100, Kmh::new(50)), destination]);
+        // first table starts unaligned
+        let mut bytes = vec![0; 3];
+        let mut position = 3;
+        nodes.write(&mut bytes, &mut position).unwrap();
+        edges.write(&mut bytes, &mut position).unwrap();
+        assert_eq!(bytes.len(), position);
+
+        let filename = std::env::temp_dir().join("emaps-flat-test.bin");
+        fs::write(&filename, &bytes).unwrap();
+        let map = Arc::new(Mmap::new(&File::open(&filename).unwrap()).unwrap());
+        let mut offset = 3;
+        let mapped_nodes: Table<Node> = Table::mapped(&map, &mut offset, 2).unwrap();
+        let mapped_edges: Table<Edge> = Table::mapped(&map, &mut offset, 2).unwrap();
+        assert_eq!(bytes.len(), offset);
+        assert!(Table::<Edge>::mapped(&map, &mut offset, 1).is_err());
+
+        assert_eq!(2, mapped_nodes[1].id);
+        assert_eq!(0, mapped_nodes[1].coordinates.distance(&Coordinates::new(-480000000, -90000000)));
+        assert_eq!(&edges[..], &mapped_edges[..]);
+
+        // edges with invalid transport or destination bytes are rejected
+        for byte in [16, 17] {
+            let mut corrupt = bytes.clone();
+            corrupt[align(3 + 2 * 16) + byte] = 7;
+            fs::write(&filename, &corrupt).unwrap();
+            let map = Arc::new(Mmap::new(&File::open(&filename).unwrap()).unwrap());
+            let mut offset = align(3 + 2 * 16);
+            match Table::<Edge>::mapped(&map, &mut offset, 2) {
+                Err(FormatError::Corrupt(_)) => {}
+                other => panic!("expected corrupt error, got {:?}", other)
+            }
+        }
+        fs::remove_file(&filename).unwrap();
+    }
+}
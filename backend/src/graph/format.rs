@@ -0,0 +1,332 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+use crate::osm::highway::{Highway, Kmh};
+use crate::osm::options::Transport;
+
+// magic bytes at the beginning of each binary graph file
+const MAGIC: [u8; 8] = *b"EMAPSBIN";
+// version of binary graph format, increment on every change of serialized structs
+pub const VERSION: u32 = 19;
+
+// offset basis and prime of 64 bit FNV-1a hash
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+// size of blocks of files which are hashed at once
+const BLOCK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug)]
+pub enum FormatError {
+    // file could not be read or written
+    Io(String),
+    // file is no binary graph file
+    Magic,
+    // file was written by another version of the binary graph format
+    Version(u32),
+    // file was created from another pbf file
+    Checksum,
+    // file was created with other speeds or transportation modes of highways
+    Parameters,
+    // file could not be serialized or deserialized
+    Encoding(String),
+    // content of file is inconsistent, e.g. records of tables are invalid
+    Corrupt(&'static str),
+    // layout of graph files is not supported on this platform
+    Layout,
+}
+
+impl fmt::Display for FormatError {
+    /**
+    Format error message of format error.
+    *
+    @param self: format error
+    @param f: formatter
+    *
+    @return result of formatting
+    */
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormatError::Io(error) => write!(f, "could not access file: {}", error),
+            FormatError::Magic => write!(f, "file is no graph file"),
+            FormatError::Version(version) =>
+                write!(f, "graph file has version {}, expected {}", version, VERSION),
+            FormatError::Checksum => write!(f, "graph file was created from another pbf file"),
+            FormatError::Parameters => write!(f, "graph file was created with other build parameters"),
+            FormatError::Encoding(error) => write!(f, "graph file could not be decoded: {}", error),
+            FormatError::Corrupt(error) => write!(f, "graph file is corrupt: {}", error),
+            FormatError::Layout => write!(f, "graph files are not supported on this platform"),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl From<std::io::Error> for FormatError {
+    fn from(error: std::io::Error) -> Self {
+        FormatError::Io(error.to_string())
+    }
+}
+
+impl From<bincode::Error> for FormatError {
+    fn from(error: bincode::Error) -> Self {
+        FormatError::Encoding(error.to_string())
+    }
+}
+
+/**
+Parameters the graph was built with. A graph file built with other parameters is stale.
+*/
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Parameters {
+    // default speed and transportation modes of each highway type
+    profiles: Vec<(Highway, Option<Kmh>, Option<Transport>)>,
+    // requested number of landmarks, fewer may be selected if the graph is small
+    landmarks: usize,
+}
+
+impl Parameters {
+    /**
+    Get parameters of the current build.
+    *
+    @param landmarks: requested number of landmarks
+    *
+    @return build parameters
+    */
+    pub fn current(landmarks: usize) -> Self {
+        let profiles = Highway::iter()
+            .map(|highway| (highway, highway.default_speed(), Transport::from(highway)))
+            .collect();
+        Self { profiles, landmarks }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Header {
+    magic: [u8; 8],
+    version: u32,
+    // key and checksum of pbf file the graph was created from
+    key: u64,
+    checksum: u64,
+    parameters: Parameters,
+}
+
+impl Header {
+    /**
+    Create header of current build for graph created from pbf file.
+    *
+    @param key: key of pbf file
+    @param checksum: checksum of pbf file
+    @param landmarks: requested number of landmarks
+    *
+    @return new header
+    */
+    pub fn new(key: u64, checksum: u64, landmarks: usize) -> Self {
+        Self {
+            magic: MAGIC,
+            version: VERSION,
+            key,
+            checksum,
+            parameters: Parameters::current(landmarks),
+        }
+    }
+
+    /**
+    Create header of current build for graph created from pbf file, which is hashed completely.
+    *
+    @param pbf: name of pbf file
+    @param landmarks: requested number of landmarks
+    *
+    @return Result<Header, FormatError>: new header, error if pbf file could not be read
+    */
+    pub fn of(pbf: &str, landmarks: usize) -> Result<Self, FormatError> {
+        Ok(Self::new(key(pbf)?, checksum(pbf)?, landmarks))
+    }
+
+    /**
+    Verify that header belongs to a graph file which can be used by the current build.
+    *
+    @param self: header read from file
+    @param pbf: (optional) name of pbf file the graph must be created from
+    @param landmarks: requested number of landmarks
+    *
+    @return Result<(), FormatError>: error if the graph file is stale or mismatched
+    */
+    pub fn verify(&self, pbf: Option<&str>, landmarks: usize) -> Result<(), FormatError> {
+        if self.magic != MAGIC {
+            return Err(FormatError::Magic);
+        }
+        if self.version != VERSION {
+            return Err(FormatError::Version(self.version));
+        }
+        if self.parameters != Parameters::current(landmarks) {
+            return Err(FormatError::Parameters);
+        }
+        if let Some(pbf) = pbf {
+            // pbf file is only hashed completely if its key changed, e.g. after it was copied
+            if key(pbf)? != self.key && checksum(pbf)? != self.checksum {
+                return Err(FormatError::Checksum);
+            }
+        }
+        Ok(())
+    }
+}
+
+/**
+Write header to binary file.
+*
+@param writer: writer of binary file
+@param header: header of file
+*
+@return Result<(), FormatError>: error if header could not be written
+*/
+pub fn write_header<W: Write>(writer: &mut W, header: &Header) -> Result<(), FormatError> {
+    bincode::serialize_into(writer, header)?;
+    Ok(())
+}
+
+/**
+Read and verify header of binary file.
+*
+@param reader: reader of binary file
+@param pbf: (optional) name of pbf file the graph must be created from
+@param landmarks: requested number of landmarks
+*
+@return Result<(), FormatError>: error if file is stale or mismatched
+*/
+pub fn read_header<R: Read>(reader: &mut R, pbf: Option<&str>, landmarks: usize) -> Result<(), FormatError> {
+    // magic bytes are checked first, files of other formats must not be deserialized further
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Err(FormatError::Magic);
+    }
+    let mut version = [0; 4];
+    reader.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != VERSION {
+        return Err(FormatError::Version(version));
+    }
+    let (key, checksum, parameters) = bincode::deserialize_from(reader)?;
+    Header { magic, version, key, checksum, parameters }.verify(pbf, landmarks)
+}
+
+/**
+Calculate key of file from its size, modification time and first block. Unlike the checksum it is
+cheap to calculate for large files, but changes whenever the file is written or copied.
+*
+@param filename: name of file
+*
+@return Result<u64, FormatError>: key of file
+*/
+pub fn key(filename: &str) -> Result<u64, FormatError> {
+    let file = File::open(filename)?;
+    let metadata = file.metadata()?;
+    let modified = metadata.modified()?.duration_since(UNIX_EPOCH)
+        .map_err(|error| FormatError::Io(error.to_string()))?;
+    let mut key = hash(FNV_OFFSET, &metadata.len().to_le_bytes());
+    key = hash(key, &modified.as_secs().to_le_bytes());
+    key = hash(key, &modified.subsec_nanos().to_le_bytes());
+    let mut block = Vec::with_capacity(BLOCK_SIZE);
+    file.take(BLOCK_SIZE as u64).read_to_end(&mut block)?;
+    Ok(hash(key, &block))
+}
+
+/**
+Calculate checksum of file.
+*
+@param filename: name of file
+*
+@return Result<u64, FormatError>: checksum of file
+*/
+pub fn checksum(filename: &str) -> Result<u64, FormatError> {
+    let mut reader = BufReader::new(File::open(filename)?);
+    let mut buffer = [0; BLOCK_SIZE];
+    let mut hash = FNV_OFFSET;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hash = self::hash(hash, &buffer[..read]);
+    }
+    Ok(hash)
+}
+
+/**
+Continue FNV-1a hash with bytes.
+*
+@param hash: hash of previous bytes
+@param bytes: bytes to be hashed
+*
+@return hash of all bytes
+*/
+fn hash(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::format::{self, FormatError, Header, VERSION};
+    use crate::graph::landmarks::LANDMARKS;
+
+    #[test]
+    fn header() {
+        let read = |bytes: &[u8], pbf| format::read_header(&mut &bytes[..], pbf, LANDMARKS);
+        let mut bytes = Vec::new();
+        format::write_header(&mut bytes, &Header::of("Cargo.toml", LANDMARKS).unwrap()).unwrap();
+        read(&bytes, Some("Cargo.toml")).unwrap();
+        read(&bytes, None).unwrap();
+
+        // graph with another number of landmarks
+        match format::read_header(&mut &bytes[..], None, LANDMARKS + 1) {
+            Err(FormatError::Parameters) => {}
+            other => panic!("expected parameters error, got {:?}", other)
+        }
+
+        // graph of other pbf file
+        match read(&bytes, Some("Cargo.lock")) {
+            Err(FormatError::Checksum) => {}
+            other => panic!("expected checksum error, got {:?}", other)
+        }
+
+        // pbf file with same content but other key, e.g. a copy, is hashed completely
+        let checksum = format::checksum("Cargo.toml").unwrap();
+        let mut bytes = Vec::new();
+        format::write_header(&mut bytes, &Header::new(0, checksum, LANDMARKS)).unwrap();
+        read(&bytes, Some("Cargo.toml")).unwrap();
+        // pbf file with same key is not hashed
+        let mut bytes = Vec::new();
+        format::write_header(&mut bytes, &Header::new(format::key("Cargo.toml").unwrap(), 0, LANDMARKS)).unwrap();
+        read(&bytes, Some("Cargo.toml")).unwrap();
+
+        // graph of older version
+        let mut header = Header::new(42, 42, LANDMARKS);
+        header.version = VERSION - 1;
+        let mut bytes = Vec::new();
+        format::write_header(&mut bytes, &header).unwrap();
+        match read(&bytes, None) {
+            Err(FormatError::Version(version)) => assert_eq!(VERSION - 1, version),
+            other => panic!("expected version error, got {:?}", other)
+        }
+
+        // raw bincode without header
+        match read(&bincode::serialize(&vec![1u32]).unwrap(), None) {
+            Err(FormatError::Magic) => {}
+            other => panic!("expected magic error, got {:?}", other)
+        }
+
+        assert_eq!(format::checksum("Cargo.toml").unwrap(), format::checksum("Cargo.toml").unwrap());
+        assert_ne!(format::checksum("Cargo.toml").unwrap(), format::checksum("Cargo.lock").unwrap());
+        assert_eq!(format::key("Cargo.toml").unwrap(), format::key("Cargo.toml").unwrap());
+        assert_ne!(format::key("Cargo.toml").unwrap(), format::key("Cargo.lock").unwrap());
+    }
+}
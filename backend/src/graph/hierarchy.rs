@@ -0,0 +1,491 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use log::debug;
+
+use crate::graph::Graph;
+use crate::graph::flat::{Flat, Table};
+use crate::osm::options::{Routing, Transport};
+
+// transportation mode and routing mode the hierarchy is built for
+const MODE: Transport = Transport::Car;
+const ROUTING: Routing = Routing::Time;
+// max. number of settled nodes of a witness search
+const WITNESS_LIMIT: usize = 500;
+// marks links which are original edges of graph
+const ORIGINAL: usize = usize::MAX;
+
+/**
+Edge of hierarchy, which is either an original edge of graph or a shortcut of two links.
+*/
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct Link {
+    pub source: usize,
+    pub target: usize,
+    // position of original edge in graph, or first link of shortcut
+    first: usize,
+    // second link of shortcut, or ORIGINAL
+    second: usize,
+    pub weight: u32,
+    // explicit padding, records of flat files must not contain uninitialized bytes
+    padding: u32,
+}
+
+unsafe impl Flat for Link {}
+
+/**
+Contraction hierarchy of graph for car routing by time. Nodes are contracted in order of their
+level, shortcuts replace paths via contracted nodes, so a query only follows links to nodes
+of higher levels.
+*/
+#[derive(Debug)]
+pub struct Hierarchy {
+    // level of each node, i.e. position in contraction order
+    pub levels: Table<usize>,
+    pub links: Table<Link>,
+    // links to nodes of higher level, grouped by source
+    pub up_offsets: Table<usize>,
+    pub up: Table<usize>,
+    // links from nodes of higher level, grouped by target
+    pub down_offsets: Table<usize>,
+    pub down: Table<usize>,
+}
+
+impl Hierarchy {
+    /**
+    Create empty hierarchy, which does not support any query.
+    *
+    @return empty hierarchy
+    */
+    pub fn empty() -> Self {
+        Self {
+            levels: Table::Owned(Vec::new()),
+            links: Table::Owned(Vec::new()),
+            up_offsets: Table::Owned(Vec::new()),
+            up: Table::Owned(Vec::new()),
+            down_offsets: Table::Owned(Vec::new()),
+            down: Table::Owned(Vec::new()),
+        }
+    }
+
+    /**
+    Check if hierarchy supports queries of transportation mode and routing mode.
+    *
+    @param self: hierarchy
+    @param mode: transportation mode (car/bike)
+    @param routing: routing mode (distance/time)
+    *
+    @return true if hierarchy was built for transportation mode and routing mode
+    */
+    pub fn supports(&self, mode: Transport, routing: Routing) -> bool {
+        !self.up_offsets.is_empty() && mode == MODE && routing == ROUTING
+    }
+
+    /**
+    Shortest path query on hierarchy. Searches upwards from sources and from goals until the
+    smallest cost of both searches exceeds the best path found.
+    *
+    @param self: hierarchy
+    @param sources: indices of start nodes with initial cost
+    @param goals: indices of goal nodes with final cost
+    *
+    @return (optional) positions of edges of shortest path in graph, index of its start and goal node
+    */
+    pub fn query(&self, sources: &[(usize, u32)], goals: &[(usize, u32)]) -> Option<(Vec<usize>, usize, usize)> {
+        // cost and link to reach nodes in forward and backward search
+        let mut forward: HashMap<usize, (u32, usize)> = HashMap::new();
+        let mut backward: HashMap<usize, (u32, usize)> = HashMap::new();
+        let mut forward_queue = BinaryHeap::new();
+        let mut backward_queue = BinaryHeap::new();
+        for (labels, queue, seeds) in [(&mut forward, &mut forward_queue, sources), (&mut backward, &mut backward_queue, goals)] {
+            for (index, cost) in seeds {
+                if *cost < labels.get(index).map_or(u32::MAX, |label| label.0) {
+                    labels.insert(*index, (*cost, ORIGINAL));
+                    queue.push(Reverse((*cost, *index)));
+                }
+            }
+        }
+        // node both searches met at and cost of best path found so far
+        let mut best: Option<(usize, u32)> = None;
+
+        loop {
+            let forward_min = forward_queue.peek().map_or(u32::MAX, |Reverse((cost, _))| *cost);
+            let backward_min = backward_queue.peek().map_or(u32::MAX, |Reverse((cost, _))| *cost);
+            let min = forward_min.min(backward_min);
+            if min >= best.map_or(u32::MAX, |(_, cost)| cost) {
+                break;
+            }
+            let is_forward = forward_min <= backward_min;
+            let (labels, other, queue, offsets, links) = if is_forward {
+                (&mut forward, &backward, &mut forward_queue, &self.up_offsets, &self.up)
+            } else {
+                (&mut backward, &forward, &mut backward_queue, &self.down_offsets, &self.down)
+            };
+            let Reverse((cost, index)) = queue.pop().unwrap();
+            if cost > labels[&index].0 {
+                continue;
+            }
+            if let Some((other_cost, _)) = other.get(&index) {
+                if cost + other_cost < best.map_or(u32::MAX, |(_, best_cost)| best_cost) {
+                    best = Some((index, cost + other_cost));
+                }
+            }
+            for id in &links[offsets[index]..offsets[index + 1]] {
+                let link = &self.links[*id];
+                let next = if is_forward { link.target } else { link.source };
+                let next_cost = cost + link.weight;
+                if next_cost < labels.get(&next).map_or(u32::MAX, |label| label.0) {
+                    labels.insert(next, (next_cost, *id));
+                    queue.push(Reverse((next_cost, next)));
+                }
+            }
+        }
+
+        let (meeting, _) = best?;
+        // links from start to meeting node, then from meeting node to goal
+        let mut path = Vec::new();
+        let mut start = meeting;
+        while let Some((_, id)) = forward.get(&start).filter(|label| label.1 != ORIGINAL) {
+            path.push(*id);
+            start = self.links[*id].source;
+        }
+        path.reverse();
+        let mut goal = meeting;
+        while let Some((_, id)) = backward.get(&goal).filter(|label| label.1 != ORIGINAL) {
+            path.push(*id);
+            goal = self.links[*id].target;
+        }
+        let mut positions = Vec::new();
+        for id in path {
+            self.unpack(id, &mut positions);
+        }
+        Some((positions, start, goal))
+    }
+
+    /**
+    Unpack link to original edges of graph.
+    *
+    @param self: hierarchy
+    @param id: id of link
+    @param positions: positions of original edges, edges of link are appended in order
+    */
+    fn unpack(&self, id: usize, positions: &mut Vec<usize>) {
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let link = &self.links[id];
+            if link.second == ORIGINAL {
+                positions.push(link.first);
+            } else {
+                stack.push(link.second);
+                stack.push(link.first);
+            }
+        }
+    }
+}
+
+/**
+Create contraction hierarchy of graph. Nodes are contracted lazily in order of their edge
+difference and number of contracted neighbors.
+*
+@param graph: graph with edges for car routing by time
+*
+@return hierarchy of graph
+*/
+pub fn contract(graph: &Graph) -> Hierarchy {
+    let mut contraction = Contraction::new(graph);
+    let number_nodes = graph.nodes.len();
+    let original_links = contraction.links.len();
+
+    let mut queue: BinaryHeap<Reverse<(i64, usize)>> = (0..number_nodes)
+        .map(|node| Reverse((contraction.priority(node), node)))
+        .collect();
+    let mut level = 0;
+    while let Some(Reverse((_, node))) = queue.pop() {
+        // priority may have changed since contraction of neighbors
+        let shortcuts = contraction.shortcuts(node);
+        let priority = contraction.priority_with(node, shortcuts.len());
+        if priority > queue.peek().map_or(i64::MAX, |Reverse((next, _))| *next) {
+            queue.push(Reverse((priority, node)));
+            continue;
+        }
+        contraction.contract(node, shortcuts, level);
+        level += 1;
+    }
+    debug!("Added {} shortcuts", contraction.links.len() - original_links);
+    contraction.hierarchy(number_nodes)
+}
+
+struct Contraction {
+    links: Vec<Link>,
+    // links leaving and entering nodes, links of contracted nodes are skipped
+    outgoing: Vec<Vec<usize>>,
+    incoming: Vec<Vec<usize>>,
+    // level of contracted nodes
+    levels: Vec<Option<usize>>,
+    contracted_neighbors: Vec<i64>,
+    // costs of witness search, reset after each search
+    costs: Vec<u32>,
+    touched: Vec<usize>,
+}
+
+impl Contraction {
+    /**
+    Create contraction with original edges of graph.
+    *
+    @param graph: graph to be contracted
+    *
+    @return contraction before any node is contracted
+    */
+    fn new(graph: &Graph) -> Self {
+        let number_nodes = graph.nodes.len();
+        let mut contraction = Self {
+            links: Vec::new(),
+            outgoing: vec![Vec::new(); number_nodes],
+            incoming: vec![Vec::new(); number_nodes],
+            levels: vec![None; number_nodes],
+            contracted_neighbors: vec![0; number_nodes],
+            costs: vec![u32::MAX; number_nodes],
+            touched: Vec::new(),
+        };
+        // only the fastest of parallel edges is needed
+        let mut fastest: HashMap<(usize, usize), usize> = HashMap::new();
+        for (position, edge) in graph.edges.iter().enumerate() {
+            if !edge.transport.contains(MODE) || edge.source_index == edge.target_index {
+                continue;
+            }
+            let weight = edge.cost(MODE, ROUTING);
+            let key = (edge.source_index, edge.target_index);
+            match fastest.get(&key) {
+                Some(id) if contraction.links[*id].weight <= weight => {}
+                Some(id) => {
+                    contraction.links[*id].first = position;
+                    contraction.links[*id].weight = weight;
+                }
+                None => {
+                    fastest.insert(key, contraction.links.len());
+                    contraction.add(edge.source_index, edge.target_index, position, ORIGINAL, weight);
+                }
+            }
+        }
+        contraction
+    }
+
+    /**
+    Add link to contraction.
+    *
+    @param self: contraction
+    @param source: index of source node
+    @param target: index of target node
+    @param first: position of original edge or first link of shortcut
+    @param second: second link of shortcut or ORIGINAL
+    @param weight: weight of link
+    */
+    fn add(&mut self, source: usize, target: usize, first: usize, second: usize, weight: u32) {
+        let id = self.links.len();
+        self.links.push(Link { source, target, first, second, weight, padding: 0 });
+        self.outgoing[source].push(id);
+        self.incoming[target].push(id);
+    }
+
+    /**
+    Get shortcuts needed to contract node, i.e. pairs of links via node without witness path.
+    *
+    @param self: contraction
+    @param node: index of node to be contracted
+    *
+    @return list of incoming link, outgoing link and weight of shortcut
+    */
+    fn shortcuts(&mut self, node: usize) -> Vec<(usize, usize, u32)> {
+        let incoming = self.active(&self.incoming[node], |link| link.source);
+        let outgoing = self.active(&self.outgoing[node], |link| link.target);
+        let max_outgoing = outgoing.iter().map(|id| self.links[*id].weight).max().unwrap_or(0);
+        let mut shortcuts = Vec::new();
+
+        for in_id in incoming {
+            let source = self.links[in_id].source;
+            let in_weight = self.links[in_id].weight;
+            self.witness_search(source, node, in_weight + max_outgoing);
+            for out_id in &outgoing {
+                let target = self.links[*out_id].target;
+                let weight = in_weight + self.links[*out_id].weight;
+                if target != source && self.costs[target] > weight {
+                    shortcuts.push((in_id, *out_id, weight));
+                }
+            }
+            self.reset();
+        }
+        shortcuts
+    }
+
+    /**
+    Get links to nodes which are not contracted yet.
+    *
+    @param self: contraction
+    @param ids: ids of links
+    @param neighbor: function returning neighbor of link
+    *
+    @return ids of links to nodes which are not contracted
+    */
+    fn active<F: Fn(&Link) -> usize>(&self, ids: &[usize], neighbor: F) -> Vec<usize> {
+        ids.iter()
+            .filter(|id| self.levels[neighbor(&self.links[**id])].is_none())
+            .cloned()
+            .collect()
+    }
+
+    /**
+    Limited search for paths from source avoiding the node to be contracted.
+    *
+    @param self: contraction
+    @param source: index of source node
+    @param avoid: index of node to be contracted
+    @param limit: max. cost of witness paths
+    */
+    fn witness_search(&mut self, source: usize, avoid: usize, limit: u32) {
+        let mut queue = BinaryHeap::new();
+        self.costs[source] = 0;
+        self.touched.push(source);
+        queue.push(Reverse((0, source)));
+        let mut settled = 0;
+
+        while let Some(Reverse((cost, node))) = queue.pop() {
+            if cost > self.costs[node] {
+                continue;
+            }
+            settled += 1;
+            if cost > limit || settled > WITNESS_LIMIT {
+                break;
+            }
+            for id in &self.outgoing[node] {
+                let link = &self.links[*id];
+                if link.target == avoid || self.levels[link.target].is_some() {
+                    continue;
+                }
+                let next = cost + link.weight;
+                if next < self.costs[link.target] {
+                    if self.costs[link.target] == u32::MAX {
+                        self.touched.push(link.target);
+                    }
+                    self.costs[link.target] = next;
+                    queue.push(Reverse((next, link.target)));
+                }
+            }
+        }
+    }
+
+    /**
+    Reset costs of witness search.
+    *
+    @param self: contraction
+    */
+    fn reset(&mut self) {
+        for node in self.touched.drain(..) {
+            self.costs[node] = u32::MAX;
+        }
+    }
+
+    /**
+    Priority of node for contraction, nodes with lower priority are contracted first.
+    *
+    @param self: contraction
+    @param node: index of node
+    *
+    @return priority of node
+    */
+    fn priority(&mut self, node: usize) -> i64 {
+        let shortcuts = self.shortcuts(node).len();
+        self.priority_with(node, shortcuts)
+    }
+
+    /**
+    Priority of node for contraction with known number of shortcuts.
+    *
+    @param self: contraction
+    @param node: index of node
+    @param shortcuts: number of shortcuts needed to contract node
+    *
+    @return priority of node
+    */
+    fn priority_with(&self, node: usize, shortcuts: usize) -> i64 {
+        let degree = self.active(&self.incoming[node], |link| link.source).len()
+            + self.active(&self.outgoing[node], |link| link.target).len();
+        // edge difference
+        shortcuts as i64 - degree as i64 + self.contracted_neighbors[node]
+    }
+
+    /**
+    Contract node, i.e. add its shortcuts and assign its level.
+    *
+    @param self: contraction
+    @param node: index of node
+    @param shortcuts: shortcuts needed to contract node
+    @param level: level of node
+    */
+    fn contract(&mut self, node: usize, shortcuts: Vec<(usize, usize, u32)>, level: usize) {
+        for (in_id, out_id, weight) in shortcuts {
+            let source = self.links[in_id].source;
+            let target = self.links[out_id].target;
+            self.add(source, target, in_id, out_id, weight);
+        }
+        self.levels[node] = Some(level);
+        let neighbors: Vec<usize> = self.incoming[node].iter().map(|id| self.links[*id].source)
+            .chain(self.outgoing[node].iter().map(|id| self.links[*id].target))
+            .collect();
+        for neighbor in neighbors {
+            self.contracted_neighbors[neighbor] += 1;
+        }
+    }
+
+    /**
+    Create hierarchy after all nodes are contracted.
+    *
+    @param self: contraction
+    @param number_nodes: number of nodes of graph
+    *
+    @return hierarchy with links to nodes of higher level and links from nodes of higher level
+    */
+    fn hierarchy(self, number_nodes: usize) -> Hierarchy {
+        let levels: Vec<usize> = self.levels.iter().map(|level| level.unwrap()).collect();
+        let (up_offsets, up) = group(number_nodes, &self.links,
+                                     |link| levels[link.source] < levels[link.target], |link| link.source);
+        let (down_offsets, down) = group(number_nodes, &self.links,
+                                         |link| levels[link.source] > levels[link.target], |link| link.target);
+        Hierarchy {
+            levels: Table::Owned(levels),
+            links: Table::Owned(self.links),
+            up_offsets: Table::Owned(up_offsets),
+            up: Table::Owned(up),
+            down_offsets: Table::Owned(down_offsets),
+            down: Table::Owned(down),
+        }
+    }
+}
+
+/**
+Group links by node.
+*
+@param number_nodes: number of nodes of graph
+@param links: all links
+@param filter: function selecting links to be grouped
+@param key: function returning node a link is grouped by
+*
+@return offsets of nodes and ids of links grouped by node
+*/
+fn group<F, K>(number_nodes: usize, links: &[Link], filter: F, key: K) -> (Vec<usize>, Vec<usize>)
+    where F: Fn(&Link) -> bool, K: Fn(&Link) -> usize {
+    let mut offsets = vec![0; number_nodes + 1];
+    for link in links.iter().filter(|link| filter(link)) {
+        offsets[key(link) + 1] += 1;
+    }
+    for i in 1..offsets.len() {
+        offsets[i] += offsets[i - 1];
+    }
+    let mut next = offsets.clone();
+    let mut ids = vec![0; offsets[number_nodes]];
+    for (id, link) in links.iter().enumerate().filter(|(_, link)| filter(link)) {
+        ids[next[key(link)]] = id;
+        next[key(link)] += 1;
+    }
+    (offsets, ids)
+}
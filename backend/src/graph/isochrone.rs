@@ -0,0 +1,429 @@
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use geo::algorithm::haversine_distance::HaversineDistance;
+use geo::Point;
+
+use crate::graph::{Edge, Graph};
+use crate::graph::router::RouterNode;
+use crate::osm::Coordinates;
+use crate::osm::options::{Routing, Transport};
+
+// max. budget in minutes or kilometers, as every street reachable within the budget is searched
+pub const MAX_BUDGET: f64 = 120.0;
+// number of grid cells along the larger side of the reachable area
+const CELLS: f64 = 100.0;
+// min. size of grid cells in m
+const MIN_CELL: f64 = 25.0;
+// min. share of the area of its polygon a hole must have, smaller ones are enclosed by reached streets
+const MIN_HOLE: f64 = 0.05;
+// length of a degree of latitude in m
+const DEGREE: f64 = 111_320.0;
+
+// vertex of grid, cell (x, y) spans from vertex (x, y) to vertex (x + 1, y + 1)
+type Vertex = (i64, i64);
+// rings of a polygon, exterior ring first followed by its holes
+pub type Polygon = Vec<Vec<Coordinates>>;
+
+pub struct IsochroneRouter<'a> {
+    graph: &'a Graph,
+    mode: Transport,
+    routing: Routing,
+}
+
+impl<'a> IsochroneRouter<'a> {
+    /**
+    Create new router for reachable areas.
+    *
+    @param graph: graph as base for routing
+    @param mode: transportation mode (car/bike)
+    @param routing: routing mode, budget is time in ms or distance in m
+    *
+    @return Self: new isochrone router
+    */
+    pub fn new(graph: &'a Graph, mode: Transport, routing: Routing) -> Self {
+        Self {
+            graph,
+            mode,
+            routing,
+        }
+    }
+
+    /**
+    Calculate area reachable from start within a budget. The streets reached by a search from
+    start are rasterized onto a grid, whose cells are traced to polygons afterwards.
+    *
+    @param self: isochrone router
+    @param start: start coordinates
+    @param budget: time in ms or distance in m based on routing
+    *
+    @return Result<Vec<Polygon>, &str>: polygons of reachable area
+    */
+    pub fn isochrone(&self, start: &Coordinates, budget: u32) -> Result<Vec<Polygon>, &'static str> {
+        let start = self.graph.snap(start, self.mode)?;
+        // reached parts of streets
+        let mut segments = vec![(start.coordinates.point(), start.coordinates.point())];
+        let mut best: HashMap<usize, u32> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+
+        // search starts at all nodes reached on partial edges leaving start
+        for part in start.leaving(self.graph) {
+            let mut line = vec![start.coordinates.clone()];
+            line.extend(part.geometry.iter().cloned());
+            line.push(self.graph.coordinates(part.index).clone());
+            let weight = self.weight(&part.edge);
+            reach(&line, 0, weight, budget, &mut segments);
+            if weight <= budget && best.get(&part.index).is_none_or(|cost| weight < *cost) {
+                best.insert(part.index, weight);
+                queue.push(RouterNode::new(part.index, weight, 0));
+            }
+        }
+        while let Some(node) = queue.pop() {
+            // better solution already found
+            if node.cost > best[&node.index] {
+                continue;
+            }
+            for edge in self.graph.edges(node.index) {
+                if !edge.transport.contains(self.mode) {
+                    continue;
+                }
+                let mut line = vec![self.graph.coordinates(node.index).clone()];
+                line.extend(self.graph.geometry(self.graph.position(edge)).iter().cloned());
+                line.push(self.graph.coordinates(edge.target_index).clone());
+                let weight = self.weight(edge);
+                // edges leaving the reachable area are reached partially
+                reach(&line, node.cost, weight, budget, &mut segments);
+                let cost = node.cost + weight;
+                if cost <= budget && best.get(&edge.target_index).is_none_or(|best| cost < *best) {
+                    best.insert(edge.target_index, cost);
+                    queue.push(RouterNode::new(edge.target_index, cost, 0));
+                }
+            }
+        }
+        Ok(contour(&segments))
+    }
+
+    /**
+    Weight of an edge in unit of budget.
+    *
+    @param self: isochrone router
+    @param edge: edge
+    *
+    @return time in ms or distance in m needed for edge
+    */
+    fn weight(&self, edge: &Edge) -> u32 {
+        match self.routing {
+            // times in ms are rounded up, so edges of a few meters do not take no time
+            Routing::Time => edge.millis(self.mode),
+            Routing::Distance => edge.distance,
+        }
+    }
+}
+
+/**
+Add the part of a line reached within the budget to the reached segments.
+*
+@param line: coordinates of line, e.g. of an edge with its intermediate coordinates
+@param cost: cost at start of line
+@param weight: weight of whole line
+@param budget: budget of search
+@param segments: reached segments
+*/
+fn reach(line: &[Coordinates], cost: u32, weight: u32, budget: u32, segments: &mut Vec<(Point<f64>, Point<f64>)>) {
+    if cost > budget {
+        return;
+    }
+    let points: Vec<Point<f64>> = line.iter().map(|coordinates| coordinates.point()).collect();
+    let lengths: Vec<f64> = points.windows(2).map(|pair| pair[0].haversine_distance(&pair[1])).collect();
+    // length of line reached with remaining budget
+    let total: f64 = lengths.iter().sum();
+    let mut remaining = if weight > budget - cost {
+        total * f64::from(budget - cost) / f64::from(weight)
+    } else {
+        total
+    };
+    for (pair, length) in points.windows(2).zip(&lengths) {
+        if *length <= remaining {
+            segments.push((pair[0], pair[1]));
+            remaining -= length;
+        } else {
+            let fraction = remaining / length;
+            let end = Point::new(pair[0].x() + (pair[1].x() - pair[0].x()) * fraction,
+                                 pair[0].y() + (pair[1].y() - pair[0].y()) * fraction);
+            segments.push((pair[0], end));
+            return;
+        }
+    }
+}
+
+/**
+Calculate polygons around segments. Segments are rasterized onto a grid, including the cells
+around each reached cell, so streets close to each other form a connected area.
+*
+@param segments: segments with longitude as x and latitude as y
+*
+@return polygons around segments
+*/
+fn contour(segments: &[(Point<f64>, Point<f64>)]) -> Vec<Polygon> {
+    let points = || segments.iter().flat_map(|(a, b)| vec![a, b]);
+    let (min_x, max_x) = points().fold((f64::MAX, f64::MIN), |(min, max), p| (min.min(p.x()), max.max(p.x())));
+    let (min_y, max_y) = points().fold((f64::MAX, f64::MIN), |(min, max), p| (min.min(p.y()), max.max(p.y())));
+    // longitudes are scaled so cells are squares
+    let scale = ((min_y + max_y) / 2.0).to_radians().cos();
+    let extent = ((max_x - min_x) * DEGREE * scale).max((max_y - min_y) * DEGREE);
+    let cell = (extent / CELLS).max(MIN_CELL);
+    let (width, height) = (cell / DEGREE / scale, cell / DEGREE);
+
+    let mut cells = HashSet::new();
+    for (a, b) in segments {
+        let length = ((b.x() - a.x()) * DEGREE * scale).hypot((b.y() - a.y()) * DEGREE);
+        // sample segment at half of cell size, so no cell along it is skipped
+        let samples = (length / cell * 2.0).ceil().max(1.0) as usize;
+        for i in 0..=samples {
+            let fraction = i as f64 / samples as f64;
+            let x = ((a.x() + (b.x() - a.x()) * fraction - min_x) / width).floor() as i64;
+            let y = ((a.y() + (b.y() - a.y()) * fraction - min_y) / height).floor() as i64;
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    cells.insert((x + dx, y + dy));
+                }
+            }
+        }
+    }
+    polygons(&cells).into_iter()
+        .map(|rings| rings.into_iter()
+            .map(|ring| ring.iter()
+                .chain(ring.first())
+                .map(|(x, y)| Coordinates::from(Point::new(min_x + *x as f64 * width, min_y + *y as f64 * height)))
+                .collect())
+            .collect())
+        .collect()
+}
+
+/**
+Trace the boundaries of filled grid cells and group them to polygons.
+*
+@param cells: filled cells
+*
+@return polygons with vertices of grid, exterior rings are counterclockwise, holes clockwise,
+small holes are filled
+*/
+fn polygons(cells: &HashSet<Vertex>) -> Vec<Vec<Vec<Vertex>>> {
+    // sides of filled cells without filled neighbor, directed so the cell is on their left
+    let mut outgoing: HashMap<Vertex, Vec<Vertex>> = HashMap::new();
+    for &(x, y) in cells {
+        let sides = [
+            ((x, y - 1), (x, y), (x + 1, y)),
+            ((x + 1, y), (x + 1, y), (x + 1, y + 1)),
+            ((x, y + 1), (x + 1, y + 1), (x, y + 1)),
+            ((x - 1, y), (x, y + 1), (x, y)),
+        ];
+        for (neighbor, from, to) in sides.iter() {
+            if !cells.contains(neighbor) {
+                outgoing.entry(*from).or_default().push(*to);
+            }
+        }
+    }
+
+    let mut exteriors = Vec::new();
+    let mut holes = Vec::new();
+    while let Some(&start) = outgoing.keys().next() {
+        let mut ring = vec![start];
+        let (mut current, mut direction) = (start, (0, 0));
+        loop {
+            let targets = outgoing.get_mut(&current).unwrap();
+            // turn left where cells only touch at a corner, so they are not connected
+            let (i, _) = targets.iter().enumerate()
+                .max_by_key(|(_, target)| direction.0 * (target.1 - current.1) - direction.1 * (target.0 - current.0))
+                .unwrap();
+            let next = targets.swap_remove(i);
+            if targets.is_empty() {
+                outgoing.remove(&current);
+            }
+            direction = (next.0 - current.0, next.1 - current.1);
+            current = next;
+            if current == start {
+                break;
+            }
+            ring.push(current);
+        }
+        let ring = simplify(ring);
+        if area(&ring) > 0 {
+            exteriors.push(vec![ring]);
+        } else {
+            holes.push(ring);
+        }
+    }
+    // each hole belongs to the smallest exterior ring containing it
+    for hole in holes {
+        let size = -area(&hole) as f64;
+        // center of the unfilled cell right of the first side of hole
+        let (a, b) = (hole[0], hole[1]);
+        let direction = ((b.0 - a.0).signum() as f64, (b.1 - a.1).signum() as f64);
+        let center = (a.0 as f64 + (direction.0 + direction.1) / 2.0, a.1 as f64 + (direction.1 - direction.0) / 2.0);
+        let exterior = exteriors.iter_mut()
+            .filter(|rings| contains(&rings[0], center))
+            .min_by_key(|rings| area(&rings[0]));
+        if let Some(rings) = exterior {
+            if size >= area(&rings[0]) as f64 * MIN_HOLE {
+                rings.push(hole);
+            }
+        }
+    }
+    exteriors
+}
+
+/**
+Remove vertices of ring which are on a straight line between their neighbors.
+*
+@param ring: vertices of ring
+*
+@return vertices of ring at corners only
+*/
+fn simplify(ring: Vec<Vertex>) -> Vec<Vertex> {
+    let n = ring.len();
+    (0..n)
+        .filter(|i| {
+            let (prev, vertex, next) = (ring[(i + n - 1) % n], ring[*i], ring[(i + 1) % n]);
+            (vertex.0 - prev.0) * (next.1 - vertex.1) != (vertex.1 - prev.1) * (next.0 - vertex.0)
+        })
+        .map(|i| ring[i])
+        .collect()
+}
+
+/**
+Calculate twice the signed area of a ring.
+*
+@param ring: vertices of ring
+*
+@return area, positive if ring is counterclockwise
+*/
+fn area(ring: &[Vertex]) -> i64 {
+    (0..ring.len())
+        .map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum()
+}
+
+/**
+Check if a point is within a ring by counting crossings of a ray from the point.
+*
+@param ring: vertices of ring
+@param point: point not on any side of ring
+*
+@return true if point is within ring
+*/
+fn contains(ring: &[Vertex], point: (f64, f64)) -> bool {
+    let mut within = false;
+    for i in 0..ring.len() {
+        let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+        let (ax, ay, bx, by) = (a.0 as f64, a.1 as f64, b.0 as f64, b.1 as f64);
+        if (ay > point.1) != (by > point.1) && point.0 < ax + (point.1 - ay) / (by - ay) * (bx - ax) {
+            within = !within;
+        }
+    }
+    within
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use geo::algorithm::contains::Contains;
+    use geo::{LineString, Point, Polygon};
+
+    use crate::graph::Edge;
+    use crate::graph::isochrone::{area, IsochroneRouter, polygons, Polygon as Rings};
+    use crate::graph::router::tests::{graph, grid};
+    use crate::osm::Coordinates;
+    use crate::osm::highway::Kmh;
+    use crate::osm::options::Routing::{Distance, Time};
+    use crate::osm::options::Transport::{Bike, Car};
+
+    #[test]
+    fn tracing() {
+        // square of 3x3 cells with hole, single cell, and two cells only touching at a corner
+        let mut cells: HashSet<(i64, i64)> = HashSet::new();
+        for x in 0..3 {
+            for y in 0..3 {
+                if (x, y) != (1, 1) {
+                    cells.insert((x, y));
+                }
+            }
+        }
+        cells.insert((5, 0));
+        cells.insert((8, 0));
+        cells.insert((9, 1));
+        let mut polygons = polygons(&cells);
+        polygons.sort_by_key(|rings| rings[0][0]);
+        assert_eq!(4, polygons.len());
+
+        let square = &polygons[0];
+        assert_eq!(2, square.len());
+        assert_eq!(vec![4, 4], square.iter().map(|ring| ring.len()).collect::<Vec<usize>>());
+        // exterior ring is counterclockwise, hole clockwise
+        assert_eq!(18, area(&square[0]));
+        assert_eq!(-2, area(&square[1]));
+        assert!(polygons[1..].iter().all(|rings| rings.len() == 1 && area(&rings[0]) == 2));
+    }
+
+    /**
+    Check if polygons contain coordinates.
+    */
+    fn within(polygons: &[Rings], coordinates: &Coordinates) -> bool {
+        polygons.iter().any(|rings| {
+            let ring = |ring: &Vec<Coordinates>| LineString::from(ring.iter()
+                .map(|coordinates| (coordinates.lon(), coordinates.lat()))
+                .collect::<Vec<(f64, f64)>>());
+            Polygon::new(ring(&rings[0]), rings[1..].iter().map(ring).collect())
+                .contains(&coordinates.point())
+        })
+    }
+
+    #[test]
+    fn isochrone() {
+        let graph = grid(6);
+        let start = graph.coordinates(0).clone();
+        let near = graph.coordinates(1).clone();
+        let far = graph.coordinates(35).clone();
+
+        // 1.5km reach the neighbors of start, but not the other corner of the grid
+        let polygons = IsochroneRouter::new(&graph, Bike, Distance).isochrone(&start, 1500).unwrap();
+        assert!(!polygons.is_empty());
+        assert!(within(&polygons, &start));
+        assert!(within(&polygons, &near));
+        assert!(!within(&polygons, &far));
+        // rings are closed
+        assert!(polygons.iter().flatten().all(|ring| ring.first() == ring.last()));
+
+        // whole grid is reachable by bike within 1.5 hours
+        let polygons = IsochroneRouter::new(&graph, Bike, Time).isochrone(&start, 5_400_000).unwrap();
+        assert!(within(&polygons, &far));
+        assert!(within(&polygons, &Coordinates::from(Point::new(9.025, 48.025))));
+
+        // area is only the surrounding of start if budget is used up
+        let polygons = IsochroneRouter::new(&graph, Car, Time).isochrone(&start, 0).unwrap();
+        assert!(within(&polygons, &start));
+        assert!(!within(&polygons, &near));
+    }
+
+    #[test]
+    fn short_edges() {
+        // motorway of edges of 3m, which take no time in s
+        let coordinates: Vec<Coordinates> = (0..1000)
+            .map(|i| Coordinates::from(Point::new(9.0 + i as f64 * 0.00004, 48.0)))
+            .collect();
+        let edges = (0..999)
+            .map(|i| Edge::new(i, i + 1, Car, coordinates[i].length(&coordinates[i + 1]), Kmh::new(130)))
+            .collect();
+        let graph = graph(&coordinates, edges, Vec::new(), Vec::new());
+        assert_eq!(0, graph.edges(0)[0].time(Car));
+
+        // 10s at 130km/h reach about 360m of the 3km long motorway
+        let polygons = IsochroneRouter::new(&graph, Car, Time).isochrone(&coordinates[0], 10_000).unwrap();
+        assert!(within(&polygons, &coordinates[100]));
+        assert!(!within(&polygons, &coordinates[200]));
+        assert!(!within(&polygons, &coordinates[999]));
+    }
+}
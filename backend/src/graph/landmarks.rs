@@ -0,0 +1,235 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use log::debug;
+
+use crate::graph::Graph;
+use crate::graph::flat::Table;
+use crate::osm::options::{Routing, Transport};
+use crate::osm::options::Routing::{Distance, Time};
+use crate::osm::options::Transport::{Bike, Car};
+
+// default number of landmarks. Each landmark costs 3 profiles * 2 directions * 4 bytes, i.e. 24
+// bytes per node, so the default takes 192 bytes per node.
+pub const LANDMARKS: usize = 8;
+// transportation modes and routing modes distances to landmarks are stored for, costs of bikes are
+// distances for both routing modes
+const PROFILES: [(Transport, Routing); 3] = [(Car, Time), (Car, Distance), (Bike, Distance)];
+// cost of nodes which cannot reach or be reached from a landmark
+const UNREACHABLE: u32 = u32::MAX;
+
+/**
+Landmarks of graph with costs from and to each landmark for all nodes. By the triangle inequality
+these costs give a lower bound of the cost between any two nodes (ALT heuristic).
+*/
+#[derive(Debug)]
+pub struct Landmarks {
+    // indices of landmark nodes
+    pub nodes: Table<usize>,
+    // costs from and to landmarks, grouped by profile, node and landmark
+    pub distances: Table<u32>,
+}
+
+impl Landmarks {
+    /**
+    Create landmarks without any landmark, which give no lower bound.
+    *
+    @return empty landmarks
+    */
+    pub fn empty() -> Self {
+        Self {
+            nodes: Table::Owned(Vec::new()),
+            distances: Table::Owned(Vec::new()),
+        }
+    }
+
+    /**
+    Check if landmarks belong to a graph.
+    *
+    @param self: landmarks
+    @param number_nodes: number of nodes of graph
+    *
+    @return true if costs of all nodes are stored
+    */
+    pub fn fits(&self, number_nodes: usize) -> bool {
+        self.distances.len() == PROFILES.len() * number_nodes * self.nodes.len() * 2
+            && self.nodes.iter().all(|index| *index < number_nodes)
+    }
+
+    /**
+    Check if landmarks give lower bounds for a transportation mode and routing mode.
+    *
+    @param self: landmarks
+    @param mode: transportation mode (car/bike)
+    @param routing: routing mode (distance/time)
+    *
+    @return true if costs of profile are stored
+    */
+    pub fn supports(&self, mode: Transport, routing: Routing) -> bool {
+        self.profile(mode, routing).is_some()
+    }
+
+    /**
+    Lower bound of cost between two nodes.
+    *
+    @param self: landmarks
+    @param mode: transportation mode (car/bike)
+    @param routing: routing mode (distance/time)
+    @param from: index of node
+    @param to: index of target node
+    *
+    @return u32: lower bound of cost from node to target node, 0 if not known
+    */
+    pub fn heuristic(&self, mode: Transport, routing: Routing, from: usize, to: usize) -> u32 {
+        let profile = match self.profile(mode, routing) {
+            Some(profile) => profile,
+            None => return 0
+        };
+        let from = self.costs(profile, from);
+        let to = self.costs(profile, to);
+        let mut bound = 0;
+        for (from, to) in from.chunks(2).zip(to.chunks(2)) {
+            // cost(from, to) >= cost(landmark, to) - cost(landmark, from)
+            if from[0] != UNREACHABLE && to[0] != UNREACHABLE {
+                bound = bound.max(to[0].saturating_sub(from[0]));
+            }
+            // cost(from, to) >= cost(from, landmark) - cost(to, landmark)
+            if from[1] != UNREACHABLE && to[1] != UNREACHABLE {
+                bound = bound.max(from[1].saturating_sub(to[1]));
+            }
+        }
+        bound
+    }
+
+    /**
+    Get position of transportation mode and routing mode in profiles.
+    *
+    @param self: landmarks
+    @param mode: transportation mode (car/bike)
+    @param routing: routing mode (distance/time)
+    *
+    @return (optional) position of profile, none if no landmarks are selected
+    */
+    fn profile(&self, mode: Transport, routing: Routing) -> Option<usize> {
+        let routing = if mode == Bike { Distance } else { routing };
+        PROFILES.iter()
+            .position(|profile| *profile == (mode, routing))
+            .filter(|_| !self.nodes.is_empty())
+    }
+
+    /**
+    Get costs from and to all landmarks of a node.
+    *
+    @param self: landmarks
+    @param profile: position of transportation mode and routing mode in profiles
+    @param index: index of node
+    *
+    @return &[u32]: pairs of cost from landmark and cost to landmark
+    */
+    fn costs(&self, profile: usize, index: usize) -> &[u32] {
+        let size = self.nodes.len() * 2;
+        let number_nodes = self.distances.len() / PROFILES.len() / size;
+        let start = (profile * number_nodes + index) * size;
+        &self.distances[start..start + size]
+    }
+}
+
+/**
+Select landmarks of graph and calculate costs from and to landmarks for all nodes. Each landmark
+is the node farthest from all landmarks selected before.
+*
+@param graph: graph
+@param count: max. number of landmarks, no landmarks are selected if 0
+*
+@return landmarks of graph
+*/
+pub fn select(graph: &Graph, count: usize) -> Landmarks {
+    let number_nodes = graph.nodes.len();
+    let start = match (0..number_nodes)
+        .filter(|_| count > 0)
+        .find(|index| graph.edges(*index).iter().any(|edge| edge.transport.contains(Car))) {
+        Some(start) => start,
+        None => return Landmarks::empty()
+    };
+    let mut nodes: Vec<usize> = Vec::new();
+    // cost from closest landmark, cost from start before first landmark is selected
+    let mut closest = dijkstra(graph, start, Car, Time, false);
+    while nodes.len() < count {
+        let next = (0..number_nodes)
+            .filter(|index| closest[*index] != UNREACHABLE)
+            .max_by_key(|index| closest[*index]);
+        // all reachable nodes are landmarks already
+        let next = match next {
+            Some(next) if nodes.is_empty() || closest[next] > 0 => next,
+            _ => break
+        };
+        let costs = dijkstra(graph, next, Car, Time, false);
+        if nodes.is_empty() {
+            closest = costs;
+        } else {
+            for (closest, cost) in closest.iter_mut().zip(costs) {
+                *closest = (*closest).min(cost);
+            }
+        }
+        nodes.push(next);
+    }
+    debug!("Selected {} landmarks", nodes.len());
+
+    let size = nodes.len() * 2;
+    let mut distances = vec![UNREACHABLE; PROFILES.len() * number_nodes * size];
+    for (profile, (mode, routing)) in PROFILES.iter().enumerate() {
+        for (i, landmark) in nodes.iter().enumerate() {
+            let from = dijkstra(graph, *landmark, *mode, *routing, false);
+            let to = dijkstra(graph, *landmark, *mode, *routing, true);
+            for index in 0..number_nodes {
+                let start = (profile * number_nodes + index) * size + i * 2;
+                distances[start] = from[index];
+                distances[start + 1] = to[index];
+            }
+        }
+    }
+    Landmarks {
+        nodes: Table::Owned(nodes),
+        distances: Table::Owned(distances),
+    }
+}
+
+/**
+Calculate costs of all nodes from or to a node.
+*
+@param graph: graph
+@param source: index of node
+@param mode: transportation mode (car/bike)
+@param routing: routing mode (distance/time)
+@param backward: calculate costs to node along incoming edges instead of costs from node
+*
+@return Vec<u32>: costs of all nodes, UNREACHABLE if not connected
+*/
+fn dijkstra(graph: &Graph, source: usize, mode: Transport, routing: Routing, backward: bool) -> Vec<u32> {
+    let mut costs = vec![UNREACHABLE; graph.nodes.len()];
+    let mut queue = BinaryHeap::new();
+    costs[source] = 0;
+    queue.push(Reverse((0, source)));
+
+    while let Some(Reverse((cost, index))) = queue.pop() {
+        if cost > costs[index] {
+            continue;
+        }
+        let edges: Box<dyn Iterator<Item=_>> = if backward {
+            Box::new(graph.incoming_edges(index).map(|edge| (edge, edge.source_index)))
+        } else {
+            Box::new(graph.edges(index).iter().map(|edge| (edge, edge.target_index)))
+        };
+        for (edge, next) in edges {
+            if !edge.transport.contains(mode) {
+                continue;
+            }
+            let next_cost = cost + edge.cost(mode, routing);
+            if next_cost < costs[next] {
+                costs[next] = next_cost;
+                queue.push(Reverse((next_cost, next)));
+            }
+        }
+    }
+    costs
+}
@@ -0,0 +1,267 @@
+use std::collections::{BinaryHeap, HashSet};
+
+use crate::graph::{Edge, Graph};
+use crate::graph::router::RouterNode;
+use crate::graph::tree::{Part, Snap};
+use crate::osm::Coordinates;
+use crate::osm::options::{Routing, Transport};
+
+// max. number of sources and targets of a matrix, as one search is needed per source
+pub const MAX_LOCATIONS: usize = 100;
+
+pub struct MatrixRouter<'a> {
+    graph: &'a Graph,
+    mode: Transport,
+    routing: Routing,
+}
+
+impl<'a> MatrixRouter<'a> {
+    /**
+    Create new router for cost matrices.
+    *
+    @param graph: graph as base for routing
+    @param mode: transportation mode (car/bike)
+    @param routing: routing mode (distance/time) of routes between sources and targets
+    *
+    @return Self: new matrix router
+    */
+    pub fn new(graph: &'a Graph, mode: Transport, routing: Routing) -> Self {
+        Self {
+            graph,
+            mode,
+            routing,
+        }
+    }
+
+    /**
+    Calculate time and distance of routes from all sources to all targets. A single search is
+    started at each source, which runs until the nodes all targets are reached from are settled.
+    *
+    @param self: matrix router
+    @param sources: coordinates of sources
+    @param targets: coordinates of targets
+    *
+    @return entries by index of source and target, none if target is unreachable from source
+    */
+    pub fn matrix(&self, sources: &[Coordinates], targets: &[Coordinates]) -> Vec<Vec<Option<Entry>>> {
+        // project targets onto nearest edge segments, unreachable without any for transportation mode
+        let targets: Vec<Option<(Snap, Vec<Part>)>> = targets.iter()
+            .map(|coordinates| self.graph.snap(coordinates, self.mode).ok())
+            .map(|snap| snap.map(|snap| {
+                let entering = snap.entering(self.graph);
+                (snap, entering)
+            }))
+            .collect();
+        let mut best = vec![None; self.graph.nodes.len()];
+        sources.iter()
+            .map(|source| match self.graph.snap(source, self.mode) {
+                Ok(source) => self.row(&source, &targets, &mut best),
+                Err(_) => vec![None; targets.len()]
+            })
+            .collect()
+    }
+
+    /**
+    Search from a source until the nodes all targets are reached from are settled.
+    *
+    @param self: matrix router
+    @param source: projected source
+    @param targets: projected targets with partial edges entering them
+    @param best: best entry of each node, reset after search
+    *
+    @return entries by index of target, none if target is unreachable
+    */
+    fn row(&self, source: &Snap, targets: &[Option<(Snap, Vec<Part>)>],
+           best: &mut [Option<Entry>]) -> Vec<Option<Entry>> {
+        let mut remaining: HashSet<usize> = targets.iter()
+            .flatten()
+            .flat_map(|(_, entering)| entering.iter().map(|part| part.index))
+            .collect();
+        let mut touched = Vec::new();
+        let mut queue = BinaryHeap::new();
+        // search starts at all nodes reached on partial edges leaving source
+        for part in source.leaving(self.graph) {
+            let entry = Entry::new(0, 0, 0).add(&part.edge, self.mode, self.routing);
+            if best[part.index].is_none_or(|best: Entry| entry.cost < best.cost) {
+                best[part.index] = Some(entry);
+                touched.push(part.index);
+                queue.push(RouterNode::new(part.index, entry.cost, 0));
+            }
+        }
+        while let Some(node) = queue.pop() {
+            if remaining.is_empty() {
+                break;
+            }
+            let current = best[node.index].unwrap();
+            // better solution already found
+            if node.cost > current.cost {
+                continue;
+            }
+            remaining.remove(&node.index);
+            for edge in self.graph.edges(node.index) {
+                if !edge.transport.contains(self.mode) {
+                    continue;
+                }
+                let entry = current.add(edge, self.mode, self.routing);
+                if best[edge.target_index].is_none_or(|best: Entry| entry.cost < best.cost) {
+                    if best[edge.target_index].is_none() {
+                        touched.push(edge.target_index);
+                    }
+                    best[edge.target_index] = Some(entry);
+                    queue.push(RouterNode::new(edge.target_index, entry.cost, 0));
+                }
+            }
+        }
+
+        let row = targets.iter()
+            .map(|target| target.as_ref().and_then(|(snap, entering)| {
+                let mut candidates: Vec<Entry> = entering.iter()
+                    .filter_map(|part| best[part.index].map(|entry| entry.add(&part.edge, self.mode, self.routing)))
+                    .collect();
+                candidates.extend(self.direct(source, snap));
+                candidates.into_iter().min_by_key(|entry| entry.cost)
+            }))
+            .collect();
+        for index in touched {
+            best[index] = None;
+        }
+        row
+    }
+
+    /**
+    Get entry of route from source to target on the same edge, if target is ahead of source.
+    *
+    @param self: matrix router
+    @param source: projected source
+    @param target: projected target
+    *
+    @return (optional) entry of part of edge between source and target
+    */
+    fn direct(&self, source: &Snap, target: &Snap) -> Option<Entry> {
+        source.edges.iter()
+            .flat_map(|(position, start_fraction, start_split)| target.edges.iter()
+                // target is ahead of source on edge
+                .filter(move |(goal_position, goal_fraction, _)| {
+                    goal_position == position && goal_fraction >= start_fraction
+                })
+                .map(move |(_, goal_fraction, goal_split)| {
+                    let from = (*start_fraction, *start_split);
+                    let to = (*goal_fraction, *goal_split);
+                    let part = self.graph.part(*position, self.graph.edge(*position).target_index, from, to);
+                    Entry::new(0, 0, 0).add(&part.edge, self.mode, self.routing)
+                }))
+            .min_by_key(|entry| entry.cost)
+    }
+}
+
+/**
+Cost, time and distance of the route from a source to a node or target.
+*/
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Entry {
+    // cost based on routing
+    pub cost: u32,
+    pub time: u32,
+    pub distance: u32,
+}
+
+impl Entry {
+    /**
+    Create new entry of matrix.
+    *
+    @param cost: cost based on routing
+    @param time: time needed for route
+    @param distance: distance of route
+    *
+    @return new entry
+    */
+    pub fn new(cost: u32, time: u32, distance: u32) -> Self {
+        Self {
+            cost,
+            time,
+            distance,
+        }
+    }
+
+    /**
+    Extend route of entry by an edge.
+    *
+    @param self: entry
+    @param edge: edge to travel along
+    @param mode: transportation mode (car/bike)
+    @param routing: routing mode (distance/time)
+    *
+    @return entry of extended route
+    */
+    fn add(&self, edge: &Edge, mode: Transport, routing: Routing) -> Self {
+        Self::new(
+            self.cost + edge.cost(mode, routing),
+            self.time + edge.time(mode),
+            self.distance + edge.distance,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::Point;
+
+    use crate::graph::Edge;
+    use crate::graph::matrix::MatrixRouter;
+    use crate::graph::router::{Route, Router};
+    use crate::graph::router::tests::{assert_same_routes, graph, grid};
+    use crate::osm::Coordinates;
+    use crate::osm::highway::Kmh;
+    use crate::osm::options::Routing::{Distance, Time};
+    use crate::osm::options::Search::Unidirectional;
+    use crate::osm::options::Transport::{Bike, Car};
+
+    #[test]
+    fn matrix() {
+        let graph = grid(6);
+        let mut points: Vec<Coordinates> = (0..36)
+            .map(|index| graph.coordinates(index).clone())
+            .collect();
+        // coordinates beside streets are projected onto them
+        points.push(Coordinates::from(Point::new(9.005, 48.0001)));
+        points.push(Coordinates::from(Point::new(9.0301, 48.034)));
+        for &(mode, routing) in &[(Car, Time), (Bike, Distance)] {
+            let cost = |route: &Route| if routing == Time { route.time } else { route.distance };
+            // routes between coordinates of nodes
+            let routes = assert_same_routes(&graph, mode, routing, Unidirectional);
+            let matrix = MatrixRouter::new(&graph, mode, routing).matrix(&points, &points);
+            assert_eq!(points.len(), matrix.len());
+            for (i, source) in points.iter().enumerate() {
+                assert_eq!(points.len(), matrix[i].len());
+                for (j, target) in points.iter().enumerate() {
+                    // one search per source gives same route as routing between each pair
+                    let expected = match routes.get(i).and_then(|row| row.get(j)) {
+                        Some(route) => route.as_ref().ok().map(cost),
+                        None => Router::new(&graph, mode, routing).shortest_path(source, target).ok()
+                            .map(|route| cost(&route))
+                    };
+                    let actual = matrix[i][j].map(|entry| if routing == Time { entry.time } else { entry.distance });
+                    assert_eq!(expected, actual, "{} to {}", i, j);
+                }
+                assert_eq!(Some(0), matrix[i][i].map(|entry| entry.distance));
+            }
+        }
+    }
+
+    #[test]
+    fn unreachable() {
+        // oneway from 0 to 2
+        let coordinates: Vec<Coordinates> = (0..3)
+            .map(|i| Coordinates::from(Point::new(9.0 + i as f64 * 0.01, 48.0)))
+            .collect();
+        let edges = vec![Edge::new(0, 1, Car, 1000, Kmh::new(50)), Edge::new(1, 2, Car, 1000, Kmh::new(50))];
+        let graph = graph(&coordinates, edges, Vec::new(), Vec::new());
+        let points = [graph.coordinates(0).clone(), graph.coordinates(2).clone()];
+        let matrix = MatrixRouter::new(&graph, Car, Time).matrix(&points, &points);
+        assert_eq!(Some(2000), matrix[0][1].map(|entry| entry.distance));
+        assert_eq!(None, matrix[1][0]);
+        // no street allows bikes
+        let matrix = MatrixRouter::new(&graph, Bike, Distance).matrix(&points, &points);
+        assert!(matrix.iter().flatten().all(|entry| entry.is_none()));
+    }
+}
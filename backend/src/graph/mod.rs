@@ -1,7 +1,8 @@
 use std::cmp::Ordering;
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufReader, Write};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Seek, Write};
+use std::process;
+use std::sync::Arc;
 
 use log::debug;
 use serde::{Deserialize, Serialize};
@@ -11,21 +12,57 @@ use crate::osm::highway::Kmh;
 use crate::osm::options::{Routing, Transport, ChargingOptions};
 use crate::osm::options::Routing::Time;
 use crate::osm::options::Transport::{Bike, Car};
+use crate::graph::flat::{Mmap, Table};
+use crate::graph::format::{FormatError, Header};
+use crate::graph::hierarchy::Hierarchy;
+use crate::graph::landmarks::Landmarks;
+use crate::graph::tree::KdTree;
+use crate::osm::charging::{max_power, Socket, SocketType};
 use crate::osm::pbf::Pbf;
+use crate::osm::restriction::RestrictionKind;
 use crate::osm::Coordinates;
+use crate::vehicle;
 
+pub mod alternatives;
+pub mod charging;
+pub mod flat;
+pub mod format;
+pub mod hierarchy;
+pub mod isochrone;
+pub mod landmarks;
+pub mod matrix;
 pub mod router;
-mod grid;
+pub mod tour;
+mod tree;
 
-pub type Cells = HashMap<Coordinates, Vec<usize>>;
+// constant speed of bikes
+pub const BIKE_SPEED: Kmh = Kmh { speed: 20 };
+// factor of cost of edges cars may only use to reach a destination, keeps through traffic away
+const DESTINATION_PENALTY: u32 = 10;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug)]
 pub struct Graph {
-    nodes: Vec<Node>,
-    offsets: Vec<usize>,
-    edges: Vec<Edge>,
-    cells: Cells,
+    nodes: Table<Node>,
+    offsets: Table<usize>,
+    edges: Table<Edge>,
+    // offsets of incoming edges of nodes
+    reverse_offsets: Table<usize>,
+    // positions of incoming edges in edges, grouped by target node
+    reverse_edges: Table<usize>,
+    // offsets of intermediate coordinates of edges
+    geometry_offsets: Table<usize>,
+    // intermediate coordinates of merged chains of nodes, grouped by edge
+    geometry: Table<Coordinates>,
+    // spatial index of nodes and edges
+    tree: KdTree,
     charging_nodes: Vec<ChargingNode>,
+    restrictions: Vec<TurnRestriction>,
+    // max. speed of each transportation mode in graph
+    max_speeds: Vec<(Transport, Kmh)>,
+    // contraction hierarchy, empty if not contracted
+    hierarchy: Hierarchy,
+    // landmarks for lower bounds of costs, empty if not selected
+    landmarks: Landmarks,
 }
 
 impl Graph {
@@ -35,27 +72,46 @@ impl Graph {
     @param nodes: parsed nodes
     @param offsets: offset array for nodes
     @param edges: parsed edges
+    @param geometry: intermediate coordinates of each edge, empty if no edge has any
     @param charging_nodes: charging station nodes
+    @param restrictions: turn restrictions, already applied to nodes and edges
     *
     @return Self: a new graph
     */
-    pub fn new(nodes: StableVec<Node>, offsets: Vec<usize>, edges: Vec<Edge>, charging_nodes: Vec<ChargingNode>) -> Self {
-        // StableVec does not implement Serialize
+    pub fn new(nodes: StableVec<Node>, offsets: Vec<usize>, edges: Vec<Edge>, geometry: Vec<Vec<Coordinates>>,
+               charging_nodes: Vec<ChargingNode>, restrictions: Vec<TurnRestriction>) -> Self {
+        // tables of graph are contiguous, StableVec is not
         let mut vec = Vec::with_capacity(nodes.capacity());
         // add all nodes to vec
         for (_, node) in nodes {
             vec.push(node);
         }
-        // create grid
-        let cells = grid::create(&vec);
-        // create and return graph object with all data
-        Self {
-            nodes: vec,
-            edges,
-            offsets,
-            cells,
-            charging_nodes,
+        let mut geometry_offsets = vec![0; edges.len() + 1];
+        for (position, coordinates) in geometry.iter().enumerate() {
+            geometry_offsets[position + 1] = geometry_offsets[position] + coordinates.len();
         }
+        let geometry: Vec<Coordinates> = geometry.into_iter().flatten().collect();
+        let (reverse_offsets, reverse_edges) = reverse(offsets.len() - 1, &edges);
+        let max_speeds = max_speeds(&edges);
+        // create graph object with all data
+        let mut graph = Self {
+            nodes: Table::Owned(vec),
+            edges: Table::Owned(edges),
+            offsets: Table::Owned(offsets),
+            reverse_offsets: Table::Owned(reverse_offsets),
+            reverse_edges: Table::Owned(reverse_edges),
+            geometry_offsets: Table::Owned(geometry_offsets),
+            geometry: Table::Owned(geometry),
+            tree: KdTree::empty(),
+            charging_nodes,
+            restrictions,
+            max_speeds,
+            hierarchy: Hierarchy::empty(),
+            landmarks: Landmarks::empty(),
+        };
+        // create spatial index
+        graph.tree = tree::create(&graph);
+        graph
     }
 
     pub fn get_charging_stations(&self) -> &Vec<ChargingNode> {
@@ -63,52 +119,207 @@ impl Graph {
     }
 
     /**
-    Read pbf file and create graph.
+    Read pbf file and create graph with contraction hierarchy and landmarks.
     *
     @param filename: name of the pbf file to be read
+    @param landmarks: max. number of landmarks
     *
     @return Self: a new graph
     */
-    pub fn from_pbf(filename: &str) -> Self {
-        Pbf::new(filename).read()
+    pub fn from_pbf(filename: &str, landmarks: usize) -> Self {
+        let mut graph = Pbf::new(filename).read();
+        graph.contract();
+        graph.select_landmarks(landmarks);
+        graph
     }
 
     /**
-    Read binary file and create graph.
+    Create contraction hierarchy of graph.
+    *
+    @param self: graph
+    */
+    pub fn contract(&mut self) {
+        debug!("Contracting graph...");
+        self.hierarchy = hierarchy::contract(self);
+        debug!("Finished contracting graph");
+    }
+
+    /**
+    Select landmarks of graph and calculate costs from and to landmarks.
+    *
+    @param self: graph
+    @param count: max. number of landmarks
+    */
+    pub fn select_landmarks(&mut self, count: usize) {
+        debug!("Selecting landmarks...");
+        self.landmarks = landmarks::select(self, count);
+        debug!("Finished selecting landmarks");
+    }
+
+    /**
+    Read binary file and create graph. Nodes, edges and spatial index are mapped into memory and used
+    directly, so the file is shared by all processes reading it.
     *
     @param filename: name of the binary file to be read
+    @param pbf: (optional) name of the pbf file the graph must be created from
+    @param landmarks: requested number of landmarks the graph must be created with
     *
-    @return Self: a new graph
+    @return Result<Self, FormatError>: a new graph or error if the file is stale or corrupt
     */
-    pub fn from_bin(filename: &str) -> Self {
+    pub fn from_bin(filename: &str, pbf: Option<&str>, landmarks: usize) -> Result<Self, FormatError> {
         debug!("Reading graph from {}...", filename);
-        // open specified file
-        let file = File::open(filename).unwrap();
-        let reader = BufReader::new(file);
-        // deserialize graph from bin file
-        let graph: Self = bincode::deserialize_from(reader).unwrap();
+        flat::supported()?;
+        let file = File::open(filename)?;
+        let mut reader = BufReader::new(&file);
+        format::read_header(&mut reader, pbf, landmarks)?;
+        // lengths of tables, charging stations, turn restrictions and speeds are stored before the tables
+        let (lengths, charging_nodes, restrictions, max_speeds): ([usize; 18], _, _, _) =
+            bincode::deserialize_from(&mut reader)?;
+        let mut offset = reader.stream_position()? as usize;
+
+        let map = Arc::new(Mmap::new(&file)?);
+        let graph = Self {
+            nodes: Table::mapped(&map, &mut offset, lengths[0])?,
+            offsets: Table::mapped(&map, &mut offset, lengths[1])?,
+            edges: Table::mapped(&map, &mut offset, lengths[2])?,
+            reverse_offsets: Table::mapped(&map, &mut offset, lengths[3])?,
+            reverse_edges: Table::mapped(&map, &mut offset, lengths[4])?,
+            geometry_offsets: Table::mapped(&map, &mut offset, lengths[5])?,
+            geometry: Table::mapped(&map, &mut offset, lengths[6])?,
+            tree: KdTree {
+                indices: Table::mapped(&map, &mut offset, lengths[7])?,
+                edges: Table::mapped(&map, &mut offset, lengths[8])?,
+                boxes: Table::mapped(&map, &mut offset, lengths[9])?,
+            },
+            charging_nodes,
+            restrictions,
+            max_speeds,
+            hierarchy: Hierarchy {
+                levels: Table::mapped(&map, &mut offset, lengths[10])?,
+                links: Table::mapped(&map, &mut offset, lengths[11])?,
+                up_offsets: Table::mapped(&map, &mut offset, lengths[12])?,
+                up: Table::mapped(&map, &mut offset, lengths[13])?,
+                down_offsets: Table::mapped(&map, &mut offset, lengths[14])?,
+                down: Table::mapped(&map, &mut offset, lengths[15])?,
+            },
+            landmarks: Landmarks {
+                nodes: Table::mapped(&map, &mut offset, lengths[16])?,
+                distances: Table::mapped(&map, &mut offset, lengths[17])?,
+            },
+        };
+        if graph.offsets.len() != graph.nodes.len() + 1
+            || graph.offsets.last() != Some(&graph.edges.len())
+            || graph.reverse_offsets.len() != graph.offsets.len()
+            || graph.reverse_edges.len() != graph.edges.len() {
+            return Err(FormatError::Corrupt("offsets do not match edges"));
+        }
+        let nodes = graph.nodes.len();
+        if graph.edges.iter().any(|edge| edge.source_index >= nodes || edge.target_index >= nodes)
+            || graph.reverse_edges.iter().any(|edge| *edge >= graph.edges.len()) {
+            return Err(FormatError::Corrupt("edges do not match nodes"));
+        }
+        if graph.geometry_offsets.len() != graph.edges.len() + 1
+            || graph.geometry_offsets.last() != Some(&graph.geometry.len()) {
+            return Err(FormatError::Corrupt("geometry does not match edges"));
+        }
+        let tree = &graph.tree;
+        if tree.indices.len() != nodes || tree.indices.iter().any(|index| *index >= nodes)
+            || tree.edges.len() > graph.edges.len()
+            || tree.edges.iter().any(|position| *position >= graph.edges.len())
+            || tree.boxes.len() != tree.edges.len() * 2 {
+            return Err(FormatError::Corrupt("spatial index does not match nodes"));
+        }
+        let hierarchy = &graph.hierarchy;
+        if !hierarchy.up_offsets.is_empty() && (hierarchy.levels.len() != graph.nodes.len()
+            || hierarchy.up_offsets.len() != graph.offsets.len()
+            || hierarchy.down_offsets.len() != graph.offsets.len()
+            || hierarchy.up_offsets.last() != Some(&hierarchy.up.len())
+            || hierarchy.down_offsets.last() != Some(&hierarchy.down.len())) {
+            return Err(FormatError::Corrupt("hierarchy does not match nodes"));
+        }
+        if !graph.landmarks.fits(graph.nodes.len()) {
+            return Err(FormatError::Corrupt("landmarks do not match nodes"));
+        }
         debug!("Parsed {} nodes", graph.nodes.len());
         debug!("Parsed {} edges", graph.edges.len());
         debug!("Parsed {} charging stations", graph.charging_nodes.len());
+        debug!("Parsed {} turn restrictions", graph.restrictions.len());
         debug!("Finished reading graph from {}...", filename);
-        graph
+        Ok(graph)
     }
 
     /**
-    Save graph parsed from pbf to bin file.
+    Save graph parsed from pbf to bin file. The file is replaced at once, so processes which mapped
+    an existing file keep reading the old graph.
     *
     @param self: graph to be saved
     @param filename: name of file to be saved
+    @param pbf: name of the pbf file the graph was created from
+    @param landmarks: requested number of landmarks the graph was created with
+    *
+    @return Result<(), FormatError>: error if the file could not be written
     */
-    pub fn save(&self, filename: &str) {
+    pub fn save(&self, filename: &str, pbf: &str, landmarks: usize) -> Result<(), FormatError> {
         debug!("Writing graph to {}...", filename);
-        // create new file
-        let mut bin = File::create(filename).unwrap();
-        // serialize graph
-        let encoded = bincode::serialize(self).unwrap();
-        // write serialized graph to bin file
-        bin.write_all(&encoded).unwrap();
+        flat::supported()?;
+        let header = Header::of(pbf, landmarks)?;
+        // temporary file in the same directory, so it can be renamed to the graph file
+        let temporary = format!("{}.{}.tmp", filename, process::id());
+        if let Err(error) = self.write(&temporary, &header) {
+            let _ = fs::remove_file(&temporary);
+            return Err(error);
+        }
+        fs::rename(&temporary, filename)?;
         debug!("Wrote graph to {}", filename);
+        Ok(())
+    }
+
+    /**
+    Write graph to new bin file.
+    *
+    @param self: graph to be written
+    @param filename: name of file to be written
+    @param header: header of file
+    *
+    @return Result<(), FormatError>: error if the file could not be written
+    */
+    fn write(&self, filename: &str, header: &Header) -> Result<(), FormatError> {
+        let mut bytes = Vec::new();
+        format::write_header(&mut bytes, header)?;
+        let hierarchy = &self.hierarchy;
+        let lengths = [self.nodes.len(), self.offsets.len(), self.edges.len(),
+            self.reverse_offsets.len(), self.reverse_edges.len(),
+            self.geometry_offsets.len(), self.geometry.len(), self.tree.indices.len(),
+            self.tree.edges.len(), self.tree.boxes.len(),
+            hierarchy.levels.len(), hierarchy.links.len(), hierarchy.up_offsets.len(),
+            hierarchy.up.len(), hierarchy.down_offsets.len(), hierarchy.down.len(),
+            self.landmarks.nodes.len(), self.landmarks.distances.len()];
+        bincode::serialize_into(&mut bytes, &(lengths, &self.charging_nodes, &self.restrictions, &self.max_speeds))?;
+
+        let mut writer = BufWriter::new(File::create(filename)?);
+        writer.write_all(&bytes)?;
+        let mut position = bytes.len();
+        // tables are written in their in-memory layout
+        self.nodes.write(&mut writer, &mut position)?;
+        self.offsets.write(&mut writer, &mut position)?;
+        self.edges.write(&mut writer, &mut position)?;
+        self.reverse_offsets.write(&mut writer, &mut position)?;
+        self.reverse_edges.write(&mut writer, &mut position)?;
+        self.geometry_offsets.write(&mut writer, &mut position)?;
+        self.geometry.write(&mut writer, &mut position)?;
+        self.tree.indices.write(&mut writer, &mut position)?;
+        self.tree.edges.write(&mut writer, &mut position)?;
+        self.tree.boxes.write(&mut writer, &mut position)?;
+        hierarchy.levels.write(&mut writer, &mut position)?;
+        hierarchy.links.write(&mut writer, &mut position)?;
+        hierarchy.up_offsets.write(&mut writer, &mut position)?;
+        hierarchy.up.write(&mut writer, &mut position)?;
+        hierarchy.down_offsets.write(&mut writer, &mut position)?;
+        hierarchy.down.write(&mut writer, &mut position)?;
+        self.landmarks.nodes.write(&mut writer, &mut position)?;
+        self.landmarks.distances.write(&mut writer, &mut position)?;
+        writer.flush()?;
+        Ok(())
     }
 
     /**
@@ -148,8 +359,159 @@ impl Graph {
         let end = self.offsets[node_index + 1];
         &self.edges[start..end]
     }
+
+    /**
+    Get incoming edges of node of graph.
+    *
+    @param self: graph
+    @param node_index: index of node
+    *
+    @return iterator over edges with node as target
+    */
+    pub fn incoming_edges(&self, node_index: usize) -> impl Iterator<Item=&Edge> {
+        let start = self.reverse_offsets[node_index];
+        let end = self.reverse_offsets[node_index + 1];
+        self.reverse_edges[start..end].iter().map(move |position| &self.edges[*position])
+    }
+
+    /**
+    Get edge of graph by its position.
+    *
+    @param self: graph
+    @param position: position of edge in edges
+    *
+    @return &Edge: reference of edge
+    */
+    pub fn edge(&self, position: usize) -> &Edge {
+        &self.edges[position]
+    }
+
+    /**
+    Get position of edge of graph.
+    *
+    @param self: graph
+    @param edge: reference of edge within edges of graph
+    *
+    @return position of edge in edges
+    */
+    pub fn position(&self, edge: &Edge) -> usize {
+        let offset = edge as *const Edge as usize - self.edges.as_ptr() as usize;
+        offset / std::mem::size_of::<Edge>()
+    }
+
+    /**
+    Get intermediate coordinates of edge, i.e. of nodes of a merged chain.
+    *
+    @param self: graph
+    @param position: position of edge in edges
+    *
+    @return &[Coordinates]: intermediate coordinates in direction of edge
+    */
+    pub fn geometry(&self, position: usize) -> &[Coordinates] {
+        &self.geometry[self.geometry_offsets[position]..self.geometry_offsets[position + 1]]
+    }
+
+    /**
+    Get max. speed of a transportation mode in graph, no edge can be traveled faster.
+    *
+    @param self: graph
+    @param mode: transportation mode (car/bike)
+    *
+    @return (optional) max. speed, none if no edge allows transportation mode
+    */
+    pub fn max_speed(&self, mode: Transport) -> Option<Kmh> {
+        self.max_speeds.iter()
+            .find(|(transport, _)| *transport == mode)
+            .map(|(_, speed)| *speed)
+    }
+
+    /**
+    Get lower bound of time in milliseconds of a transportation mode to travel a linear distance in
+    graph. Distances and times in ms of edges are rounded up, so they add up to at least the bound.
+    *
+    @param self: graph
+    @param mode: transportation mode (car/bike)
+    @param distance: linear distance
+    *
+    @return lower bound of time in ms, 0 if no edge allows transportation mode
+    */
+    pub fn min_millis(&self, mode: Transport, distance: u32) -> u32 {
+        self.max_speed(mode).map_or(0, |speed| speed.min_millis(distance))
+    }
+
+    /**
+    Get contraction hierarchy of graph.
+    *
+    @param self: graph
+    *
+    @return &Hierarchy: reference of hierarchy, empty if graph is not contracted
+    */
+    pub fn hierarchy(&self) -> &Hierarchy {
+        &self.hierarchy
+    }
+
+    /**
+    Get landmarks of graph.
+    *
+    @param self: graph
+    *
+    @return &Landmarks: reference of landmarks, empty if not selected
+    */
+    pub fn landmarks(&self) -> &Landmarks {
+        &self.landmarks
+    }
 }
 
+/**
+Get max. speeds of transportation modes in graph, i.e. the max. speed limit of all edges of cars and
+the constant speed of bikes.
+*
+@param edges: edges of graph
+*
+@return max. speed of car and bike, modes without any edge are omitted
+*/
+fn max_speeds(edges: &[Edge]) -> Vec<(Transport, Kmh)> {
+    let mut max_speeds = Vec::new();
+    let car = edges.iter()
+        .filter(|edge| edge.transport.contains(Car))
+        .map(|edge| edge.max_speed.speed)
+        .max();
+    if let Some(speed) = car {
+        max_speeds.push((Car, Kmh::new(speed)));
+    }
+    if edges.iter().any(|edge| edge.transport.contains(Bike)) {
+        max_speeds.push((Bike, BIKE_SPEED));
+    }
+    max_speeds
+}
+
+/**
+Create reverse adjacency of graph, i.e. incoming edges of all nodes.
+*
+@param number_nodes: number of nodes of graph
+@param edges: edges of graph sorted by source node
+*
+@return offsets of incoming edges and positions of incoming edges in edges
+*/
+fn reverse(number_nodes: usize, edges: &[Edge]) -> (Vec<usize>, Vec<usize>) {
+    let mut offsets = vec![0; number_nodes + 1];
+    for edge in edges {
+        offsets[edge.target_index + 1] += 1;
+    }
+    for i in 1..offsets.len() {
+        offsets[i] += offsets[i - 1];
+    }
+    // place positions of edges at next free slot of their target node
+    let mut next = offsets.clone();
+    let mut positions = vec![0; edges.len()];
+    for (position, edge) in edges.iter().enumerate() {
+        positions[next[edge.target_index]] = position;
+        next[edge.target_index] += 1;
+    }
+    (offsets, positions)
+}
+
+#[repr(C)]
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Node {
     pub id: i64,
@@ -178,6 +540,17 @@ pub struct ChargingNode {
     pub id: i64,
     pub coordinates: Coordinates,
     pub charging_options: ChargingOptions,
+    // charging power in kW
+    pub power: f32,
+    pub sockets: Vec<Socket>,
+    // number of vehicles which can be charged at the same time
+    pub capacity: Option<u32>,
+    pub operator: Option<String>,
+    pub network: Option<String>,
+    pub fee: Option<bool>,
+    // supported authentication methods, e.g. app or membership_card
+    pub authentication: Vec<String>,
+    pub opening_hours: Option<String>,
 }
 
 impl ChargingNode {
@@ -187,23 +560,99 @@ impl ChargingNode {
     @param id: id of charging station node
     @param coordinates: coordinates of charging station
     @charging_options: ChargingOptions object specifiying whether charging station is valid for e-bike/e-car/both
+    @power: charging power in kW
     *
     @return Self: charging station node
     */
-    pub fn new(id: i64, coordinates: Coordinates, charging_options: ChargingOptions) -> Self {
+    pub fn new(id: i64, coordinates: Coordinates, charging_options: ChargingOptions, power: f32) -> Self {
         Self {
             id,
             coordinates,
             charging_options,
+            power,
+            sockets: Vec::new(),
+            capacity: None,
+            operator: None,
+            network: None,
+            fee: None,
+            authentication: Vec::new(),
+            opening_hours: None,
+        }
+    }
+
+    /**
+    Check if charging station supports one of the sockets of a vehicle.
+    *
+    @param self: charging station node
+    @param sockets: sockets of vehicle, empty if any socket is supported
+    *
+    @return true if vehicle is able to charge, or sockets of charging station are unknown
+    */
+    pub fn supports(&self, sockets: &[SocketType]) -> bool {
+        sockets.is_empty() || self.sockets.is_empty()
+            || self.sockets.iter().any(|socket| sockets.contains(&socket.kind))
+    }
+
+    /**
+    Get charging power of charging station for sockets of a vehicle.
+    *
+    @param self: charging station node
+    @param sockets: sockets of vehicle, empty if any socket is supported
+    *
+    @return (optional) charging power in kW, none if vehicle is not able to charge
+    */
+    pub fn power_for(&self, sockets: &[SocketType]) -> Option<f32> {
+        if !self.supports(sockets) {
+            return None;
+        }
+        let compatible = self.sockets.iter()
+            .filter(|socket| sockets.is_empty() || sockets.contains(&socket.kind))
+            .map(|socket| socket.power());
+        max_power(compatible).or(Some(self.power))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnRestriction {
+    pub from_index: usize,
+    pub via_index: usize,
+    pub to_index: usize,
+    pub kind: RestrictionKind,
+    pub transport: Transport,
+}
+
+impl TurnRestriction {
+    /**
+    Create new turn restriction between nodes of graph.
+    *
+    @param from_index: index of node the turn starts at
+    @param via_index: index of node the turn is made at
+    @param to_index: index of node the turn ends at
+    @param kind: kind of restriction (no/only)
+    @param transport: transportation modes the restriction applies to
+    *
+    @return Self: turn restriction
+    */
+    pub fn new(from_index: usize, via_index: usize, to_index: usize,
+               kind: RestrictionKind, transport: Transport) -> Self {
+        Self {
+            from_index,
+            via_index,
+            to_index,
+            kind,
+            transport,
         }
     }
 }
 
+#[repr(C)]
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Edge {
     pub source_index: usize,
     pub target_index: usize,
     pub transport: Transport,
+    // true if cars may only use edge to reach a destination along it
+    pub destination: bool,
     pub distance: u32,
     pub max_speed: Kmh,
 }
@@ -226,10 +675,25 @@ impl Edge {
             source_index,
             target_index,
             transport,
+            destination: false,
             distance,
             max_speed,
         }
     }
+    /**
+    Create part of an edge, e.g. between a node and a point on the edge.
+    *
+    @param self: edge
+    @param fraction: fraction of the edge
+    *
+    @return Edge: edge with fraction of distance
+    */
+    pub fn partial(&self, fraction: f64) -> Self {
+        let mut partial = self.clone();
+        partial.distance = (f64::from(self.distance) * fraction).round() as u32;
+        partial
+    }
+
     /**
     Cost function of an edge.
     *
@@ -240,12 +704,19 @@ impl Edge {
     @return u32: cost of edge
     */
     pub fn cost(&self, mode: Transport, routing: Routing) -> u32 {
-        // if routing mode is car and routing is for time, cost of edge is time needed for distance
-        if mode == Car && routing == Time {
-            self.max_speed.time(self.distance)
+        // if routing mode is car and routing is for time, cost of edge is time in ms needed for distance
+        let cost = if mode == Car && routing == Time {
+            self.millis(Car)
         } else {
             // Bike and Walk are assumed to have constant speed
             self.distance
+        };
+        // through traffic on edges for destination traffic is penalized, so they are only used to save
+        // a long detour or if there is no other way, partial edges at start and goal are not penalized
+        if mode == Car && self.destination {
+            cost.saturating_mul(DESTINATION_PENALTY)
+        } else {
+            cost
         }
     }
 
@@ -261,11 +732,40 @@ impl Edge {
         match mode {
             // calc time with max speed of car on edge
             Car => self.max_speed.time(self.distance),
-            // for bike assume constant speed of 20 kmh
-            Bike => Kmh::new(20).time(self.distance),
+            // for bike assume constant speed
+            Bike => BIKE_SPEED.time(self.distance),
             _ => panic!("Unsupported transport mode")
         }
     }
+
+    /**
+    Time function of an edge in milliseconds, rounded up so times of consecutive edges are not
+    underestimated.
+    *
+    @param self: edge
+    @param mode: transportation mode (bike or car)
+    *
+    @return u32: time in ms needed to travel along distance of edge with max speed
+    */
+    pub fn millis(&self, mode: Transport) -> u32 {
+        match mode {
+            Car => self.max_speed.millis(self.distance),
+            Bike => BIKE_SPEED.millis(self.distance),
+            _ => panic!("Unsupported transport mode")
+        }
+    }
+
+    /**
+    Energy function of an edge.
+    *
+    @param self: edge
+    @param mode: transportation mode (bike or car)
+    *
+    @return f32: energy in Wh needed to travel along distance of edge with max speed
+    */
+    pub fn energy(&self, mode: Transport) -> f32 {
+        vehicle::consumption(self.max_speed, mode) * self.distance as f32 / 1000.0
+    }
 }
 
 impl Ord for Edge {
@@ -299,15 +799,97 @@ impl PartialOrd for Edge {
 
 #[cfg(test)]
 mod tests {
+    use std::fs;
     use std::time::Instant;
 
-    use crate::graph::Graph;
+    use stable_vec::StableVec;
+
+    use crate::graph::{ChargingNode, Edge, Graph, Node};
+    use crate::graph::format::FormatError;
+    use crate::graph::landmarks::LANDMARKS;
+    use crate::osm::Coordinates;
+    use crate::osm::highway::Kmh;
+    use crate::osm::options::ChargingOptions;
+    use crate::osm::pbf::Pbf;
+    use crate::osm::options::Routing::Time;
+    use crate::osm::options::Transport::{All, Bike, Car};
+
+    #[test]
+    fn binary_file() {
+        let mut nodes = StableVec::new();
+        nodes.push(Node::new(10, Coordinates::new(480000000, 90000000)));
+        nodes.push(Node::new(11, Coordinates::new(480100000, 90000000)));
+        nodes.push(Node::new(12, Coordinates::new(485000000, 95000000)));
+        let edges = vec![
+            Edge::new(0, 1, All, 1_000, Kmh::new(50)),
+            Edge::new(1, 0, Car, 1_000, Kmh::new(50)),
+            Edge::new(1, 2, Bike, 70_000, Kmh::new(30)),
+        ];
+        let offsets = vec![0, 1, 3, 3];
+        // edge to node 12 is a merged chain
+        let chain = vec![Coordinates::new(482000000, 92000000), Coordinates::new(484000000, 94000000)];
+        let geometry = vec![Vec::new(), Vec::new(), chain.clone()];
+        let charging = ChargingNode::new(20, Coordinates::new(480100000, 90000000), ChargingOptions::Car, 50.0);
+        let mut graph = Graph::new(nodes, offsets, edges, geometry, vec![charging], Vec::new());
+        graph.contract();
+        graph.select_landmarks(LANDMARKS);
+
+        let filename = std::env::temp_dir().join("emaps-graph-test.bin");
+        let filename = filename.to_str().unwrap();
+        graph.save(filename, "Cargo.toml", LANDMARKS).unwrap();
+        let mapped = Graph::from_bin(filename, Some("Cargo.toml"), LANDMARKS).unwrap();
+        match Graph::from_bin(filename, Some("Cargo.lock"), LANDMARKS) {
+            Err(FormatError::Checksum) => {}
+            other => panic!("expected checksum error, got {:?}", other)
+        }
+        // graph is rebuilt if another number of landmarks is requested, even if fewer were selected
+        assert!(graph.landmarks().nodes.len() < LANDMARKS);
+        match Graph::from_bin(filename, Some("Cargo.toml"), LANDMARKS + 1) {
+            Err(FormatError::Parameters) => {}
+            other => panic!("expected parameters error, got {:?}", other)
+        }
+        // saving again replaces the file, the mapped graph keeps the old one
+        graph.save(filename, "Cargo.toml", LANDMARKS).unwrap();
+        let temporary = format!("{}.{}.tmp", filename, std::process::id());
+        assert!(!std::path::Path::new(&temporary).exists());
+        fs::remove_file(filename).unwrap();
+
+        for i in 0..3 {
+            assert_eq!(graph.node(i).id, mapped.node(i).id);
+            assert_eq!(graph.edges(i), mapped.edges(i));
+            assert!(graph.incoming_edges(i).eq(mapped.incoming_edges(i)));
+        }
+        let incoming: Vec<usize> = mapped.incoming_edges(0).map(|edge| edge.source_index).collect();
+        assert_eq!(vec![1], incoming);
+        assert_eq!(Some(0), mapped.incoming_edges(1).map(|edge| edge.source_index).next());
+        assert_eq!(12, mapped.node(2).id);
+        assert!(mapped.geometry(0).is_empty());
+        assert_eq!(&chain[..], mapped.geometry(2));
+        assert_eq!(1, mapped.get_charging_stations().len());
+        let coordinates = Coordinates::new(480090000, 90000000);
+        assert_eq!(graph.nearest_neighbor(&coordinates, Car), mapped.nearest_neighbor(&coordinates, Car));
+        assert_eq!(Ok(1), mapped.nearest_neighbor(&coordinates, Car));
+        assert!(mapped.hierarchy().supports(Car, Time));
+        assert_eq!(graph.hierarchy().query(&[(0, 0)], &[(1, 0)]), mapped.hierarchy().query(&[(0, 0)], &[(1, 0)]));
+        assert_eq!(Some((vec![1], 1, 0)), mapped.hierarchy().query(&[(1, 0)], &[(0, 0)]));
+        // only edge from node 0 to node 1 gives exact bound
+        let cost = Edge::new(0, 1, Car, 1_000, Kmh::new(50)).cost(Car, Time);
+        assert_eq!(cost, mapped.landmarks().heuristic(Car, Time, 0, 1));
+        assert_eq!(graph.landmarks().heuristic(Car, Time, 0, 1), mapped.landmarks().heuristic(Car, Time, 0, 1));
+        assert_eq!(Some(Kmh::new(50)), mapped.max_speed(Car));
+        assert_eq!(graph.max_speed(Bike), mapped.max_speed(Bike));
+    }
 
     #[test]
     fn parse_germany() {
         let now = Instant::now();
-        let graph = Graph::from_pbf("germany-latest.osm.pbf");
-        graph.save("germany-latest.bin");
+        let mut graph = Pbf::new("germany-latest.osm.pbf").read();
+        // budget of the streamed import alone, preprocessing follows below
+        let import = now.elapsed().as_secs() / 60;
+        assert!(import < 5);
+        graph.contract();
+        graph.select_landmarks(LANDMARKS);
+        graph.save("germany-latest.bin", "germany-latest.osm.pbf", LANDMARKS).unwrap();
         let mins = now.elapsed().as_secs() / 60;
         assert!(mins < 10);
     }
@@ -1,24 +1,32 @@
 use std::cmp::Ordering;
-use std::collections::BinaryHeap;
+use std::collections::{BinaryHeap, HashMap};
 
 use stable_vec::StableVec;
 
 use log::debug;
 
 use crate::graph::{Edge, Graph};
+use crate::graph::tree::Part;
 use crate::osm::Coordinates;
-use crate::osm::options::{Routing, Transport, ChargingOptions};
+use crate::osm::options::{Routing, Search, Transport};
 use crate::osm::options::Routing::Time;
 use crate::osm::options::Transport::Car;
 
+// cost and edge to reach a node in bidirectional search
+type Labels<'a> = HashMap<usize, (u32, Option<&'a Edge>)>;
+
 pub struct Router<'a> {
     graph: &'a Graph,
     mode: Transport,
     routing: Routing,
+    // search strategy, unidirectional unless selected otherwise
+    pub search: Search,
 
+    // state of unidirectional search, allocated by the first search and reset after each search
     queue: BinaryHeap<RouterNode>,
     cost: Vec<u32>,
     prev: StableVec<&'a Edge>,
+    touched: Vec<usize>,
 }
 
 impl<'a> Router<'a> {
@@ -36,10 +44,12 @@ impl<'a> Router<'a> {
             graph,
             mode,
             routing,
+            search: Search::Unidirectional,
 
-            queue: BinaryHeap::with_capacity(graph.nodes.len()),
-            cost: vec![u32::max_value(); graph.nodes.len()],
-            prev: StableVec::with_capacity(graph.nodes.len()),
+            queue: BinaryHeap::new(),
+            cost: Vec::new(),
+            prev: StableVec::new(),
+            touched: Vec::new(),
         }
     }
 
@@ -52,33 +62,125 @@ impl<'a> Router<'a> {
     *
     @return Result<Route, &str>: result object of shortest path routing
     */
-    pub fn shortest_path(&mut self, start: &Coordinates, goal: &Coordinates) -> Result<Route, &str> {
-        // retrieve start index based on nearest neighbor of start coordinates in graph
-        let start_index = self.graph.nearest_neighbor(start, self.mode)?;
-        let start_id = self.graph.node(start_index).id;
-        // retrieve goal index based on nearest neighbor of goal coordinates in graph
-        let goal_index = self.graph.nearest_neighbor(goal, self.mode)?;
-        let goal_id = self.graph.node(goal_index).id;
-        if start_id == goal_id {
-            return Err("No path found, start is goal");
-        }
-
-        self.cost[start_index] = 0;
-        // push start node to queue of router
-        self.queue.push(RouterNode::new(start_index, 0, 0));
+    pub fn shortest_path(&mut self, start: &Coordinates, goal: &Coordinates) -> Result<Route, &'static str> {
+        // project start and goal coordinates onto nearest edge segments of graph
+        let start = self.graph.snap(start, self.mode)?;
+        let goal = self.graph.snap(goal, self.mode)?;
+        let leaving = start.leaving(self.graph);
+        let entering = goal.entering(self.graph);
+        // cost and route of each candidate
+        let mut routes = Vec::new();
+
+        for (first, (position, start_fraction, start_split)) in leaving.iter().zip(&start.edges) {
+            for (last, (goal_position, goal_fraction, goal_split)) in entering.iter().zip(&goal.edges) {
+                // start and goal on same edge, goal is ahead of start
+                if position == goal_position && goal_fraction >= start_fraction {
+                    let (from, to) = ((*start_fraction, *start_split), (*goal_fraction, *goal_split));
+                    let part = self.graph.part(*position, first.index, from, to);
+                    let ends = Ends::new(start.coordinates.clone(), part,
+                                         goal.coordinates.clone(), self.graph.part(*position, last.index, to, to));
+                    let cost = ends.first.edge.cost(self.mode, self.routing);
+                    let mut route = Route::new(Vec::new(), 0, 0, 0.0, None);
+                    route.extend(&ends, self.mode);
+                    routes.push((cost, route));
+                }
+            }
+        }
+        // one search from all nodes reached from start to all nodes goal is reached from, the
+        // partial edges add their cost at both ends of the search
+        let sources = self.seeds(&leaving);
+        let goals = self.seeds(&entering);
+        if let Ok(mut path) = self.path(&sources, &goals) {
+            if path.route.path.is_empty() {
+                // edges of start and goal meet at a node
+                path.route.path.push(self.graph.coordinates(path.start_index).clone());
+            }
+            let ends = Ends::new(start.coordinates.clone(), self.cheapest(&leaving, path.start_index),
+                                 goal.coordinates.clone(), self.cheapest(&entering, path.goal_index));
+            path.route.extend(&ends, self.mode);
+            routes.push((path.cost, path.route));
+        }
+        let (_, route) = routes.into_iter()
+            .min_by_key(|(cost, _)| *cost)
+            .ok_or("No path found")?;
+        debug!("Distance of calculated route is {}.", &route.distance);
+        Ok(route)
+    }
+
+    /**
+    Nodes at the ends of partial edges of projected coordinates with the cost of the partial edges.
+    *
+    @param self: router
+    @param parts: partial edges leaving or entering projected coordinates
+    *
+    @return index of node and cost of partial edge for each partial edge
+    */
+    fn seeds(&self, parts: &[Part]) -> Vec<(usize, u32)> {
+        parts.iter()
+            .map(|part| (part.index, part.edge.cost(self.mode, self.routing)))
+            .collect()
+    }
+
+    /**
+    Cheapest of the partial edges ending at a node.
+    *
+    @param self: router
+    @param parts: partial edges leaving or entering projected coordinates
+    @param index: index of node
+    *
+    @return cheapest partial edge with node at its other end
+    */
+    fn cheapest(&self, parts: &[Part], index: usize) -> Part {
+        parts.iter()
+            .filter(|part| part.index == index)
+            .min_by_key(|part| part.edge.cost(self.mode, self.routing))
+            .unwrap()
+            .clone()
+    }
+
+    /**
+    Shortest path algorithm between nodes of graph with selected search strategy. The search starts
+    at all sources with their initial cost and the path ends at the goal with the smallest total
+    cost including the final cost of the goal.
+    *
+    @param self: router
+    @param sources: indices of start nodes with initial cost
+    @param goals: indices of goal nodes with final cost
+    *
+    @return Result<Path, &str>: path with smallest total cost
+    */
+    fn path(&mut self, sources: &[(usize, u32)], goals: &[(usize, u32)]) -> Result<Path, &'static str> {
+        if self.search == Search::Bidirectional {
+            return self.bidirectional(sources, goals);
+        }
+        if self.search == Search::Hierarchy && self.graph.hierarchy().supports(self.mode, self.routing) {
+            return self.hierarchy(sources, goals);
+        }
+
+        self.reset();
+        // push start nodes to queue of router
+        for (index, cost) in sources {
+            if *cost < self.cost[*index] {
+                self.cost[*index] = *cost;
+                self.touched.push(*index);
+                self.queue.push(RouterNode::new(*index, *cost, self.estimate(*index, goals)));
+            }
+        }
+        // goal and cost of best path found so far
+        let mut best: Option<(usize, u32)> = None;
         // while still a node in the queue
         while let Some(node) = self.queue.pop() {
-            // get id of current node in queue and check if equals goal id
-            let id = self.graph.node(node.index).id;
-            if id == goal_id {
-                let route = self.backtrack_path(start_index, node.index);
-                debug!("Distance of calculated route is {}.", &route.distance);
-                return Ok(route);
-            }
             // better solution already found
             if node.cost > self.cost[node.index] {
                 continue;
             }
+            // no path via unsettled nodes can be shorter than best path
+            if best.is_some_and(|(_, cost)| node.priority() >= cost) {
+                break;
+            }
+            for (_, remaining) in goals.iter().filter(|(index, _)| *index == node.index) {
+                update_best(&mut best, node.index, node.cost, Some(*remaining));
+            }
             // iterate over edges of current node
             for edge in self.graph.edges(node.index) {
                 // check if edge is valid for transportation method
@@ -88,138 +190,265 @@ impl<'a> Router<'a> {
 
                 let cost = node.cost + edge.cost(self.mode, self.routing);
                 if cost < self.cost[edge.target_index] {
-                    let heuristic = self.heuristic(edge.target_index, goal_index);
+                    let heuristic = self.estimate(edge.target_index, goals);
                     // create new router node with current edge, cost and heuristic
                     let next = RouterNode::new(edge.target_index, cost, heuristic);
                     self.prev.insert(next.index, edge);
                     self.cost[next.index] = next.cost;
+                    self.touched.push(next.index);
                     self.queue.push(next);
                 }
             }
         }
-        Err("No path found")
+
+        let (goal_index, cost) = best.ok_or("No path found")?;
+        // start nodes are the only nodes without previous edge
+        let mut edges = Vec::new();
+        let mut index = goal_index;
+        while let Some(edge) = self.prev.get(index) {
+            edges.push(*edge);
+            index = edge.source_index;
+        }
+        edges.reverse();
+        Ok(Path::new(self.collect_route(&edges), index, goal_index, cost))
     }
 
     /**
-    Shortest path calculation from original start to a charging station.
+    Bidirectional shortest path algorithm between nodes of graph. Searches forward from the sources
+    and backward from the goals until the sum of both smallest costs exceeds the best path found.
     *
     @param self: router
-    @param actual_start: original start as chosen by user in frontend
-    @param actual_goal: original goal as chosen by user in frontend
-    @param current_range: current range of electric vehicle
+    @param sources: indices of start nodes with initial cost
+    @param goals: indices of goal nodes with final cost
     *
-    @return calculated route from original start to charging station
+    @return Result<Path, &str>: path with smallest total cost
     */
-    pub fn calc_route_with_charging_station(&mut self, actual_start: &Coordinates, actual_goal: &Coordinates, current_range: &u32) -> Result<Route, &str> {
-        // retrieve "optimal" charging station coordinates
-        let coords_of_chosen_charging =
-            self.get_optimal_charging_station_coords(actual_start, actual_goal, current_range.clone());
-        // get nearest neighbor of charging station coordinates in graph
-        let nearest_neighbor = self.graph.nearest_neighbor(&coords_of_chosen_charging, self.mode)?;
-        // get coordinates of nearest neighbor as in graph
-        let nearest_neighbor_coords = &self.graph.node(nearest_neighbor).coordinates;
-        // calc shortest path from actual start to charging station
-        let route = self.shortest_path(actual_start, nearest_neighbor_coords);
-        route
+    fn bidirectional(&mut self, sources: &[(usize, u32)], goals: &[(usize, u32)]) -> Result<Path, &'static str> {
+        let mut forward: Labels = HashMap::new();
+        let mut backward: Labels = HashMap::new();
+        let mut forward_queue = BinaryHeap::new();
+        let mut backward_queue = BinaryHeap::new();
+        for (labels, queue, seeds) in [(&mut forward, &mut forward_queue, sources), (&mut backward, &mut backward_queue, goals)] {
+            for (index, cost) in seeds {
+                if *cost < labels.get(index).map_or(u32::MAX, |label| label.0) {
+                    labels.insert(*index, (*cost, None));
+                    queue.push(RouterNode::new(*index, *cost, 0));
+                }
+            }
+        }
+        // node both searches met at and cost of best path found so far
+        let mut best: Option<(usize, u32)> = None;
+        for (index, (cost, _)) in &forward {
+            update_best(&mut best, *index, *cost, backward.get(index).map(|label| label.0));
+        }
+
+        loop {
+            let forward_min = forward_queue.peek().map_or(u32::MAX, |node: &RouterNode| node.cost);
+            let backward_min = backward_queue.peek().map_or(u32::MAX, |node: &RouterNode| node.cost);
+            if forward_min == u32::MAX && backward_min == u32::MAX {
+                break;
+            }
+            // no path via unsettled nodes can be shorter than best path
+            if let Some((_, cost)) = best {
+                if forward_min.saturating_add(backward_min) >= cost {
+                    break;
+                }
+            }
+            // expand search with smaller cost
+            if forward_min <= backward_min {
+                let node = forward_queue.pop().unwrap();
+                if node.cost > forward[&node.index].0 {
+                    continue;
+                }
+                for edge in self.graph.edges(node.index) {
+                    if !edge.transport.contains(self.mode) {
+                        continue;
+                    }
+                    let cost = node.cost + edge.cost(self.mode, self.routing);
+                    let target = edge.target_index;
+                    if cost >= forward.get(&target).map_or(u32::MAX, |label| label.0) {
+                        continue;
+                    }
+                    forward.insert(target, (cost, Some(edge)));
+                    forward_queue.push(RouterNode::new(target, cost, 0));
+                    update_best(&mut best, target, cost, backward.get(&target).map(|label| label.0));
+                }
+            } else {
+                let node = backward_queue.pop().unwrap();
+                if node.cost > backward[&node.index].0 {
+                    continue;
+                }
+                for edge in self.graph.incoming_edges(node.index) {
+                    if !edge.transport.contains(self.mode) {
+                        continue;
+                    }
+                    let cost = node.cost + edge.cost(self.mode, self.routing);
+                    let source = edge.source_index;
+                    if cost >= backward.get(&source).map_or(u32::MAX, |label| label.0) {
+                        continue;
+                    }
+                    backward.insert(source, (cost, Some(edge)));
+                    backward_queue.push(RouterNode::new(source, cost, 0));
+                    update_best(&mut best, source, cost, forward.get(&source).map(|label| label.0));
+                }
+            }
+        }
+
+        let (meeting_index, cost) = best.ok_or("No path found")?;
+        // edges from start to meeting node, then from meeting node to goal
+        let mut edges = Vec::new();
+        let mut start_index = meeting_index;
+        while let Some((_, Some(edge))) = forward.get(&start_index) {
+            edges.push(*edge);
+            start_index = edge.source_index;
+        }
+        edges.reverse();
+        let mut goal_index = meeting_index;
+        while let Some((_, Some(edge))) = backward.get(&goal_index) {
+            edges.push(*edge);
+            goal_index = edge.target_index;
+        }
+        Ok(Path::new(self.collect_route(&edges), start_index, goal_index, cost))
     }
 
     /**
-    Get optimal charging station coordinates.
+    Shortest path query on contraction hierarchy of graph, shortcuts are unpacked to the edges
+    of the graph.
     *
     @param self: router
-    @param actual_start: original start as chosen by user in frontend
-    @param actual_goal: original goal as chosen by user in frontend
-    @param current_range: current range of electric vehicle
+    @param sources: indices of start nodes with initial cost
+    @param goals: indices of goal nodes with final cost
     *
-    @return coordinates of charging station based on original start, goal, and current range
+    @return Result<Path, &str>: path with smallest total cost
     */
-    pub fn get_optimal_charging_station_coords(&self, actual_start: &Coordinates, actual_goal: &Coordinates, current_range: u32) -> Coordinates {
-        let mut global_dist_sum = u32::max_value();
-        let mut charging_coords = actual_start;
-        // get required charging station mode based on mode, e.g. for e-car or e-bike
-        let required_charging = ChargingOptions::from(self.mode);
-
-        // iterate over all charging stations
-        for charging_node in &self.graph.charging_nodes {
-            // check if charging station supports required charging mode
-            if charging_node.charging_options.contains(required_charging) {
-                let dist_from_start = actual_start.distance(&charging_node.coordinates);
-
-
-                // assumption: dijkstra route distance is not bigger than 1,35 * haversine distance
-                if f64::from(dist_from_start) * 1.35 <= f64::from(current_range) {
-                    // we want to utilize at least 50% of current range
-                    if f64::from(dist_from_start) >= (f64::from(current_range) * 0.5) {
-                        let dist_to_goal = actual_goal.distance(&charging_node.coordinates);
-                        let dist_sum = dist_from_start + dist_to_goal;
-                        if f64::from(dist_sum) < f64::from(global_dist_sum) {
-                            global_dist_sum = dist_sum;
-                            charging_coords = &charging_node.coordinates;
-                        }
-                    }
-                }
-            }
-        }
-        charging_coords.clone()
+    fn hierarchy(&mut self, sources: &[(usize, u32)], goals: &[(usize, u32)]) -> Result<Path, &'static str> {
+        let (positions, start_index, goal_index) = self.graph.hierarchy().query(sources, goals)
+            .ok_or("No path found")?;
+        let edges: Vec<&Edge> = positions.iter().map(|position| self.graph.edge(*position)).collect();
+        // initial and final cost of path are the cheapest of start and goal node
+        let seed = |seeds: &[(usize, u32)], index: usize| seeds.iter()
+            .filter(|(seed, _)| *seed == index)
+            .map(|(_, cost)| *cost)
+            .min()
+            .unwrap_or(0);
+        let cost = edges.iter().map(|edge| edge.cost(self.mode, self.routing)).sum::<u32>()
+            + seed(sources, start_index) + seed(goals, goal_index);
+        Ok(Path::new(self.collect_route(&edges), start_index, goal_index, cost))
     }
 
     /**
-    Shortest path backtracking.
+    Create route from edges of path.
     *
     @param self: router
-    @param start_index: index of start node
-    @param goal_index: index of goal node
+    @param edges: edges of path from start to goal
     *
-    @return final route for shortest path
+    @return route along edges
     */
-    fn backtrack_path(&mut self, start_index: usize, goal_index: usize) -> Route {
-        let mut path = Vec::new();
+    pub fn collect_route(&self, edges: &[&Edge]) -> Route {
+        let mut path = Vec::with_capacity(edges.len() + 1);
+        let mut positions = Vec::with_capacity(edges.len());
         let mut time = 0;
         let mut distance = 0;
-        let mut edge = self.prev[goal_index];
-
-        loop {
-            // increment distance and time of route based on edge
+        let mut energy = 0.0;
+        // path starts at goal
+        for edge in edges.iter().rev() {
             distance += edge.distance;
             time += edge.time(self.mode);
-
-            // add coordinates to path
+            energy += edge.energy(self.mode);
             path.push(self.graph.coordinates(edge.target_index).clone());
+            path.extend(self.graph.geometry(self.graph.position(edge)).iter().rev().cloned());
+            positions.push(self.graph.position(edge));
+        }
+        if let Some(edge) = edges.first() {
+            path.push(self.graph.coordinates(edge.source_index).clone());
+        }
+        let mut route = Route::new(path, time, distance, energy, None);
+        route.edges = positions;
+        route
+    }
 
-            edge = self.prev[edge.source_index];
-            if edge.source_index == start_index {
-                break;
-            }
+    /**
+    Reset state of unidirectional search, so router can be used for another route. State is
+    allocated by the first search, afterwards only nodes touched by the last search are reset.
+    *
+    @param self: router
+    */
+    fn reset(&mut self) {
+        self.queue.clear();
+        if self.cost.is_empty() {
+            self.cost = vec![u32::MAX; self.graph.nodes.len()];
+            self.prev.reserve_exact(self.graph.nodes.len());
+        }
+        for index in self.touched.drain(..) {
+            self.cost[index] = u32::MAX;
+            self.prev.remove(index);
         }
-        path.push(self.graph.coordinates(edge.source_index).clone());
-        Route::new(path, time, distance, None)
     }
 
     /**
-    Heuristic for distance.
+    Heuristic for cost, i.e. lower bound by landmarks or for distance the linear distance.
     *
     @param self: router
-    @param from: start node for distance calculation
-    @param to: target node for distance calculation
+    @param from: start node for cost calculation
+    @param to: target node for cost calculation
     *
-    @return distance heuristic value
+    @return cost heuristic value
     */
     fn heuristic(&self, from: usize, to: usize) -> u32 {
-        // if routing for time return 0
+        let landmarks = self.graph.landmarks();
+        let bound = landmarks.heuristic(self.mode, self.routing, from, to);
+        // calc (linear) distance from a to b
+        let distance = self.graph.coordinates(from)
+            .distance(self.graph.coordinates(to));
         if self.mode == Car && self.routing == Time {
-            0
+            // no edge can be traveled faster than max. speed of graph
+            bound.max(self.graph.min_millis(Car, distance))
+        } else if landmarks.supports(self.mode, self.routing) {
+            // edge distances are rounded, so only landmarks give an exact lower bound
+            bound
         } else {
-            // calc (linear) distance from a to b
-            self.graph.coordinates(from)
-                .distance(self.graph.coordinates(to))
+            distance
+        }
+    }
+
+    /**
+    Heuristic for cost to the nearest of several goals, including the final cost of each goal.
+    *
+    @param self: router
+    @param from: start node for cost calculation
+    @param goals: indices of goal nodes with final cost
+    *
+    @return cost heuristic value
+    */
+    fn estimate(&self, from: usize, goals: &[(usize, u32)]) -> u32 {
+        goals.iter()
+            .map(|(to, cost)| self.heuristic(from, *to).saturating_add(*cost))
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+/**
+Update best path of bidirectional search if both searches met at a node on a shorter path.
+*
+@param best: meeting node and cost of best path found so far
+@param index: index of node reached by one search
+@param cost: cost of reaching node by one search
+@param remaining: (optional) cost of reaching node by other search
+*/
+fn update_best(best: &mut Option<(usize, u32)>, index: usize, cost: u32, remaining: Option<u32>) {
+    if let Some(remaining) = remaining {
+        let total = cost + remaining;
+        if total < best.map_or(u32::MAX, |(_, best_cost)| best_cost) {
+            *best = Some((index, total));
         }
     }
 }
 
 #[derive(Debug, Eq, PartialEq)]
-struct RouterNode {
-    index: usize,
-    cost: u32,
+pub struct RouterNode {
+    pub index: usize,
+    pub cost: u32,
     heuristic: u32,
 }
 
@@ -233,7 +462,7 @@ impl RouterNode {
     *
     @return new router node
     */
-    fn new(index: usize, cost: u32, heuristic: u32) -> Self {
+    pub fn new(index: usize, cost: u32, heuristic: u32) -> Self {
         Self {
             index,
             cost,
@@ -281,12 +510,84 @@ impl PartialOrd for RouterNode {
     }
 }
 
+/**
+Route found by a search between nodes of graph.
+*/
+struct Path {
+    route: Route,
+    // index of node route starts at
+    start_index: usize,
+    // index of node route ends at
+    goal_index: usize,
+    // cost of route including initial cost of start and final cost of goal
+    cost: u32,
+}
+
+impl Path {
+    /**
+    Create new path.
+    *
+    @param route: route along path
+    @param start_index: index of node route starts at
+    @param goal_index: index of node route ends at
+    @param cost: cost of route including initial cost of start and final cost of goal
+    *
+    @return new path
+    */
+    fn new(route: Route, start_index: usize, goal_index: usize, cost: u32) -> Self {
+        Self {
+            route,
+            start_index,
+            goal_index,
+            cost,
+        }
+    }
+}
+
+/**
+Projected start and goal coordinates with the partial edges between them and the graph.
+*/
+#[derive(Debug, Clone)]
+pub struct Ends {
+    pub start: Coordinates,
+    // part of edge from projected start to first node of route
+    pub first: Part,
+    pub goal: Coordinates,
+    // part of edge from last node of route to projected goal
+    pub last: Part,
+}
+
+impl Ends {
+    /**
+    Create new ends of route.
+    *
+    @param start: projected start coordinates
+    @param first: part of edge from projected start to first node of route
+    @param goal: projected goal coordinates
+    @param last: part of edge from last node of route to projected goal
+    *
+    @return new ends of route
+    */
+    pub fn new(start: Coordinates, first: Part, goal: Coordinates, last: Part) -> Self {
+        Self {
+            start,
+            first,
+            goal,
+            last,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Route {
     pub path: Vec<Coordinates>,
     pub time: u32,
     pub distance: u32,
+    // energy in Wh
+    pub energy: f32,
     pub visited_charging: Option<Vec<Coordinates>>,
+    // positions of edges of route between nodes of graph in order of path, i.e. starting at goal
+    pub edges: Vec<usize>,
 }
 
 impl Route {
@@ -296,32 +597,66 @@ impl Route {
     @param path: path of shortest path
     @param time: time needed for route
     @param distance: distance of route
+    @param energy: energy in Wh needed for route
     @param visited_charging: (optional) list of visited charging stations on route
     *
     @return new route
     */
-    pub fn new(path: Vec<Coordinates>, time: u32, distance: u32, visited_charging: Option<Vec<Coordinates>>) -> Self {
+    pub fn new(path: Vec<Coordinates>, time: u32, distance: u32, energy: f32,
+               visited_charging: Option<Vec<Coordinates>>) -> Self {
         Self {
             path,
             time,
             distance,
+            energy,
             visited_charging,
+            edges: Vec::new(),
+        }
+    }
+
+    /**
+    Extend route by the partial edges to its projected start and goal coordinates.
+    *
+    @param self: route between nodes of graph
+    @param ends: projected start and goal with partial edges
+    @param mode: transportation mode (car/bike)
+    */
+    pub fn extend(&mut self, ends: &Ends, mode: Transport) {
+        for part in &[&ends.first, &ends.last] {
+            self.time += part.edge.time(mode);
+            self.distance += part.edge.distance;
+            self.energy += part.edge.energy(mode);
+        }
+        // path starts at goal, projected coordinates at a node of the route are not repeated
+        self.path.splice(0..0, ends.last.geometry.iter().rev().cloned());
+        if self.path.first().is_none_or(|coordinates| coordinates.distance(&ends.goal) > 0) {
+            self.path.insert(0, ends.goal.clone());
+        }
+        self.path.extend(ends.first.geometry.iter().rev().cloned());
+        if self.path.last().is_none_or(|coordinates| coordinates.distance(&ends.start) > 0) {
+            self.path.push(ends.start.clone());
         }
     }
 }
 
 #[cfg(test)]
-mod tests {
+pub mod tests {
     use std::collections::BinaryHeap;
     use std::time::Instant;
 
     use geo::Point;
+    use stable_vec::StableVec;
 
-    use crate::graph::Graph;
-    use crate::graph::router::{Router, RouterNode};
+    use crate::graph::{ChargingNode, Edge, Graph, Node, TurnRestriction};
+    use crate::graph::landmarks::LANDMARKS;
+    use crate::graph::router::{Path, Route, Router, RouterNode};
     use crate::osm::Coordinates;
+    use crate::osm::highway::Kmh;
+    use crate::osm::options::{Routing, Search, Transport};
     use crate::osm::options::Routing::{Distance, Time};
-    use crate::osm::options::Transport::Car;
+    use crate::osm::options::Search::{Bidirectional, Hierarchy, Unidirectional};
+    use crate::osm::options::Transport::{All, Bike, Car, CarBike};
+    use crate::osm::restriction::apply;
 
     #[test]
     fn min_priority_queue() {
@@ -341,14 +676,351 @@ mod tests {
         assert_eq!(queue.pop().unwrap().cost, 20);
     }
 
+    impl Router<'_> {
+        /**
+        Shortest path algorithm between nodes of graph.
+        *
+        @param self: router
+        @param start_index: index of start node
+        @param goal_index: index of goal node
+        *
+        @return Result<Route, &str>: result object of shortest path routing
+        */
+        pub fn route(&mut self, start_index: usize, goal_index: usize) -> Result<Route, &'static str> {
+            Ok(self.between(start_index, goal_index)?.route)
+        }
+
+        /**
+        Cost of shortest path between nodes of graph.
+        *
+        @param self: router
+        @param start_index: index of start node
+        @param goal_index: index of goal node
+        *
+        @return Result<u32, &str>: cost of shortest path based on routing
+        */
+        pub fn cost(&mut self, start_index: usize, goal_index: usize) -> Result<u32, &'static str> {
+            Ok(self.between(start_index, goal_index)?.cost)
+        }
+
+        /**
+        Shortest path between nodes of graph.
+        *
+        @param self: router
+        @param start_index: index of start node
+        @param goal_index: index of goal node
+        *
+        @return Result<Path, &str>: path with smallest cost
+        */
+        fn between(&mut self, start_index: usize, goal_index: usize) -> Result<Path, &'static str> {
+            let start_id = self.graph.node(start_index).id;
+            let goal_id = self.graph.node(goal_index).id;
+            if start_index == goal_index || start_id == goal_id {
+                return Err("No path found, start is goal");
+            }
+            // copies of goal node for turn restrictions share its id and are goals as well
+            let goals: Vec<(usize, u32)> = (0..self.graph.nodes.len())
+                .filter(|index| self.graph.node(*index).id == goal_id)
+                .map(|index| (index, 0))
+                .collect();
+            self.path(&[(start_index, 0)], &goals)
+        }
+    }
+
+    /**
+    Create graph of a grid of streets with pseudo-random speeds, oneways and transportation modes.
+    */
+    pub fn grid(size: usize) -> Graph {
+        let coordinates: Vec<Coordinates> = (0..size * size)
+            .map(|i| Coordinates::from(Point::new(9.0 + (i % size) as f64 * 0.01, 48.0 + (i / size) as f64 * 0.01)))
+            .collect();
+        let mut random = 7u32;
+        let mut edges = Vec::new();
+        for i in 0..size * size {
+            for j in &[i + 1, i + size] {
+                if (*j == i + 1 && j % size == 0) || *j >= size * size {
+                    continue;
+                }
+                random = random.wrapping_mul(1_103_515_245).wrapping_add(12345) % (1 << 31);
+                let speed = Kmh::new(30 + random % 100);
+                let transport = [All, All, Car, Bike, CarBike][(random / 100 % 5) as usize];
+                let distance = coordinates[i].length(&coordinates[*j]);
+                edges.push(Edge::new(i, *j, transport, distance, speed));
+                // some streets are oneways
+                if random % 7 != 3 {
+                    edges.push(Edge::new(*j, i, transport, distance, speed));
+                }
+            }
+        }
+        graph(&coordinates, edges, Vec::new(), Vec::new())
+    }
+
+    /**
+    Create graph of nodes numbered in order of their coordinates and edges between them.
+    *
+    @param coordinates: coordinates of nodes
+    @param edges: edges between nodes in any order
+    @param charging_nodes: charging station nodes
+    @param restrictions: turn restrictions, which are applied to nodes and edges
+    *
+    @return graph
+    */
+    pub fn graph(coordinates: &[Coordinates], mut edges: Vec<Edge>, charging_nodes: Vec<ChargingNode>,
+                 restrictions: Vec<TurnRestriction>) -> Graph {
+        let mut nodes = StableVec::with_capacity(coordinates.len());
+        for (i, coordinates) in coordinates.iter().enumerate() {
+            nodes.push(Node::new(i as i64, coordinates.clone()));
+        }
+        edges.sort();
+        let mut number_nodes = coordinates.len();
+        apply(&restrictions, &mut nodes, &mut edges, &mut number_nodes);
+        let mut offsets = vec![0; number_nodes + 1];
+        for edge in &edges {
+            offsets[edge.source_index + 1] += 1;
+        }
+        for i in 1..offsets.len() {
+            offsets[i] += offsets[i - 1];
+        }
+        Graph::new(nodes, offsets, edges, Vec::new(), charging_nodes, restrictions)
+    }
+
+    /**
+    Assert that a router with search strategy finds routes of the same cost as a new unidirectional
+    router between all pairs of nodes of graph, both between nodes and between their coordinates.
+    The router is reused for all routes.
+    *
+    @param graph: graph to be routed on
+    @param mode: transportation mode (car/bike)
+    @param routing: routing mode (distance/time)
+    @param search: search strategy of router
+    *
+    @return routes between coordinates of all pairs of nodes found with search strategy
+    */
+    pub fn assert_same_routes(graph: &Graph, mode: Transport, routing: Routing, search: Search)
+                              -> Vec<Vec<Result<Route, &'static str>>> {
+        let cost = |route: &Route| if mode == Car && routing == Time { route.time } else { route.distance };
+        let mut router = Router::new(graph, mode, routing);
+        router.search = search;
+        let mut routes = Vec::new();
+        for start in 0..graph.nodes.len() {
+            let mut row = Vec::new();
+            for goal in 0..graph.nodes.len() {
+                let expected = Router::new(graph, mode, routing).route(start, goal);
+                match (&expected, router.route(start, goal)) {
+                    (Ok(expected), Ok(actual)) => {
+                        assert_eq!(cost(expected), cost(&actual), "{} to {}", start, goal);
+                        assert_eq!(0, actual.path[0].distance(graph.coordinates(goal)));
+                        assert_eq!(0, actual.path.last().unwrap().distance(graph.coordinates(start)));
+                    }
+                    (Err(_), Err(_)) => {}
+                    (expected, actual) => panic!("{} to {}: {:?} vs {:?}", start, goal, expected, actual)
+                }
+                // coordinates at nodes give same cost as routing between nodes
+                let actual = router.shortest_path(graph.coordinates(start), graph.coordinates(goal));
+                if let (Ok(expected), Ok(actual)) = (&expected, &actual) {
+                    assert_eq!(cost(expected), cost(actual), "{} to {}", start, goal);
+                }
+                row.push(actual);
+            }
+            routes.push(row);
+        }
+        routes
+    }
+
+    #[test]
+    fn backtracking() {
+        // road of 3 nodes with 1km between nodes
+        let coordinates: Vec<Coordinates> = (0..3)
+            .map(|i| Coordinates::from(Point::new(9.0 + i as f64 * 0.01, 48.0)))
+            .collect();
+        let edges = vec![Edge::new(0, 1, All, 1000, Kmh::new(36)), Edge::new(1, 2, All, 1000, Kmh::new(72))];
+        let graph = graph(&coordinates, edges, Vec::new(), Vec::new());
+
+        // first edge leaving start is part of route as well
+        let route = Router::new(&graph, Car, Time).route(0, 2).unwrap();
+        assert_eq!(2000, route.distance);
+        assert_eq!(150, route.time);
+        assert_eq!(vec![graph.coordinates(2).clone(), graph.coordinates(1).clone(),
+                        graph.coordinates(0).clone()], route.path);
+        // route of a single edge
+        let route = Router::new(&graph, Car, Time).route(0, 1).unwrap();
+        assert_eq!(1000, route.distance);
+        assert_eq!(2, route.path.len());
+    }
+
+    #[test]
+    fn short_edges() {
+        // motorways of many short edges, whose times in s are rounded down
+        let motorway = |spacing: f64| {
+            let coordinates: Vec<Coordinates> = (0..60)
+                .map(|i| Coordinates::from(Point::new(9.0 + i as f64 * spacing, 48.0)))
+                .collect();
+            let edges = (0..59)
+                .map(|i| Edge::new(i, i + 1, Car, coordinates[i].length(&coordinates[i + 1]), Kmh::new(130)))
+                .collect();
+            graph(&coordinates, edges, Vec::new(), Vec::new())
+        };
+        // edges of 3m take no time, edges of 45m take 1s instead of 1.25s
+        let graphs = [motorway(0.00004), motorway(0.0006)];
+        assert_eq!(0, graphs[0].edges(0)[0].time(Car));
+        for graph in &graphs {
+            // max. speed is the speed limit, even if edges take no time
+            assert_eq!(Some(Kmh::new(130)), graph.max_speed(Car));
+            // heuristic by max. speed is admissible, as costs are times in ms rounded up
+            for start in 0..59 {
+                let cost = Router::new(graph, Car, Time).cost(start, 59).unwrap();
+                let bound = Router::new(graph, Car, Time).heuristic(start, 59);
+                assert!(bound <= cost, "{} to 59", start);
+            }
+            assert_same_routes(graph, Car, Time, Bidirectional);
+        }
+        // heuristic is not switched off by edges taking no time
+        assert!(Router::new(&graphs[1], Car, Time).heuristic(0, 59) > 0);
+        assert!(Router::new(&graphs[0], Car, Time).heuristic(0, 59) > 0);
+    }
+
+    #[test]
+    fn collinear_edges() {
+        // road of many edges of 1.47m, which would be rounded to 1m each
+        let coordinates: Vec<Coordinates> = (0..1000)
+            .map(|i| Coordinates::from(Point::new(9.0 + i as f64 * 0.00002, 48.0)))
+            .collect();
+        assert_eq!(1, coordinates[0].distance(&coordinates[1]));
+        let edges = (0..999)
+            .map(|i| Edge::new(i, i + 1, Car, coordinates[i].length(&coordinates[i + 1]), Kmh::new(130)))
+            .collect();
+        let graph = graph(&coordinates, edges, Vec::new(), Vec::new());
+        // heuristic by max. speed is admissible, as distances of edges are rounded up
+        for start in (0..999).step_by(37) {
+            let cost = Router::new(&graph, Car, Time).cost(start, 999).unwrap();
+            let bound = Router::new(&graph, Car, Time).heuristic(start, 999);
+            assert!(bound <= cost, "{} to 999", start);
+            let distance = Router::new(&graph, Car, Distance).route(start, 999).unwrap().distance;
+            assert!(distance >= coordinates[start].distance(&coordinates[999]), "{} to 999", start);
+        }
+    }
+
+    #[test]
+    fn destination() {
+        // square of streets with dead end at 1, dead end and street from 0 to 1 only for destination traffic
+        let coordinates: Vec<Coordinates> = [(9.0, 48.0), (9.01, 48.0), (9.01, 48.01), (9.0, 48.01), (9.02, 48.0)].iter()
+            .map(|(lon, lat)| Coordinates::from(Point::new(*lon, *lat)))
+            .collect();
+        let mut edges = Vec::new();
+        for &(a, b, destination) in &[(0, 1, true), (1, 2, false), (2, 3, false), (3, 0, false), (1, 4, true)] {
+            for &(source, target) in &[(a, b), (b, a)] {
+                let mut edge = Edge::new(source, target, All, 1000, Kmh::new(50));
+                edge.destination = destination;
+                edges.push(edge);
+            }
+        }
+        let graph = graph(&coordinates, edges, Vec::new(), Vec::new());
+
+        // through traffic takes a detour around destination street
+        let route = Router::new(&graph, Car, Time).route(0, 1).unwrap();
+        assert_eq!(3000, route.distance);
+        // destination street is used if it leads to goal
+        let route = Router::new(&graph, Car, Distance).route(2, 4).unwrap();
+        assert_eq!(2000, route.distance);
+        // bikes are not restricted
+        let route = Router::new(&graph, Bike, Distance).route(0, 1);
+        assert_eq!(1000, route.unwrap().distance);
+        // partial edges to start and goal on destination streets are not penalized
+        let start = Coordinates::from(Point::new(9.001, 48.0));
+        let goal = Coordinates::from(Point::new(9.019, 48.0));
+        let route = Router::new(&graph, Car, Distance).shortest_path(&start, &goal).unwrap();
+        assert_eq!(1800, route.distance);
+    }
+
+    #[test]
+    fn bidirectional() {
+        let graph = grid(6);
+        for &(mode, routing) in &[(Car, Time), (Car, Distance), (Bike, Distance)] {
+            assert_same_routes(&graph, mode, routing, Bidirectional);
+        }
+    }
+
+    #[test]
+    fn hierarchy() {
+        let mut graph = grid(8);
+        graph.contract();
+        assert!(graph.hierarchy().supports(Car, Time));
+        assert!(!graph.hierarchy().supports(Bike, Distance));
+        for route in assert_same_routes(&graph, Car, Time, Hierarchy).iter().flatten().flatten() {
+            // shortcuts are unpacked, so path consists of neighbored nodes only
+            assert!(route.path.windows(2).all(|pair| pair[0].distance(&pair[1]) < 1200));
+        }
+        // other modes fall back to unidirectional search
+        let mut router = Router::new(&graph, Bike, Distance);
+        router.search = Hierarchy;
+        let expected = Router::new(&graph, Bike, Distance).route(0, 63).map(|route| route.distance);
+        assert_eq!(expected, router.route(0, 63).map(|route| route.distance));
+    }
+
+    #[test]
+    fn heuristics() {
+        let mut graph = grid(8);
+        graph.select_landmarks(LANDMARKS);
+        assert!(!graph.landmarks().nodes.is_empty());
+        for &(mode, routing) in &[(Car, Time), (Car, Distance), (Bike, Distance)] {
+            // bidirectional search uses no heuristic, so it verifies unidirectional search with heuristics
+            assert_same_routes(&graph, mode, routing, Bidirectional);
+            for start in 0..64 {
+                for goal in 0..64 {
+                    if let Ok(cost) = Router::new(&graph, mode, routing).cost(start, goal) {
+                        // lower bound of landmarks is admissible
+                        let bound = graph.landmarks().heuristic(mode, routing, start, goal);
+                        assert!(bound <= cost, "{} to {}", start, goal);
+                        if routing == Time {
+                            // bound by max. speed is admissible as well
+                            let bound = Router::new(&graph, mode, routing).heuristic(start, goal);
+                            assert!(bound <= cost, "{} to {}", start, goal);
+                        }
+                    }
+                }
+            }
+        }
+        // bikes have landmarks as well, which are shared by both routing modes
+        let landmarks = graph.landmarks();
+        assert!((0..64).any(|goal| landmarks.heuristic(Bike, Distance, landmarks.nodes[0], goal) > 0));
+        assert!((0..64).all(|goal| landmarks.heuristic(Bike, Distance, landmarks.nodes[0], goal)
+            == landmarks.heuristic(Bike, Time, landmarks.nodes[0], goal)));
+        // fewer landmarks take less memory and still give lower bounds
+        let size = landmarks.distances.len();
+        graph.select_landmarks(2);
+        assert_eq!(2, graph.landmarks().nodes.len());
+        assert_eq!(size / LANDMARKS * 2, graph.landmarks().distances.len());
+        assert_same_routes(&graph, Car, Distance, Bidirectional);
+        graph.select_landmarks(0);
+        assert!(!graph.landmarks().supports(Car, Distance));
+    }
+
+    #[test]
+    fn snapping() {
+        let graph = grid(6);
+        for &(mode, routing) in &[(Car, Time), (Bike, Distance)] {
+            assert_same_routes(&graph, mode, routing, Unidirectional);
+        }
+        // coordinates beside streets are projected onto the middle of them
+        let start = Coordinates::from(Point::new(9.005, 48.0001));
+        let goal = Coordinates::from(Point::new(9.045, 48.0499));
+        let route = Router::new(&graph, Bike, Distance).shortest_path(&start, &goal).unwrap();
+        let projected_start = Coordinates::from(Point::new(9.005, 48.0));
+        let projected_goal = Coordinates::from(Point::new(9.045, 48.05));
+        assert!(route.path.last().unwrap().distance(&projected_start) <= 1);
+        assert!(route.path[0].distance(&projected_goal) <= 1);
+        assert!(route.distance >= projected_start.distance(&projected_goal));
+        assert!(route.distance < projected_start.distance(&projected_goal) * 2);
+    }
+
     #[test]
     fn shortest_path() {
-        let graph = Graph::from_bin("stuttgart-regbez-latest.bin");
+        let graph = Graph::from_bin("stuttgart-regbez-latest.bin", None, LANDMARKS).unwrap();
         let mut router = Router::new(&graph, Car, Distance);
         let start =
-            Coordinates::from(Point::new(48.7417761, 9.1036340));
+            Coordinates::from(Point::new(9.1036340, 48.7417761));
         let goal =
-            Coordinates::from(Point::new(48.7452193, 9.1025545));
+            Coordinates::from(Point::new(9.1025545, 48.7452193));
         let max_distance = start.distance(&goal) * 2;
 
         let route = router.shortest_path(&start, &goal);
@@ -358,14 +1030,28 @@ mod tests {
 
     #[test]
     fn time_stuttgart_hamburg() {
-        let graph = Graph::from_bin("germany-latest.bin");
+        let graph = Graph::from_bin("germany-latest.bin", None, LANDMARKS).unwrap();
         let mut router = Router::new(&graph, Car, Time);
-        let stuttgart = Coordinates::from(Point::new(48.783418, 9.181945));
-        let hamburg = Coordinates::from(Point::new(53.552483, 10.006797));
+        let stuttgart = Coordinates::from(Point::new(9.181945, 48.783418));
+        let hamburg = Coordinates::from(Point::new(10.006797, 53.552483));
         let now = Instant::now();
         let route = router.shortest_path(&stuttgart, &hamburg);
         let secs = now.elapsed().as_secs();
         assert!(route.is_ok());
         assert!(secs < 10);
     }
+
+    #[test]
+    fn hierarchy_stuttgart_hamburg() {
+        let graph = Graph::from_bin("germany-latest.bin", None, LANDMARKS).unwrap();
+        let stuttgart = Coordinates::from(Point::new(9.181945, 48.783418));
+        let hamburg = Coordinates::from(Point::new(10.006797, 53.552483));
+        let expected = Router::new(&graph, Car, Time).shortest_path(&stuttgart, &hamburg).unwrap();
+        let mut router = Router::new(&graph, Car, Time);
+        router.search = Hierarchy;
+        let route = router.shortest_path(&stuttgart, &hamburg).unwrap();
+        assert_eq!(expected.time, route.time);
+        assert_eq!(expected.path.first(), route.path.first());
+        assert_eq!(expected.path.last(), route.path.last());
+    }
 }
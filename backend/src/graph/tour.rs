@@ -0,0 +1,316 @@
+use log::debug;
+
+use crate::graph::charging::{join, Section};
+use crate::graph::Graph;
+use crate::graph::matrix::MatrixRouter;
+use crate::graph::router::{Route, Router};
+use crate::osm::Coordinates;
+use crate::osm::options::{Routing, Search, Transport};
+
+// max. number of stops of a tour, as the cost of all pairs of stops is calculated
+pub const MAX_STOPS: usize = 50;
+// cost between points which are not connected
+const UNREACHABLE: u32 = u32::MAX;
+// max. number of consecutive stops moved at once by or-opt
+const SEGMENT: usize = 3;
+
+pub struct TourPlanner<'a> {
+    graph: &'a Graph,
+    mode: Transport,
+    routing: Routing,
+    // search strategy of routes between stops
+    pub search: Search,
+}
+
+impl<'a> TourPlanner<'a> {
+    /**
+    Create new planner for tours through multiple stops.
+    *
+    @param graph: graph as base for routing
+    @param mode: transportation mode (car/bike)
+    @param routing: routing mode (distance/time) tours are optimized for
+    *
+    @return Self: new tour planner
+    */
+    pub fn new(graph: &'a Graph, mode: Transport, routing: Routing) -> Self {
+        Self {
+            graph,
+            mode,
+            routing,
+            search: Search::Unidirectional,
+        }
+    }
+
+    /**
+    Create router for routes between stops.
+    *
+    @param self: tour planner
+    *
+    @return router with transportation mode, routing mode and search strategy of tour planner
+    */
+    fn router(&self) -> Router<'a> {
+        let mut router = Router::new(self.graph, self.mode, self.routing);
+        router.search = self.search;
+        router
+    }
+
+    /**
+    Plan tour from start through all stops in a near-optimal order. The order is constructed by
+    visiting the nearest unvisited stop and improved by 2-opt and or-opt moves afterwards.
+    *
+    @param self: tour planner
+    @param start: start coordinates
+    @param stops: coordinates of stops to visit in any order
+    @param end: (optional) coordinates tour has to end at, e.g. start for a round trip, tour ends
+    at its last stop otherwise
+    *
+    @return Result<Tour, &str>: tour with order of stops, error if stops are not connected
+    */
+    pub fn plan(&self, start: &Coordinates, stops: &[Coordinates],
+                end: Option<&Coordinates>) -> Result<Tour, &'static str> {
+        if stops.len() > MAX_STOPS {
+            return Err("Too many stops for a tour");
+        }
+        // points of tour are start, stops and (optional) end
+        let mut points = vec![start.clone()];
+        points.extend(stops.iter().cloned());
+        points.extend(end.cloned());
+        let costs = self.costs(&points);
+
+        let mut order = nearest_neighbor(&costs, stops.len());
+        if end.is_some() {
+            order.push(points.len() - 1);
+        }
+        let fixed = if end.is_some() { order.len() - 1 } else { order.len() };
+        let initial = length(&costs, &order);
+        while two_opt(&costs, &mut order, fixed) || or_opt(&costs, &mut order, fixed) {}
+        if length(&costs, &order) >= u64::from(UNREACHABLE) {
+            return Err("No tour found, stops are not connected");
+        }
+        debug!("Improved cost of tour from {} to {}", initial, length(&costs, &order));
+
+        // route along stops of tour
+        let routes = order.windows(2)
+            .map(|pair| self.router().shortest_path(&points[pair[0]], &points[pair[1]]))
+            .collect::<Result<Vec<Route>, _>>()?;
+        let (route, sections) = join(routes, None);
+        let order = order.into_iter()
+            .filter(|point| (1..=stops.len()).contains(point))
+            .map(|point| point - 1)
+            .collect();
+        Ok(Tour::new(order, route, sections))
+    }
+
+    /**
+    Calculate cost of routes between all pairs of points.
+    *
+    @param self: tour planner
+    @param points: coordinates of points
+    *
+    @return cost matrix by index of source and target point, unreachable if there is no route
+    */
+    fn costs(&self, points: &[Coordinates]) -> Vec<Vec<u32>> {
+        MatrixRouter::new(self.graph, self.mode, self.routing)
+            .matrix(points, points)
+            .into_iter()
+            .map(|row| row.into_iter()
+                .map(|entry| entry.map_or(UNREACHABLE, |entry| entry.cost))
+                .collect())
+            .collect()
+    }
+}
+
+/**
+Calculate length of path through points.
+*
+@param costs: cost matrix of points
+@param order: indices of points in order of visiting
+*
+@return sum of costs between consecutive points
+*/
+fn length(costs: &[Vec<u32>], order: &[usize]) -> u64 {
+    order.windows(2)
+        .map(|pair| u64::from(costs[pair[0]][pair[1]]))
+        .sum()
+}
+
+/**
+Construct path by always visiting the nearest unvisited stop next.
+*
+@param costs: cost matrix of points, starting with start point followed by stops
+@param stops: number of stops
+*
+@return indices of start and all stops in order of visiting
+*/
+fn nearest_neighbor(costs: &[Vec<u32>], stops: usize) -> Vec<usize> {
+    let mut order = vec![0];
+    let mut visited = vec![false; stops + 1];
+    visited[0] = true;
+    for _ in 0..stops {
+        let current = order[order.len() - 1];
+        let next = (1..=stops)
+            .filter(|stop| !visited[*stop])
+            .min_by_key(|stop| costs[current][*stop])
+            .unwrap();
+        visited[next] = true;
+        order.push(next);
+    }
+    order
+}
+
+/**
+Improve path by reversing a part of it, accepting the first move which lowers its length. Costs
+may be asymmetric, so the length of the whole path is compared.
+*
+@param costs: cost matrix of points
+@param order: indices of points in order of visiting, first point is fixed
+@param fixed: index in order up to which points may be moved
+*
+@return true if path was improved
+*/
+fn two_opt(costs: &[Vec<u32>], order: &mut [usize], fixed: usize) -> bool {
+    let current = length(costs, order);
+    for i in 1..fixed {
+        for j in i + 1..fixed {
+            order[i..=j].reverse();
+            if length(costs, order) < current {
+                return true;
+            }
+            order[i..=j].reverse();
+        }
+    }
+    false
+}
+
+/**
+Improve path by moving up to three consecutive points to another position, accepting the first
+move which lowers its length.
+*
+@param costs: cost matrix of points
+@param order: indices of points in order of visiting, first point is fixed
+@param fixed: index in order up to which points may be moved
+*
+@return true if path was improved
+*/
+fn or_opt(costs: &[Vec<u32>], order: &mut Vec<usize>, fixed: usize) -> bool {
+    let current = length(costs, order);
+    for size in 1..=SEGMENT {
+        for i in 1..(fixed + 1).saturating_sub(size) {
+            let segment: Vec<usize> = order.drain(i..i + size).collect();
+            for position in (1..=fixed - size).filter(|position| *position != i) {
+                order.splice(position..position, segment.iter().cloned());
+                if length(costs, order) < current {
+                    return true;
+                }
+                order.drain(position..position + size);
+            }
+            order.splice(i..i, segment);
+        }
+    }
+    false
+}
+
+#[derive(Debug)]
+pub struct Tour {
+    // indices of stops in order of visiting
+    pub order: Vec<usize>,
+    // route from start through all stops to end
+    pub route: Route,
+    // sections of route between consecutive points of tour
+    pub sections: Vec<Section>,
+}
+
+impl Tour {
+    /**
+    Create new tour.
+    *
+    @param order: indices of stops in order of visiting
+    @param route: route from start through all stops to end
+    @param sections: sections of route between consecutive points of tour
+    *
+    @return new tour
+    */
+    pub fn new(order: Vec<usize>, route: Route, sections: Vec<Section>) -> Self {
+        Self {
+            order,
+            route,
+            sections,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::Point;
+
+    use crate::graph::Edge;
+    use crate::graph::router::tests::graph;
+    use crate::graph::tour::{length, nearest_neighbor, or_opt, two_opt, TourPlanner};
+    use crate::osm::Coordinates;
+    use crate::osm::highway::Kmh;
+    use crate::osm::options::Routing::Distance;
+    use crate::osm::options::Transport::{All, Car};
+
+    #[test]
+    fn local_search() {
+        // points on a line, nearest neighbor leaves point 6 behind
+        let positions = [0, 2, 3, 5, 8, 12, 1];
+        let costs: Vec<Vec<u32>> = positions.iter()
+            .map(|a: &i32| positions.iter().map(|b: &i32| (a - b).unsigned_abs()).collect())
+            .collect();
+        let mut order = nearest_neighbor(&costs, 6);
+        assert_eq!(vec![0, 6, 1, 2, 3, 4, 5], order);
+
+        // round trip back to start, first stop is reached on the way back
+        let mut costs = costs;
+        for row in &mut costs {
+            row.push(row[0]);
+        }
+        costs.push(costs[0].clone());
+        order.push(7);
+        let fixed = order.len() - 1;
+        order.swap(1, 3);
+        let before = length(&costs, &order);
+        while two_opt(&costs, &mut order, fixed) || or_opt(&costs, &mut order, fixed) {}
+        assert!(length(&costs, &order) < before);
+        assert_eq!(24, length(&costs, &order));
+        assert_eq!((0, 7), (order[0], order[fixed]));
+
+        // asymmetric costs, stops may only be visited in one direction cheaply
+        let mut costs = vec![vec![10; 4]; 4];
+        costs[0][3] = 1;
+        costs[3][2] = 1;
+        costs[2][1] = 1;
+        let mut order = vec![0, 1, 2, 3];
+        while two_opt(&costs, &mut order, 4) || or_opt(&costs, &mut order, 4) {}
+        assert_eq!(vec![0, 3, 2, 1], order);
+    }
+
+    #[test]
+    fn tour() {
+        // road with 7 nodes and 1km between nodes
+        let coordinates = |i: usize| Coordinates::from(Point::new(9.0 + i as f64 * 0.001, 48.0));
+        let mut edges = Vec::new();
+        for i in 1..7 {
+            edges.push(Edge::new(i - 1, i, All, 1000, Kmh::new(50)));
+            edges.push(Edge::new(i, i - 1, All, 1000, Kmh::new(50)));
+        }
+        let graph = graph(&(0..7).map(coordinates).collect::<Vec<_>>(), edges, Vec::new(), Vec::new());
+        let planner = TourPlanner::new(&graph, Car, Distance);
+        let stops = [coordinates(5), coordinates(1), coordinates(6), coordinates(3)];
+
+        // stops are visited from start at 2 towards the nearer end of the road first
+        let tour = planner.plan(&coordinates(2), &stops, None).unwrap();
+        assert_eq!(vec![1, 3, 0, 2], tour.order);
+        assert_eq!(6000, tour.route.distance);
+        assert_eq!(4, tour.sections.len());
+        assert_eq!(tour.route.path[0], coordinates(6));
+        assert_eq!(*tour.route.path.last().unwrap(), coordinates(2));
+
+        // round trip is the same in both directions
+        let tour = planner.plan(&coordinates(2), &stops, Some(&coordinates(2))).unwrap();
+        assert_eq!(10_000, tour.route.distance);
+        assert_eq!(5, tour.sections.len());
+        assert_eq!(tour.route.path[0], coordinates(2));
+    }
+}
@@ -0,0 +1,651 @@
+use std::collections::BinaryHeap;
+
+use geo::algorithm::haversine_distance::HaversineDistance;
+use geo::Point;
+
+use crate::graph::{Edge, Graph};
+use crate::graph::flat::Table;
+use crate::osm::Coordinates;
+use crate::osm::options::Transport;
+
+// radius of earth in meters, slightly smaller than in distances so bounds never exceed them
+const EARTH_RADIUS: f64 = 6_370_000.0;
+// number of nearest edges whose nodes' edges are candidates for snapping
+const SNAP_CANDIDATES: usize = 16;
+
+/**
+Static KD-trees over the nodes and over the edges of the graph. The trees are implicit: the element
+at the middle of each range of indices splits the range by latitude or longitude, alternating with
+depth. Edges are split by the centers of their bounding boxes, and the root of each range stores
+the bounding box of all edges of the range.
+*/
+#[derive(Debug)]
+pub struct KdTree {
+    // node indices in tree order
+    pub indices: Table<usize>,
+    // positions of edges in tree order, edges along the coordinates of an opposite edge are left out
+    pub edges: Table<usize>,
+    // min. and max. coordinates of all edges of each range, stored at the middle of the range
+    pub boxes: Table<Coordinates>,
+}
+
+impl KdTree {
+    /**
+    Create KD-tree without any node or edge.
+    *
+    @return empty tree
+    */
+    pub fn empty() -> Self {
+        Self {
+            indices: Table::Owned(Vec::new()),
+            edges: Table::Owned(Vec::new()),
+            boxes: Table::Owned(Vec::new()),
+        }
+    }
+
+    /**
+    Lower bound of distance between coordinates and any edge of a range.
+    *
+    @param self: tree
+    @param coords: coordinates
+    @param middle: index of middle of range
+    *
+    @return lower bound of distance in meters
+    */
+    fn bound(&self, coords: &Coordinates, middle: usize) -> f64 {
+        let (min, max) = (&self.boxes[middle * 2], &self.boxes[middle * 2 + 1]);
+        // meridians are closest at the latitude farthest from the equator
+        let latitude = coords.lat().abs().max(min.lat().abs()).max(max.lat().abs());
+        let mut bound: f64 = 0.0;
+        for depth in 0..2 {
+            if key(coords, depth) < key(min, depth) {
+                bound = bound.max(self::bound(coords, min, depth, latitude));
+            } else if key(coords, depth) > key(max, depth) {
+                bound = bound.max(self::bound(coords, max, depth, latitude));
+            }
+        }
+        bound
+    }
+}
+
+/**
+Create KD-trees of nodes and edges of graph.
+*
+@param graph: graph
+*
+@return KdTree: trees containing indices of all nodes and positions of edges
+*/
+pub fn create(graph: &Graph) -> KdTree {
+    let points: Vec<&Coordinates> = graph.nodes.iter().map(|node| &node.coordinates).collect();
+    let mut indices: Vec<usize> = (0..points.len()).collect();
+    split(&mut indices, &points, 0);
+
+    // opposite edges along the same coordinates are found at the target of indexed edges
+    let mut edges: Vec<usize> = (0..graph.edges.len())
+        .filter(|position| graph.opposite(*position).is_none_or(|opposite| opposite > *position))
+        .collect();
+    let bounds: Vec<(Coordinates, Coordinates)> = (0..graph.edges.len())
+        .map(|position| enclose(graph.points(position)))
+        .collect();
+    let centers: Vec<Coordinates> = bounds.iter()
+        .map(|(min, max)| Coordinates::from(Point::new((min.lon() + max.lon()) / 2.0, (min.lat() + max.lat()) / 2.0)))
+        .collect();
+    split(&mut edges, &centers.iter().collect::<Vec<_>>(), 0);
+    let mut boxes = vec![Coordinates::new(0, 0); edges.len() * 2];
+    unite(&edges, &bounds, &mut boxes);
+    KdTree {
+        indices: Table::Owned(indices),
+        edges: Table::Owned(edges),
+        boxes: Table::Owned(boxes),
+    }
+}
+
+/**
+Get bounding box of coordinates.
+*
+@param points: coordinates, at least one
+*
+@return min. and max. coordinates
+*/
+fn enclose<'a, I: Iterator<Item=&'a Coordinates>>(points: I) -> (Coordinates, Coordinates) {
+    let (mut min, mut max) = ((f64::MAX, f64::MAX), (f64::MIN, f64::MIN));
+    for coords in points {
+        min = (min.0.min(coords.lat()), min.1.min(coords.lon()));
+        max = (max.0.max(coords.lat()), max.1.max(coords.lon()));
+    }
+    (Coordinates::from(Point::new(min.1, min.0)), Coordinates::from(Point::new(max.1, max.0)))
+}
+
+/**
+Store bounding boxes of all edges of each range recursively at the middle of the range.
+*
+@param edges: range of edge positions in tree order
+@param bounds: bounding box of each edge
+@param boxes: bounding boxes of range, two coordinates for each edge of range
+*
+@return (optional) bounding box of range, none if range is empty
+*/
+fn unite(edges: &[usize], bounds: &[(Coordinates, Coordinates)], boxes: &mut [Coordinates])
+         -> Option<(Coordinates, Coordinates)> {
+    if edges.is_empty() {
+        return None;
+    }
+    let middle = edges.len() / 2;
+    let (before, after) = boxes.split_at_mut(middle * 2);
+    let lower = unite(&edges[..middle], bounds, before);
+    let upper = unite(&edges[middle + 1..], bounds, &mut after[2..]);
+    let (min, max) = enclose(std::iter::once(&bounds[edges[middle]])
+        .chain(lower.iter())
+        .chain(upper.iter())
+        .flat_map(|(min, max)| vec![min, max]));
+    boxes[middle * 2] = min.clone();
+    boxes[middle * 2 + 1] = max.clone();
+    Some((min, max))
+}
+
+/**
+Order range of point indices recursively, so the middle point splits the range.
+*
+@param indices: range of point indices
+@param points: coordinates of all points
+@param depth: depth of range in tree
+*/
+fn split(indices: &mut [usize], points: &[&Coordinates], depth: usize) {
+    if indices.len() <= 1 {
+        return;
+    }
+    let middle = indices.len() / 2;
+    indices.select_nth_unstable_by(middle, |a, b| {
+        key(points[*a], depth).total_cmp(&key(points[*b], depth))
+    });
+    let (lower, upper) = indices.split_at_mut(middle);
+    split(lower, points, depth + 1);
+    split(&mut upper[1..], points, depth + 1);
+}
+
+/**
+Get coordinate the tree is split by at a depth.
+*
+@param coords: coordinates
+@param depth: depth in tree
+*
+@return latitude on even depths, longitude on odd depths
+*/
+fn key(coords: &Coordinates, depth: usize) -> f64 {
+    if depth.is_multiple_of(2) { coords.lat() } else { coords.lon() }
+}
+
+/**
+Lower bound of distance between coordinates and any coordinates on the other side of a split.
+Parallels are equally far apart everywhere, meridians are closest at the max. latitude.
+*
+@param coords: coordinates
+@param split: coordinates of point splitting the tree
+@param depth: depth of split in tree
+@param latitude: max. absolute latitude of coordinates on both sides of a meridian
+*
+@return lower bound of distance in meters
+*/
+fn bound(coords: &Coordinates, split: &Coordinates, depth: usize, latitude: f64) -> f64 {
+    let difference = (key(coords, depth) - key(split, depth)).abs().to_radians();
+    if depth.is_multiple_of(2) {
+        EARTH_RADIUS * difference
+    } else {
+        EARTH_RADIUS * latitude.min(90.0).to_radians().cos() * difference
+    }
+}
+
+// range of tree with its depth and lower bound of distance to query coordinates
+type Range = (usize, usize, usize, f64);
+
+/**
+Coordinates projected onto the nearest edge segment of the graph, which act as a virtual node.
+*/
+#[derive(Debug)]
+pub struct Snap {
+    // projected coordinates on edge segment
+    pub coordinates: Coordinates,
+    // positions of edges of segment allowing transportation mode, with fraction of edge before
+    // projected coordinates and number of intermediate coordinates before projected coordinates
+    pub edges: Vec<(usize, f64, usize)>,
+}
+
+/**
+Part of an edge between projected coordinates and a node of the edge.
+*/
+#[derive(Debug, Clone)]
+pub struct Part {
+    // index of node at the other end of part
+    pub index: usize,
+    // part of edge with its share of distance
+    pub edge: Edge,
+    // intermediate coordinates of part in direction of edge
+    pub geometry: Vec<Coordinates>,
+}
+
+impl Snap {
+    /**
+    Get partial edges leaving the projected coordinates.
+    *
+    @param self: snap
+    @param graph: graph
+    *
+    @return list of parts from projected coordinates to target node of edge
+    */
+    pub fn leaving(&self, graph: &Graph) -> Vec<Part> {
+        self.edges.iter()
+            .map(|(position, fraction, split)| {
+                let end = graph.geometry(*position).len();
+                graph.part(*position, graph.edge(*position).target_index, (*fraction, *split), (1.0, end))
+            })
+            .collect()
+    }
+
+    /**
+    Get partial edges entering the projected coordinates.
+    *
+    @param self: snap
+    @param graph: graph
+    *
+    @return list of parts from source node of edge to projected coordinates
+    */
+    pub fn entering(&self, graph: &Graph) -> Vec<Part> {
+        self.edges.iter()
+            .map(|(position, fraction, split)| {
+                graph.part(*position, graph.edge(*position).source_index, (0.0, 0), (*fraction, *split))
+            })
+            .collect()
+    }
+}
+
+/**
+Project coordinates onto an edge segment, locally treating the earth as flat.
+*
+@param coords: coordinates to be projected
+@param source: coordinates of start of segment
+@param target: coordinates of end of segment
+*
+@return fraction of segment before projected coordinates and projected coordinates
+*/
+fn project(coords: &Coordinates, source: &Coordinates, target: &Coordinates) -> (f64, Coordinates) {
+    // longitudes are scaled so both axes have the same unit
+    let scale = source.lat().to_radians().cos();
+    let (x, y) = ((coords.lon() - source.lon()) * scale, coords.lat() - source.lat());
+    let (dx, dy) = ((target.lon() - source.lon()) * scale, target.lat() - source.lat());
+    let length = dx * dx + dy * dy;
+    let fraction = if length > 0.0 { ((x * dx + y * dy) / length).clamp(0.0, 1.0) } else { 0.0 };
+    let projected = Point::new(source.lon() + fraction * (target.lon() - source.lon()),
+                               source.lat() + fraction * (target.lat() - source.lat()));
+    (fraction, Coordinates::from(projected))
+}
+
+impl Graph {
+    /**
+    Get nearest neighbor in graph.
+    *
+    @param self: graph
+    @param coords: coordinates for which nearest neighbor is searched
+    @mode: transportation mode of routing
+    *
+    @return Result<usize, &str>
+    */
+    pub fn nearest_neighbor(&self, coords: &Coordinates, mode: Transport) -> Result<usize, &'static str> {
+        self.nearest_neighbors(coords, mode, 1).first().cloned()
+            .ok_or("No point matching transportation found")
+    }
+
+    /**
+    Get k nearest neighbors in graph.
+    *
+    @param self: graph
+    @param coords: coordinates for which nearest neighbors are searched
+    @param mode: transportation mode of routing
+    @param k: max. number of neighbors
+    *
+    @return Vec<usize>: indices of nearest nodes allowing transportation mode, nearest first
+    */
+    pub fn nearest_neighbors(&self, coords: &Coordinates, mode: Transport, k: usize) -> Vec<usize> {
+        // k nearest nodes found so far, farthest on top
+        let mut nearest: BinaryHeap<(u32, usize)> = BinaryHeap::with_capacity(k + 1);
+        let mut stack: Vec<Range> = vec![(0, self.tree.indices.len(), 0, 0.0)];
+        while let Some((start, end, depth, bound)) = stack.pop() {
+            let farthest = if nearest.len() < k { u32::MAX } else { nearest.peek().unwrap().0 };
+            if start >= end || bound > f64::from(farthest) {
+                continue;
+            }
+            let middle = start + (end - start) / 2;
+            let index = self.tree.indices[middle];
+            let split = self.coordinates(index);
+            let distance = split.distance(coords);
+            if distance < farthest && self.allows(index, mode) {
+                nearest.push((distance, index));
+                if nearest.len() > k {
+                    nearest.pop();
+                }
+            }
+            let (near, far) = if key(coords, depth) < key(split, depth) {
+                ((start, middle), (middle + 1, end))
+            } else {
+                ((middle + 1, end), (start, middle))
+            };
+            // search side of coordinates first, other side only if it may be closer
+            let latitude = coords.lat().abs().max(split.lat().abs());
+            stack.push((far.0, far.1, depth + 1, bound.max(self::bound(coords, split, depth, latitude))));
+            stack.push((near.0, near.1, depth + 1, bound));
+        }
+        nearest.into_sorted_vec().into_iter().map(|(_, index)| index).collect()
+    }
+
+    /**
+    Get k nearest edges of spatial index by distance of their nearest segment.
+    *
+    @param self: graph
+    @param coords: coordinates for which nearest edges are searched
+    @param k: max. number of edges
+    @param accept: filter of edge positions
+    *
+    @return Vec<usize>: positions of nearest accepted edges, nearest first
+    */
+    fn nearest_edges<F: Fn(usize) -> bool>(&self, coords: &Coordinates, k: usize, accept: F) -> Vec<usize> {
+        // k nearest edges found so far, farthest on top
+        let mut nearest: BinaryHeap<(u32, usize)> = BinaryHeap::with_capacity(k + 1);
+        let mut stack = vec![(0, self.tree.edges.len(), 0.0)];
+        while let Some((start, end, bound)) = stack.pop() {
+            let farthest = if nearest.len() < k { u32::MAX } else { nearest.peek().unwrap().0 };
+            if start >= end || bound > f64::from(farthest) {
+                continue;
+            }
+            let middle = start + (end - start) / 2;
+            let position = self.tree.edges[middle];
+            if accept(position) {
+                let distance = self.project(coords, position).2.distance(coords);
+                if distance < farthest {
+                    nearest.push((distance, position));
+                    if nearest.len() > k {
+                        nearest.pop();
+                    }
+                }
+            }
+            // search range with the closer bounding box first
+            let mut ranges: Vec<(usize, usize, f64)> = [(start, middle), (middle + 1, end)].iter()
+                .filter(|(start, end)| start < end)
+                .map(|(start, end)| (*start, *end, self.tree.bound(coords, start + (end - start) / 2)))
+                .collect();
+            ranges.sort_by(|a, b| b.2.total_cmp(&a.2));
+            stack.extend(ranges);
+        }
+        nearest.into_sorted_vec().into_iter().map(|(_, position)| position).collect()
+    }
+
+    /**
+    Snap coordinates to the nearest edge segment allowing transportation mode. Candidates are
+    the edges of the nodes of the nearest edges.
+    *
+    @param self: graph
+    @param coords: coordinates to be snapped
+    @param mode: transportation mode of routing
+    *
+    @return Result<Snap, &str>: projected coordinates with edges of segment
+    */
+    pub fn snap(&self, coords: &Coordinates, mode: Transport) -> Result<Snap, &'static str> {
+        let allows = |position: usize| self.edge(position).transport.contains(mode);
+        let mut positions = Vec::new();
+        for position in self.nearest_edges(coords, SNAP_CANDIDATES, |position| {
+            allows(position) || self.opposite(position).is_some_and(allows)
+        }) {
+            // opposite edge of segment is an incoming edge of its source
+            let edge = self.edge(position);
+            for index in &[edge.source_index, edge.target_index] {
+                positions.extend(self.offsets[*index]..self.offsets[*index + 1]);
+                positions.extend((self.reverse_offsets[*index]..self.reverse_offsets[*index + 1]).map(|i| self.reverse_edges[i]));
+            }
+        }
+        positions.sort_unstable();
+        positions.dedup();
+        // position, fraction, split, distance and projected coordinates of candidate edges
+        let candidates: Vec<_> = positions.into_iter()
+            .filter(|position| allows(*position))
+            .map(|position| {
+                let (fraction, split, projected) = self.project(coords, position);
+                (position, fraction, split, projected.distance(coords), projected)
+            })
+            .collect();
+        let nearest = candidates.iter().min_by_key(|candidate| candidate.3)
+            .ok_or("No point matching transportation found")?;
+        // all edges through projected coordinates, i.e. both directions of segment, copies of nodes
+        // for turn restrictions and all edges of a node if coordinates are projected onto it
+        let edges = candidates.iter()
+            .filter(|candidate| candidate.4.distance(&nearest.4) == 0)
+            .map(|candidate| (candidate.0, candidate.1, candidate.2))
+            .collect();
+        Ok(Snap {
+            coordinates: nearest.4.clone(),
+            edges,
+        })
+    }
+
+    /**
+    Get coordinates of an edge, i.e. its nodes and intermediate coordinates.
+    *
+    @param self: graph
+    @param position: position of edge in edges
+    *
+    @return iterator over coordinates in direction of edge
+    */
+    fn points(&self, position: usize) -> impl Iterator<Item=&Coordinates> {
+        let edge = self.edge(position);
+        std::iter::once(self.coordinates(edge.source_index))
+            .chain(self.geometry(position))
+            .chain(std::iter::once(self.coordinates(edge.target_index)))
+    }
+
+    /**
+    Get edge in opposite direction along the same coordinates.
+    *
+    @param self: graph
+    @param position: position of edge in edges
+    *
+    @return (optional) position of opposite edge
+    */
+    fn opposite(&self, position: usize) -> Option<usize> {
+        let edge = self.edge(position);
+        (self.offsets[edge.target_index]..self.offsets[edge.target_index + 1])
+            .find(|other| *other != position && self.edge(*other).target_index == edge.source_index
+                && self.geometry(*other).iter().eq(self.geometry(position).iter().rev()))
+    }
+
+    /**
+    Project coordinates onto the nearest segment of an edge, including its intermediate coordinates.
+    *
+    @param self: graph
+    @param coords: coordinates to be projected
+    @param position: position of edge in edges
+    *
+    @return fraction of edge before projected coordinates, number of intermediate coordinates
+    before projected coordinates and projected coordinates
+    */
+    fn project(&self, coords: &Coordinates, position: usize) -> (f64, usize, Coordinates) {
+        let points: Vec<&Coordinates> = self.points(position).collect();
+        let lengths: Vec<f64> = points.windows(2)
+            .map(|pair| pair[0].point().haversine_distance(&pair[1].point()))
+            .collect();
+        let (split, (fraction, projected)) = points.windows(2)
+            .map(|pair| project(coords, pair[0], pair[1]))
+            .enumerate()
+            .min_by_key(|(_, (_, projected))| projected.distance(coords))
+            .unwrap();
+        let total: f64 = lengths.iter().sum();
+        let fraction = if total > 0.0 {
+            (lengths[..split].iter().sum::<f64>() + fraction * lengths[split]) / total
+        } else {
+            fraction
+        };
+        (fraction, split, projected)
+    }
+
+    /**
+    Get part of an edge between two locations on it.
+    *
+    @param self: graph
+    @param position: position of edge in edges
+    @param index: index of node at the other end of part
+    @param from: fraction of edge and number of intermediate coordinates before start of part
+    @param to: fraction of edge and number of intermediate coordinates before end of part
+    *
+    @return Part: part of edge
+    */
+    pub fn part(&self, position: usize, index: usize, from: (f64, usize), to: (f64, usize)) -> Part {
+        let mut edge = self.edge(position).partial(to.0 - from.0);
+        // part leads to projected start or goal, which is no through traffic
+        edge.destination = false;
+        Part {
+            index,
+            edge,
+            geometry: self.geometry(position)[from.1..to.1.max(from.1)].to_vec(),
+        }
+    }
+
+    /**
+    Get nodes of graph within bounding box.
+    *
+    @param self: graph
+    @param min: coordinates with min. latitude and longitude of bounding box
+    @param max: coordinates with max. latitude and longitude of bounding box
+    @param mode: transportation mode of routing
+    *
+    @return Vec<usize>: indices of nodes within bounding box allowing transportation mode
+    */
+    #[cfg(test)]
+    pub fn nodes_within(&self, min: &Coordinates, max: &Coordinates, mode: Transport) -> Vec<usize> {
+        let mut within = Vec::new();
+        let mut stack = vec![(0, self.tree.indices.len(), 0)];
+        while let Some((start, end, depth)) = stack.pop() {
+            if start >= end {
+                continue;
+            }
+            let middle = start + (end - start) / 2;
+            let index = self.tree.indices[middle];
+            let coords = self.coordinates(index);
+            if min.lat() <= coords.lat() && coords.lat() <= max.lat()
+                && min.lon() <= coords.lon() && coords.lon() <= max.lon()
+                && self.allows(index, mode) {
+                within.push(index);
+            }
+            // only visit sides overlapping bounding box
+            if key(min, depth) <= key(coords, depth) {
+                stack.push((start, middle, depth + 1));
+            }
+            if key(coords, depth) <= key(max, depth) {
+                stack.push((middle + 1, end, depth + 1));
+            }
+        }
+        within
+    }
+
+    /**
+    Check if transportation mode is allowed on any edge of node.
+    *
+    @param self: graph
+    @param index: index of node
+    @param mode: transportation mode of routing
+    *
+    @return true if any edge of node allows transportation mode
+    */
+    fn allows(&self, index: usize, mode: Transport) -> bool {
+        self.edges(index).iter().any(|edge| edge.transport.contains(mode))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::Point;
+
+    use crate::graph::Edge;
+    use crate::graph::router::Router;
+    use crate::graph::router::tests::graph;
+    use crate::graph::tree::bound;
+    use crate::osm::Coordinates;
+    use crate::osm::highway::Kmh;
+    use crate::osm::options::Routing::Distance;
+    use crate::osm::options::Transport::{Bike, Car, CarBike};
+
+    #[test]
+    fn spatial_queries() {
+        // nodes along three rows across borders of former grid cells, odd nodes are for bikes
+        let coordinates: Vec<Coordinates> = (0..90)
+            .map(|i| Coordinates::from(Point::new(8.93 + (i % 30) as f64 * 0.005, 47.93 + (i / 30) as f64 * 0.07)))
+            .collect();
+        let edges = (0..90)
+            .map(|i| Edge::new(i, (i + 1) % 90, if i % 2 == 0 { CarBike } else { Bike }, 0, Kmh::new(50)))
+            .collect();
+        let graph = graph(&coordinates, edges, Vec::new(), Vec::new());
+        let all: Vec<usize> = (0..90).collect();
+
+        for (lat, lon) in &[(47.95, 9.0), (48.0, 9.05), (48.1, 8.9), (47.5, 8.5)] {
+            let coords = Coordinates::from(Point::new(*lon, *lat));
+            for &(mode, k) in &[(Car, 1), (Bike, 1), (Car, 5), (Bike, 12)] {
+                // compare with distances of all nodes
+                let mut expected: Vec<u32> = all.iter()
+                    .filter(|i| mode == Bike || *i % 2 == 0)
+                    .map(|i| graph.coordinates(*i).distance(&coords))
+                    .collect();
+                expected.sort_unstable();
+                expected.truncate(k);
+                let actual: Vec<u32> = graph.nearest_neighbors(&coords, mode, k).iter()
+                    .map(|i| graph.coordinates(*i).distance(&coords))
+                    .collect();
+                assert_eq!(expected, actual);
+            }
+        }
+        // nearest node is found even far outside of the graph
+        let far = Coordinates::from(Point::new(2.0, 40.0));
+        assert!(graph.nearest_neighbor(&far, Car).is_ok());
+
+        let min = Coordinates::from(Point::new(8.9925, 47.9));
+        let max = Coordinates::from(Point::new(9.0125, 48.01));
+        let mut within = graph.nodes_within(&min, &max, Car);
+        within.sort_unstable();
+        assert_eq!(vec![14, 16, 44, 46], within);
+        assert_eq!(8, graph.nodes_within(&min, &max, Bike).len());
+    }
+
+    #[test]
+    fn split_bounds() {
+        let coords = Coordinates::from(Point::new(9.0, 48.0));
+        let north = Coordinates::from(Point::new(9.0, 48.01));
+        let east = Coordinates::from(Point::new(9.01, 48.0));
+        // bounds are lower bounds, tight along the meridian and nearly so along the parallel
+        for (split, depth) in &[(&north, 0), (&east, 1)] {
+            let latitude = coords.lat().abs().max(split.lat().abs());
+            let bound = bound(&coords, split, *depth, latitude);
+            let distance = f64::from(coords.distance(split));
+            assert!(bound <= distance && bound > distance - 2.0, "{} vs {}", bound, distance);
+        }
+        // meridians are closer at higher latitudes
+        assert!(bound(&coords, &east, 1, 60.0) < bound(&coords, &east, 1, 48.0));
+    }
+
+    #[test]
+    fn long_edges() {
+        // long straight road from 0 to 1 without intermediate coordinates, many short streets north of it
+        let mut coordinates = vec![Coordinates::from(Point::new(9.0, 48.0)), Coordinates::from(Point::new(9.1, 48.0))];
+        coordinates.extend((0..20).map(|i| Coordinates::from(Point::new(9.045 + i as f64 * 0.0005, 48.01))));
+        let length = coordinates[0].distance(&coordinates[1]);
+        let mut edges = vec![Edge::new(0, 1, Car, length, Kmh::new(100)), Edge::new(1, 0, Car, length, Kmh::new(100))];
+        edges.extend((2..21).map(|i| Edge::new(i, i + 1, CarBike, 37, Kmh::new(30))));
+        let graph = graph(&coordinates, edges, Vec::new(), Vec::new());
+
+        // click next to the middle of the road is projected onto it, not onto the nearer streets
+        let click = Coordinates::from(Point::new(9.05, 48.001));
+        let snap = graph.snap(&click, Car).unwrap();
+        assert!(snap.coordinates.distance(&Coordinates::from(Point::new(9.05, 48.0))) <= 1);
+        let mut edges: Vec<usize> = snap.edges.iter().map(|(position, _, _)| *position).collect();
+        edges.sort_unstable();
+        assert_eq!(vec![0, 1], edges);
+        assert!(snap.edges.iter().all(|(_, fraction, _)| (fraction - 0.5).abs() < 0.01));
+        let route = Router::new(&graph, Car, Distance).shortest_path(&click, &coordinates[1]).unwrap();
+        assert!(route.distance.abs_diff(length / 2) <= 1);
+        // bikes are not allowed on the road
+        assert!(graph.snap(&click, Bike).unwrap().coordinates.distance(&click) > 500);
+    }
+}
@@ -5,11 +5,13 @@ use std::time::Instant;
 use log::debug;
 
 use crate::graph::Graph;
+use crate::graph::landmarks::LANDMARKS;
 
 mod graph;
 mod logger;
 mod osm;
 mod rest;
+mod vehicle;
 
 /**
 Entry point.
@@ -34,6 +36,21 @@ fn graph() -> Graph {
         println!("Please provide a *.osm.pbf file as argument");
         process::exit(1);
     };
+    // number of landmarks, each landmark takes 24 bytes per node
+    let landmarks = match env::args().skip(2).collect::<Vec<String>>().as_slice() {
+        [] => LANDMARKS,
+        [flag, count] if flag == "--landmarks" => match count.parse() {
+            Ok(count) => count,
+            Err(_) => {
+                println!("Please provide the number of landmarks as integer");
+                process::exit(1);
+            }
+        },
+        _ => {
+            println!("Please provide the number of landmarks via --landmarks <count>");
+            process::exit(1);
+        }
+    };
     // get name without extension
     let name_stub = pbf_name.split('.').collect::<Vec<&str>>()[0];
     // binary filename with same name as pbf input filename
@@ -42,14 +59,19 @@ fn graph() -> Graph {
     // check if binary file already exists
     if Path::new(&bin_name).exists() {
         debug!("Found existing graph");
-        // create graph from binary file
-        Graph::from_bin(&bin_name)
+        // create graph from binary file if it was created from the same pbf file with the same landmarks by this version
+        match Graph::from_bin(&bin_name, Some(&pbf_name), landmarks) {
+            Ok(graph) => return graph,
+            Err(error) => debug!("Existing graph can not be used, {}, parsing...", error)
+        }
     } else {
         debug!("No existing graph found, parsing...");
-        // create graph from pbf file
-        let graph = Graph::from_pbf(&pbf_name);
-        // save graph to binary file
-        graph.save(&bin_name);
-        graph
     }
+    // create graph from pbf file
+    let graph = Graph::from_pbf(&pbf_name, landmarks);
+    // save graph to binary file
+    if let Err(error) = graph.save(&bin_name, &pbf_name, landmarks) {
+        debug!("Could not save graph, {}", error);
+    }
+    graph
 }
@@ -0,0 +1,130 @@
+use osmpbfreader::Way;
+
+// access tags for cars, from most specific to most general
+pub const CAR: [&str; 4] = ["motorcar", "motor_vehicle", "vehicle", "access"];
+// access tags for bikes, from most specific to most general
+pub const BIKE: [&str; 3] = ["bicycle", "vehicle", "access"];
+// access tags for all vehicles, which may restrict but not widen the default of a highway type
+const GENERIC: [&str; 2] = ["vehicle", "access"];
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Access {
+    Yes,
+    Destination,
+    No,
+}
+
+impl Access {
+    /**
+    Get access from value of osm access tag.
+    *
+    @param value: value of access tag
+    *
+    @return (optional) access, none if value is unknown
+    */
+    pub fn from(value: &str) -> Option<Self> {
+        match value {
+            "yes" | "permissive" | "designated" | "official" | "dismount" | "discouraged" => Some(Access::Yes),
+            // way may be used to reach a destination along it
+            "destination" | "delivery" | "customers" => Some(Access::Destination),
+            "no" | "private" | "agricultural" | "forestry" | "use_sidepath" => Some(Access::No),
+            _ => None
+        }
+    }
+
+    /**
+    Get access of an osm way based on the access tag hierarchy.
+    *
+    @param way: osm way
+    @param keys: access tags from most specific to most general
+    *
+    @return (optional) access of the most specific known access tag
+    */
+    pub fn of(way: &Way, keys: &[&str]) -> Option<Self> {
+        Self::tagged(way, keys).map(|(_, access)| access)
+    }
+
+    /**
+    Get most specific known access tag of an osm way.
+    *
+    @param way: osm way
+    @param keys: access tags from most specific to most general
+    *
+    @return (optional) key and access of the most specific known access tag
+    */
+    fn tagged<'a>(way: &Way, keys: &[&'a str]) -> Option<(&'a str, Self)> {
+        keys.iter()
+            .filter_map(|key| way.tags.get(*key).map(|value| (*key, value)))
+            .filter_map(|(key, value)| Self::from(value).map(|access| (key, access)))
+            .next()
+    }
+
+    /**
+    Check if osm way may only be used to reach a destination along it.
+    *
+    @param way: osm way
+    @param keys: access tags from most specific to most general
+    *
+    @return true if the most specific known access tag only allows destination traffic
+    */
+    pub fn destination(way: &Way, keys: &[&str]) -> bool {
+        Self::of(way, keys) == Some(Access::Destination)
+    }
+
+    /**
+    Check if osm way may be used based on the access tag hierarchy.
+    *
+    @param way: osm way
+    @param keys: access tags from most specific to most general
+    @param default: true if way may be used if no access tag is specified
+    *
+    @return true if way may be used, false otherwise
+    */
+    pub fn allows(way: &Way, keys: &[&str], default: bool) -> bool {
+        match Self::tagged(way, keys) {
+            Some((_, Access::No)) => false,
+            // generic access tags only restrict the default, e.g. vehicle=destination on a footway
+            Some((key, _)) if GENERIC.contains(&key) => default,
+            // destination traffic is allowed, as start and goal may be located along the way,
+            // routing keeps through traffic away from such ways by their cost
+            Some((_, Access::Yes)) | Some((_, Access::Destination)) => true,
+            None => default
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::osm::access::{Access, BIKE, CAR};
+    use crate::osm::tests::way;
+
+    #[test]
+    fn hierarchy() {
+        let private = way(&[("access", "private")]);
+        assert!(!Access::allows(&private, &CAR, true));
+        assert!(!Access::allows(&private, &BIKE, true));
+
+        let bikes_allowed = way(&[("access", "no"), ("bicycle", "designated")]);
+        assert!(!Access::allows(&bikes_allowed, &CAR, true));
+        assert!(Access::allows(&bikes_allowed, &BIKE, false));
+
+        let no_motor_vehicles = way(&[("motor_vehicle", "no")]);
+        assert!(!Access::allows(&no_motor_vehicles, &CAR, true));
+        assert!(Access::allows(&no_motor_vehicles, &BIKE, true));
+
+        let destination = way(&[("vehicle", "destination"), ("bicycle", "no")]);
+        assert_eq!(Some(Access::Destination), Access::of(&destination, &CAR));
+        assert!(Access::allows(&destination, &CAR, true));
+        assert!(!Access::allows(&destination, &CAR, false));
+        assert!(Access::destination(&destination, &CAR));
+        assert!(!Access::destination(&bikes_allowed, &BIKE));
+        assert!(!Access::allows(&destination, &BIKE, true));
+
+        let cars_allowed = way(&[("access", "no"), ("motorcar", "destination")]);
+        assert!(Access::allows(&cars_allowed, &CAR, false));
+
+        let unknown = way(&[("access", "unknown")]);
+        assert!(Access::allows(&unknown, &CAR, true));
+        assert!(!Access::allows(&unknown, &BIKE, false));
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+use log::debug;
+use stable_vec::StableVec;
+
+use crate::graph::{Edge, Node, TurnRestriction};
+use crate::osm::Coordinates;
+use crate::osm::components;
+
+/**
+Check if a node only connects two neighbors with edges of identical attributes, so the edges
+through it can be merged. Nodes of turn restrictions and their copies are never merged.
+*
+@param index: index of node
+@param outgoing: outgoing edges of node
+@param incoming: incoming edges of node
+*
+@return true if edges through node can be merged
+*/
+fn mergeable(index: usize, outgoing: &[&Edge], incoming: &[&Edge]) -> bool {
+    let same = |a: &Edge, b: &Edge| a.transport == b.transport && a.destination == b.destination
+        && a.max_speed == b.max_speed;
+    match (outgoing, incoming) {
+        // oneway through node
+        ([next], [prev]) => {
+            prev.source_index != next.target_index && prev.source_index != index
+                && next.target_index != index && same(prev, next)
+        }
+        // both directions through node, each leaving to the neighbor the other one came from
+        ([a, b], [_, _]) => {
+            let (first, second) = (a.target_index, b.target_index);
+            if first == second || first == index || second == index {
+                return false;
+            }
+            let entering = |source: usize| incoming.iter().find(|edge| edge.source_index == source);
+            match (entering(second), entering(first)) {
+                (Some(to_first), Some(to_second)) => same(to_first, a) && same(to_second, b),
+                _ => false
+            }
+        }
+        _ => false
+    }
+}
+
+/**
+Merge chains of nodes which only connect two neighbors into single edges. Coordinates of the
+removed nodes are kept as intermediate coordinates of the merged edges.
+*
+@param nodes: nodes of graph, indices are compacted after removal
+@param edges: edges of graph sorted by source node with distances, merged edges replace chains
+@param restrictions: turn restrictions between node indices
+@param number_nodes: number of nodes of graph, decreased by number of removed nodes
+*
+@return intermediate coordinates of each edge in direction of edge
+*/
+pub fn contract(nodes: &mut StableVec<Node>, edges: &mut Vec<Edge>,
+                restrictions: &mut Vec<TurnRestriction>, number_nodes: &mut usize) -> Vec<Vec<Coordinates>> {
+    let mut outgoing: Vec<Vec<&Edge>> = vec![Vec::new(); *number_nodes];
+    let mut incoming: Vec<Vec<&Edge>> = vec![Vec::new(); *number_nodes];
+    for edge in edges.iter() {
+        outgoing[edge.source_index].push(edge);
+        incoming[edge.target_index].push(edge);
+    }
+    // turn restrictions refer to their nodes, copies of via nodes share the id of the original
+    let mut ids: HashMap<i64, usize> = HashMap::new();
+    for index in 0..*number_nodes {
+        *ids.entry(nodes[index].id).or_insert(0) += 1;
+    }
+    let mut mergeable: Vec<bool> = (0..*number_nodes)
+        .map(|index| ids[&nodes[index].id] == 1 && self::mergeable(index, &outgoing[index], &incoming[index]))
+        .collect();
+    for restriction in restrictions.iter() {
+        for index in &[restriction.from_index, restriction.via_index, restriction.to_index] {
+            mergeable[*index] = false;
+        }
+    }
+
+    // follow chains from all edges of remaining nodes
+    let mut merged = Vec::with_capacity(edges.len());
+    let mut visited = vec![false; *number_nodes];
+    for edge in edges.iter().filter(|edge| !mergeable[edge.source_index]) {
+        let mut chain = edge.clone();
+        let mut coordinates = Vec::new();
+        let mut prev = edge.source_index;
+        while mergeable[chain.target_index] {
+            let index = chain.target_index;
+            visited[index] = true;
+            coordinates.push(nodes[index].coordinates.clone());
+            let next = outgoing[index].iter().find(|next| next.target_index != prev).unwrap();
+            chain.target_index = next.target_index;
+            chain.distance += next.distance;
+            prev = index;
+        }
+        merged.push((chain, coordinates));
+    }
+    // chains without any remaining node, i.e. isolated rings, are kept as they are
+    for edge in edges.iter().filter(|edge| mergeable[edge.source_index] && !visited[edge.source_index]) {
+        merged.push((edge.clone(), Vec::new()));
+    }
+    merged.sort_by_key(|(edge, _)| edge.source_index);
+
+    let keep: Vec<bool> = (0..*number_nodes).map(|index| !visited[index]).collect();
+    debug!("Merging chains of {} nodes...", visited.iter().filter(|visited| **visited).count());
+    let (merged, geometry): (Vec<Edge>, Vec<Vec<Coordinates>>) = merged.into_iter().unzip();
+    *edges = merged;
+    components::compact(nodes, edges, restrictions, number_nodes, &keep);
+    geometry
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::Point;
+    use stable_vec::StableVec;
+
+    use crate::graph::{Edge, Graph, Node, TurnRestriction};
+    use crate::graph::router::Router;
+    use crate::osm::chains::contract;
+    use crate::osm::Coordinates;
+    use crate::osm::highway::Kmh;
+    use crate::osm::options::Routing::Distance;
+    use crate::osm::options::Transport::{All, Car};
+    use crate::osm::restriction::RestrictionKind;
+
+    #[test]
+    fn chains() {
+        // street from 0 to 6 with changes of speed at 0 and 5, oneway from 7 to 9 north of it
+        let coordinates = [
+            (9.0, 48.0), (9.001, 48.0005), (9.002, 48.0), (9.003, 48.0), (9.004, 48.0),
+            (9.005, 48.0), (9.006, 48.0), (9.0, 48.001), (9.002, 48.0015), (9.004, 48.001),
+        ];
+        let coordinates: Vec<Coordinates> = coordinates.iter()
+            .map(|(lon, lat)| Coordinates::from(Point::new(*lon, *lat)))
+            .collect();
+        let mut nodes = StableVec::new();
+        for (i, coordinates) in coordinates.iter().enumerate() {
+            nodes.push(Node::new(i as i64, coordinates.clone()));
+        }
+        let mut edges = Vec::new();
+        for &(a, b, speed) in &[(0, 1, 50), (1, 2, 50), (2, 3, 50), (3, 4, 50), (0, 7, 30),
+            (4, 9, 50), (4, 5, 50), (5, 6, 30)] {
+            edges.push(Edge::new(a, b, All, 0, Kmh::new(speed)));
+            edges.push(Edge::new(b, a, All, 0, Kmh::new(speed)));
+        }
+        edges.push(Edge::new(7, 8, Car, 0, Kmh::new(50)));
+        edges.push(Edge::new(8, 9, Car, 0, Kmh::new(50)));
+        edges.sort();
+        for edge in &mut edges {
+            edge.distance = coordinates[edge.source_index].length(&coordinates[edge.target_index]);
+        }
+        let mut restrictions = vec![TurnRestriction::new(2, 3, 4, RestrictionKind::No, Car)];
+        let mut number_nodes = coordinates.len();
+        let geometry = contract(&mut nodes, &mut edges, &mut restrictions, &mut number_nodes);
+
+        // only 1 and 8 connect two neighbors with equal edges and are not part of a turn restriction
+        let ids: Vec<i64> = (0..number_nodes).map(|index| nodes[index].id).collect();
+        assert_eq!(vec![0, 2, 3, 4, 5, 6, 7, 9], ids);
+        assert_eq!(15, edges.len());
+        assert_eq!(edges.len(), geometry.len());
+        assert!(edges.windows(2).all(|pair| pair[0].source_index <= pair[1].source_index));
+        let merged = edges.iter().position(|edge| edge.source_index == 0 && edge.target_index == 1).unwrap();
+        assert_eq!(vec![coordinates[1].clone()], geometry[merged]);
+        let distance = coordinates[0].length(&coordinates[1]) + coordinates[1].length(&coordinates[2]);
+        assert_eq!(distance, edges[merged].distance);
+        let oneway = edges.iter().position(|edge| edge.source_index == 6 && edge.target_index == 7).unwrap();
+        assert_eq!(vec![coordinates[8].clone()], geometry[oneway]);
+        assert!(!edges.iter().any(|edge| edge.source_index == 7 && edge.target_index == 6));
+        assert_eq!((1, 2, 3), (restrictions[0].from_index, restrictions[0].via_index, restrictions[0].to_index));
+
+        let mut offsets = vec![0; number_nodes + 1];
+        for edge in &edges {
+            offsets[edge.source_index + 1] += 1;
+        }
+        for i in 1..offsets.len() {
+            offsets[i] += offsets[i - 1];
+        }
+        let graph = Graph::new(nodes, offsets, edges, geometry, Vec::new(), restrictions);
+        // intermediate coordinates of merged edges are part of routes
+        let route = Router::new(&graph, Car, Distance).route(0, 7).unwrap();
+        assert_eq!(vec![coordinates[9].clone(), coordinates[8].clone(), coordinates[7].clone(),
+                        coordinates[0].clone()], route.path);
+        // coordinates are snapped onto intermediate coordinates as well
+        let snap = graph.snap(&coordinates[1], Car).unwrap();
+        assert_eq!(coordinates[1], snap.coordinates);
+        assert_eq!(2, snap.edges.len());
+        let route = Router::new(&graph, Car, Distance).shortest_path(&coordinates[1], &coordinates[6]).unwrap();
+        let expected: Vec<Coordinates> = [6, 5, 4, 3, 2, 1].iter().map(|i| coordinates[*i].clone()).collect();
+        assert_eq!(expected, route.path);
+    }
+}
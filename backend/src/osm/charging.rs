@@ -0,0 +1,413 @@
+use geo::{LineString, MultiPoint, MultiPolygon, Point, Polygon};
+use geo::algorithm::bounding_rect::BoundingRect;
+use geo::algorithm::centroid::Centroid;
+use geo::algorithm::contains::Contains;
+use osmpbfreader::Tags;
+use serde::{Deserialize, Serialize};
+use strum_macros::EnumString;
+
+use crate::graph::ChargingNode;
+use crate::osm::Coordinates;
+use crate::osm::options::ChargingOptions;
+
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy, Serialize, Deserialize, EnumString)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SocketType {
+    Type2,
+    Ccs,
+    Chademo,
+    Schuko,
+}
+
+impl SocketType {
+    /**
+    Get socket type from name of osm socket tag.
+    *
+    @param name: name of socket in tag, e.g. type2 in socket:type2
+    *
+    @return (optional) socket type, none if socket type is not supported
+    */
+    fn from(name: &str) -> Option<Self> {
+        match name {
+            "type2" => Some(SocketType::Type2),
+            "type2_combo" | "ccs" => Some(SocketType::Ccs),
+            "chademo" => Some(SocketType::Chademo),
+            "schuko" => Some(SocketType::Schuko),
+            _ => None
+        }
+    }
+
+    /**
+    Get common power of socket type if no output is specified.
+    *
+    @param self: socket type
+    *
+    @return charging power in kW
+    */
+    pub fn power(self) -> f32 {
+        match self {
+            SocketType::Type2 => 22.0,
+            SocketType::Ccs | SocketType::Chademo => 50.0,
+            SocketType::Schuko => 3.7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Socket {
+    pub kind: SocketType,
+    pub count: u32,
+    // (optional) charging power in kW
+    pub output: Option<f32>,
+}
+
+impl Socket {
+    /**
+    Get charging power of socket.
+    *
+    @param self: socket
+    *
+    @return charging power in kW, common power of socket type if no output is specified
+    */
+    pub fn power(&self) -> f32 {
+        self.output.unwrap_or_else(|| self.kind.power())
+    }
+}
+
+/**
+Create charging station node from tags of osm object.
+*
+@param id: id of osm object
+@param coordinates: coordinates of charging station
+@param tags: tags of osm object
+*
+@return charging station node with all known metadata
+*/
+pub fn charging_node(id: i64, coordinates: Coordinates, tags: &Tags) -> ChargingNode {
+    let charging_options = charging_options(tags);
+    let sockets = sockets(tags);
+    // charging station is as powerful as its most powerful socket
+    let power = max_power(sockets.iter().map(|socket| socket.power()))
+        .unwrap_or_else(|| charging_options.power());
+
+    let mut charging_node = ChargingNode::new(id, coordinates, charging_options, power);
+    charging_node.sockets = sockets;
+    charging_node.capacity = tags.get("capacity").and_then(|value| value.trim().parse().ok());
+    charging_node.operator = tags.get("operator").cloned();
+    charging_node.network = tags.get("network").cloned();
+    charging_node.fee = tags.get("fee").and_then(|value| yes_no(value));
+    charging_node.authentication = authentication(tags);
+    charging_node.opening_hours = tags.get("opening_hours").cloned();
+    charging_node
+}
+
+#[derive(Debug, Default)]
+pub struct Outline {
+    // coordinates of the ways (and nodes) an osm object consists of
+    pub rings: Vec<Vec<Coordinates>>,
+    // (optional) coordinates of an entrance node
+    pub entrance: Option<Coordinates>,
+}
+
+pub struct ChargingArea {
+    // charging station node at representative point of area
+    pub charging_node: ChargingNode,
+    polygons: MultiPolygon<f64>,
+    // coordinates of all nodes of area
+    points: Vec<Coordinates>,
+}
+
+impl ChargingArea {
+    /**
+    Check if charging station node is part of area, e.g. a charging station within a parking lot.
+    *
+    @param self: charging area
+    @param charging_node: charging station node
+    *
+    @return true if node is within the outline of area or one of its nodes
+    */
+    pub fn contains(&self, charging_node: &ChargingNode) -> bool {
+        let point = charging_node.coordinates.point();
+        let within = self.polygons.bounding_rect()
+            .map(|rect| rect.contains(&point) && self.polygons.contains(&point))
+            .unwrap_or(false);
+        within || self.points.contains(&charging_node.coordinates)
+    }
+}
+
+/**
+Create charging station area from tags and outline of osm way or relation.
+*
+@param id: id of osm object
+@param tags: tags of osm object
+@param outline: outline of osm object
+*
+@return (optional) charging area, none if no coordinates of outline are known
+*/
+pub fn charging_area(id: i64, tags: &Tags, outline: Outline) -> Option<ChargingArea> {
+    let points: Vec<Coordinates> = outline.rings.iter().flatten().cloned().collect();
+    // closed ways are outlines of areas, e.g. buildings or parking lots
+    let polygons: Vec<Polygon<f64>> = outline.rings.iter()
+        .filter(|ring| ring.len() >= 4 && ring.first() == ring.last())
+        .map(|ring| {
+            let exterior: LineString<f64> = ring.iter().map(|c| c.point()).collect();
+            Polygon::new(exterior, vec![])
+        })
+        .collect();
+    let polygons = MultiPolygon(polygons);
+
+    // an entrance is the representative point of area, otherwise the centroid is used
+    let representative = outline.entrance
+        .or_else(|| polygons.centroid().map(Coordinates::from))
+        .or_else(|| {
+            let points: Vec<Point<f64>> = points.iter().map(|c| c.point()).collect();
+            MultiPoint(points).centroid().map(Coordinates::from)
+        })?;
+
+    Some(ChargingArea {
+        charging_node: charging_node(id, representative, tags),
+        polygons,
+        points,
+    })
+}
+
+/**
+Add charging station areas to charging station nodes, except areas which contain one of the nodes.
+*
+@param charging_nodes: charging station nodes
+@param charging_areas: charging station areas
+*/
+pub fn deduplicate(charging_nodes: &mut Vec<ChargingNode>, charging_areas: Vec<ChargingArea>) {
+    let number_nodes = charging_nodes.len();
+    for area in charging_areas {
+        // charging stations mapped as nodes are more precise than their surrounding area
+        let duplicate = charging_nodes[..number_nodes].iter()
+            .any(|charging_node| area.contains(charging_node));
+        if !duplicate {
+            charging_nodes.push(area.charging_node);
+        }
+    }
+}
+
+/**
+Get charging options of charging station.
+*
+@param tags: tags of osm object
+*
+@return charging options based on car and bicycle tags
+*/
+fn charging_options(tags: &Tags) -> ChargingOptions {
+    // we assume initially that a charging station can charge cars and bikes
+    let car = tags.contains("car", "yes");
+    let bike = tags.contains("bicycle", "yes");
+    // check if charging options are further specified and set charging options accordingly
+    match (car, bike) {
+        (true, false) => ChargingOptions::Car,
+        (false, true) => ChargingOptions::Bike,
+        _ => ChargingOptions::CarBike
+    }
+}
+
+/**
+Get sockets of charging station from socket:* tags.
+*
+@param tags: tags of osm object
+*
+@return list of supported sockets with count and output
+*/
+pub fn sockets(tags: &Tags) -> Vec<Socket> {
+    let mut sockets: Vec<Socket> = Vec::new();
+
+    for (key, value) in tags.iter() {
+        let parts: Vec<&str> = key.split(':').collect();
+        // only socket:<type> tags specify the count of sockets
+        if parts.len() != 2 || parts[0] != "socket" {
+            continue;
+        }
+        let kind = match SocketType::from(parts[1]) {
+            Some(kind) => kind,
+            None => continue
+        };
+        let count = match count(value) {
+            Some(count) if count > 0 => count,
+            _ => continue
+        };
+        let output = tags.get(&format!("{}:output", key)).and_then(|value| output(value));
+
+        // type2_combo and ccs are the same socket type
+        if let Some(socket) = sockets.iter_mut().find(|socket| socket.kind == kind) {
+            socket.count += count;
+            socket.output = max_power(socket.output.into_iter().chain(output));
+        } else {
+            sockets.push(Socket { kind, count, output });
+        }
+    }
+    sockets
+}
+
+/**
+Get number of sockets from value of socket tag.
+*
+@param value: value of socket tag
+*
+@return (optional) number of sockets, none if value is unknown
+*/
+fn count(value: &str) -> Option<u32> {
+    match value.trim() {
+        "yes" => Some(1),
+        "no" => Some(0),
+        count => count.parse().ok()
+    }
+}
+
+/**
+Get charging power from value of output tag, e.g. "22 kW", "3700 W" or "50;22".
+*
+@param value: value of output tag
+*
+@return (optional) max. charging power in kW, none if value is unknown
+*/
+pub fn output(value: &str) -> Option<f32> {
+    max_power(value.split(';')
+        .filter_map(|output| {
+            let output = output.trim().to_lowercase();
+            let (number, factor) = if let Some(kw) = output.strip_suffix("kva").or_else(|| output.strip_suffix("kw")) {
+                (kw, 1.0)
+            } else if let Some(w) = output.strip_suffix('w') {
+                (w, 0.001)
+            } else {
+                // output is specified in kW by default
+                (output.as_str(), 1.0)
+            };
+            number.trim().replace(',', ".").parse::<f32>().ok().map(|power| power * factor)
+        }))
+}
+
+/**
+Get max. charging power.
+*
+@param powers: charging powers in kW
+*
+@return (optional) max. charging power, none if there is no charging power
+*/
+pub fn max_power<I: Iterator<Item=f32>>(powers: I) -> Option<f32> {
+    powers.fold(None, |max: Option<f32>, power| Some(max.map_or(power, |max| max.max(power))))
+}
+
+/**
+Get supported authentication methods from authentication:* tags.
+*
+@param tags: tags of osm object
+*
+@return names of authentication methods, e.g. app or membership_card
+*/
+fn authentication(tags: &Tags) -> Vec<String> {
+    let mut methods: Vec<String> = tags.iter()
+        .filter(|(_, value)| yes_no(value) == Some(true))
+        .filter_map(|(key, _)| key.strip_prefix("authentication:"))
+        .map(|method| method.to_string())
+        .collect();
+    methods.sort();
+    methods
+}
+
+/**
+Get boolean from value of yes/no tag.
+*
+@param value: value of tag
+*
+@return (optional) true for yes, false for no, none if value is unknown
+*/
+fn yes_no(value: &str) -> Option<bool> {
+    match value.trim() {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use osmpbfreader::Tags;
+
+    use crate::osm::charging::{charging_area, charging_node, deduplicate, Outline, output, SocketType};
+    use crate::osm::Coordinates;
+    use crate::osm::options::ChargingOptions;
+
+    fn tags(tags: &[(&str, &str)]) -> Tags {
+        tags.iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn outputs() {
+        assert_eq!(Some(22.0), output("22 kW"));
+        assert!((output("3700 W").unwrap() - 3.7).abs() < 1e-3);
+        assert_eq!(Some(50.0), output("50;22"));
+        assert_eq!(Some(11.0), output("11"));
+        assert_eq!(None, output("unknown"));
+    }
+
+    #[test]
+    fn metadata() {
+        let tags = tags(&[
+            ("amenity", "charging_station"),
+            ("car", "yes"),
+            ("socket:type2", "2"),
+            ("socket:type2:output", "22 kW"),
+            ("socket:type2_combo", "1"),
+            ("socket:type2_combo:output", "150 kW"),
+            ("socket:schuko", "no"),
+            ("capacity", "3"),
+            ("operator", "EnBW"),
+            ("fee", "yes"),
+            ("authentication:app", "yes"),
+            ("authentication:none", "no"),
+            ("opening_hours", "24/7"),
+        ]);
+        let node = charging_node(1, Coordinates::new(480_000_000, 90_000_000), &tags);
+        assert_eq!(ChargingOptions::Car, node.charging_options);
+        assert_eq!(2, node.sockets.len());
+        assert!((node.power - 150.0).abs() < 1e-3);
+        assert!(node.sockets.iter().any(|s| s.kind == SocketType::Type2 && s.count == 2));
+        assert_eq!(Some(3), node.capacity);
+        assert_eq!(Some("EnBW".to_string()), node.operator);
+        assert_eq!(Some(true), node.fee);
+        assert_eq!(vec!["app".to_string()], node.authentication);
+        assert_eq!(Some("24/7".to_string()), node.opening_hours);
+        // vehicles with schuko sockets cannot charge
+        assert_eq!(None, node.power_for(&[SocketType::Schuko]));
+        assert_eq!(Some(22.0), node.power_for(&[SocketType::Type2]));
+        assert_eq!(Some(150.0), node.power_for(&[]));
+    }
+
+    #[test]
+    fn areas() {
+        let tags = tags(&[("amenity", "charging_station"), ("socket:type2", "4")]);
+        let c = |lat: i32, lon: i32| Coordinates::new(480_000_000 + lat, 90_000_000 + lon);
+        let square = vec![c(0, 0), c(0, 1000), c(1000, 1000), c(1000, 0), c(0, 0)];
+
+        // centroid of parking lot
+        let outline = Outline { rings: vec![square.clone()], entrance: None };
+        let area = charging_area(1, &tags, outline).unwrap();
+        // coordinates are rounded to decimicro degrees
+        assert!(area.charging_node.coordinates.distance(&c(500, 500)) <= 1);
+        assert!((area.charging_node.power - 22.0).abs() < 1e-3);
+
+        // entrance of building
+        let outline = Outline { rings: vec![square], entrance: Some(c(0, 500)) };
+        let entrance = charging_area(2, &tags, outline).unwrap();
+        assert_eq!(0, entrance.charging_node.coordinates.distance(&c(0, 500)));
+
+        // charging station node within parking lot is a duplicate
+        let mut charging_nodes = vec![charging_node(3, c(200, 200), &tags)];
+        let elsewhere = Outline { rings: vec![vec![c(5000, 5000), c(5000, 6000)]], entrance: None };
+        let areas = vec![area, charging_area(4, &tags, elsewhere).unwrap()];
+        deduplicate(&mut charging_nodes, areas);
+        assert_eq!(2, charging_nodes.len());
+        assert_eq!(4, charging_nodes[1].id);
+        assert!(charging_nodes[1].coordinates.distance(&c(5000, 5500)) <= 1);
+    }
+}
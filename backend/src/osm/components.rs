@@ -0,0 +1,260 @@
+use log::debug;
+use stable_vec::StableVec;
+
+use crate::graph::{Edge, Node, TurnRestriction};
+use crate::osm::options::Transport;
+use crate::osm::options::Transport::{Bike, Car};
+
+// transportation modes components are calculated for
+const MODES: [Transport; 2] = [Car, Bike];
+// node which is not visited yet by search for components
+const UNVISITED: usize = usize::MAX;
+
+/**
+Calculate strongly connected components of graph for a transportation mode with Tarjan's
+algorithm, i.e. sets of nodes in which every node can reach every other node.
+*
+@param number_nodes: number of nodes of graph
+@param offsets: offsets of outgoing edges of nodes
+@param edges: edges of graph sorted by source node
+@param mode: transportation mode (car/bike)
+*
+@return component of each node, numbered in order of completion
+*/
+fn components(number_nodes: usize, offsets: &[usize], edges: &[Edge], mode: Transport) -> Vec<usize> {
+    let mut component = vec![UNVISITED; number_nodes];
+    // order of discovery and lowest order reachable within search tree of each node
+    let mut order = vec![UNVISITED; number_nodes];
+    let mut low = vec![0; number_nodes];
+    // nodes of components which are not completed yet
+    let mut stack = Vec::new();
+    let mut on_stack = vec![false; number_nodes];
+    let mut discovered = 0;
+    let mut number_components = 0;
+
+    for root in 0..number_nodes {
+        if order[root] != UNVISITED {
+            continue;
+        }
+        // path of depth first search with position of next edge to visit for each node
+        let mut path = vec![(root, offsets[root])];
+        order[root] = discovered;
+        low[root] = discovered;
+        discovered += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some((index, position)) = path.last_mut() {
+            let index = *index;
+            if *position < offsets[index + 1] {
+                let edge = &edges[*position];
+                *position += 1;
+                if !edge.transport.contains(mode) {
+                    continue;
+                }
+                let target = edge.target_index;
+                if order[target] == UNVISITED {
+                    order[target] = discovered;
+                    low[target] = discovered;
+                    discovered += 1;
+                    stack.push(target);
+                    on_stack[target] = true;
+                    path.push((target, offsets[target]));
+                } else if on_stack[target] {
+                    low[index] = low[index].min(order[target]);
+                }
+                continue;
+            }
+            // all edges of node are visited
+            path.pop();
+            if let Some((parent, _)) = path.last() {
+                low[*parent] = low[*parent].min(low[index]);
+            }
+            // node is root of a component, which consists of all nodes above it on stack
+            if low[index] == order[index] {
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    component[member] = number_components;
+                    if member == index {
+                        break;
+                    }
+                }
+                number_components += 1;
+            }
+        }
+    }
+    component
+}
+
+/**
+Find nodes of the largest strongly connected component of graph for a transportation mode.
+*
+@param number_nodes: number of nodes of graph
+@param offsets: offsets of outgoing edges of nodes
+@param edges: edges of graph sorted by source node
+@param mode: transportation mode (car/bike)
+*
+@return true for each node which is part of the largest component
+*/
+fn largest(number_nodes: usize, offsets: &[usize], edges: &[Edge], mode: Transport) -> Vec<bool> {
+    let component = components(number_nodes, offsets, edges, mode);
+    // nodes without any edge of transportation mode are not part of the network of mode
+    let mut used = vec![false; number_nodes];
+    for edge in edges.iter().filter(|edge| edge.transport.contains(mode)) {
+        used[edge.source_index] = true;
+        used[edge.target_index] = true;
+    }
+    let mut sizes = vec![0; number_nodes];
+    for index in (0..number_nodes).filter(|index| used[*index]) {
+        sizes[component[index]] += 1;
+    }
+    let largest = (0..number_nodes).max_by_key(|component| sizes[*component]);
+    (0..number_nodes)
+        .map(|index| used[index] && largest == Some(component[index]))
+        .collect()
+}
+
+/**
+Remove disconnected islands of graph, e.g. parking lots or mapping errors. Edges keep only the
+transportation modes whose largest strongly connected component contains both of their nodes,
+nodes are removed if they are not part of the largest component of any transportation mode.
+*
+@param nodes: nodes of graph, indices are compacted after removal
+@param edges: edges of graph sorted by source node, stay sorted after removal
+@param restrictions: turn restrictions between node indices, removed if one of their nodes is
+@param number_nodes: number of nodes of graph, decreased by number of removed nodes
+*/
+pub fn remove_islands(nodes: &mut StableVec<Node>, edges: &mut Vec<Edge>,
+                      restrictions: &mut Vec<TurnRestriction>, number_nodes: &mut usize) {
+    let mut offsets = vec![0; *number_nodes + 1];
+    for edge in edges.iter() {
+        offsets[edge.source_index + 1] += 1;
+    }
+    for i in 1..offsets.len() {
+        offsets[i] += offsets[i - 1];
+    }
+    let connected: Vec<(Transport, Vec<bool>)> = MODES.iter()
+        .map(|mode| (*mode, largest(*number_nodes, &offsets, edges, *mode)))
+        .collect();
+
+    // remove transportation modes of edges outside of largest component of mode
+    edges.retain_mut(|edge| {
+        for (mode, connected) in &connected {
+            if edge.transport.contains(*mode)
+                && !(connected[edge.source_index] && connected[edge.target_index]) {
+                match edge.transport.difference(*mode) {
+                    Some(transport) => edge.transport = transport,
+                    None => return false
+                }
+            }
+        }
+        true
+    });
+
+    let keep: Vec<bool> = (0..*number_nodes)
+        .map(|index| connected.iter().any(|(_, connected)| connected[index]))
+        .collect();
+    debug!("Removing {} nodes of disconnected islands...", keep.iter().filter(|keep| !**keep).count());
+    compact(nodes, edges, restrictions, number_nodes, &keep);
+}
+
+/**
+Remove nodes of graph and compact indices of remaining nodes. Edges and turn restrictions must
+not refer to removed nodes, except restrictions which are removed with them.
+*
+@param nodes: nodes of graph, indices are compacted after removal
+@param edges: edges of graph sorted by source node, stay sorted after removal
+@param restrictions: turn restrictions between node indices, removed if one of their nodes is
+@param number_nodes: number of nodes of graph, decreased by number of removed nodes
+@param keep: true for each node which is kept
+*/
+pub fn compact(nodes: &mut StableVec<Node>, edges: &mut [Edge],
+               restrictions: &mut Vec<TurnRestriction>, number_nodes: &mut usize, keep: &[bool]) {
+    // new index of each kept node
+    let mut indices = vec![None; *number_nodes];
+    let mut kept = StableVec::with_capacity(*number_nodes);
+    for index in (0..*number_nodes).filter(|index| keep[*index]) {
+        indices[index] = nodes.remove(index).map(|node| kept.push(node));
+    }
+
+    // order of edges by source node does not change, as indices keep their order
+    for edge in edges.iter_mut() {
+        edge.source_index = indices[edge.source_index].unwrap();
+        edge.target_index = indices[edge.target_index].unwrap();
+    }
+    restrictions.retain_mut(|restriction| {
+        match (indices[restriction.from_index], indices[restriction.via_index], indices[restriction.to_index]) {
+            (Some(from_index), Some(via_index), Some(to_index)) => {
+                restriction.from_index = from_index;
+                restriction.via_index = via_index;
+                restriction.to_index = to_index;
+                true
+            }
+            _ => false
+        }
+    });
+    *number_nodes = kept.num_elements();
+    *nodes = kept;
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::Point;
+    use stable_vec::StableVec;
+
+    use crate::graph::{Edge, Node, TurnRestriction};
+    use crate::osm::components::remove_islands;
+    use crate::osm::Coordinates;
+    use crate::osm::highway::Kmh;
+    use crate::osm::options::Transport::{All, Bike, Car};
+    use crate::osm::restriction::RestrictionKind;
+
+    #[test]
+    fn islands() {
+        let mut nodes = StableVec::new();
+        for i in 0..9 {
+            nodes.push(Node::new(i, Coordinates::from(Point::new(9.0 + i as f64 * 0.001, 48.0))));
+        }
+        let mut edges = vec![
+            // streets 0, 1, 2 and 3 are connected in both directions
+            Edge::new(0, 1, All, 100, Kmh::new(50)),
+            Edge::new(1, 0, All, 100, Kmh::new(50)),
+            Edge::new(1, 2, All, 100, Kmh::new(50)),
+            Edge::new(2, 1, All, 100, Kmh::new(50)),
+            Edge::new(2, 3, Car, 100, Kmh::new(50)),
+            Edge::new(3, 0, Car, 100, Kmh::new(50)),
+            // oneway to 4 is a dead end
+            Edge::new(3, 4, All, 100, Kmh::new(50)),
+            // cycleway from 2 to 5 and back
+            Edge::new(2, 5, Bike, 100, Kmh::new(50)),
+            Edge::new(5, 2, Bike, 100, Kmh::new(50)),
+            // parking lot 6, 7 without connection to other streets
+            Edge::new(6, 7, All, 100, Kmh::new(50)),
+            Edge::new(7, 6, All, 100, Kmh::new(50)),
+            // node 8 has no edges
+        ];
+        edges.sort();
+        let mut restrictions = vec![
+            TurnRestriction::new(1, 2, 3, RestrictionKind::No, Car),
+            TurnRestriction::new(6, 7, 6, RestrictionKind::No, Car),
+        ];
+        let mut number_nodes = 9;
+        remove_islands(&mut nodes, &mut edges, &mut restrictions, &mut number_nodes);
+
+        assert_eq!(5, number_nodes);
+        let ids: Vec<i64> = (0..number_nodes).map(|index| nodes[index].id).collect();
+        assert_eq!(vec![0, 1, 2, 3, 5], ids);
+        // edges with nodes of islands are removed, remaining ones refer to new indices
+        assert_eq!(8, edges.len());
+        assert!(edges.iter().all(|edge| edge.source_index < 5 && edge.target_index < 5));
+        assert!(edges.windows(2).all(|pair| pair[0].source_index <= pair[1].source_index));
+        // edges between car component and bike component only keep the mode of both
+        let edge = edges.iter().find(|edge| edge.source_index == 1 && edge.target_index == 2).unwrap();
+        assert_eq!(All, edge.transport);
+        let edge = edges.iter().find(|edge| edge.source_index == 2 && edge.target_index == 4).unwrap();
+        assert_eq!(Bike, edge.transport);
+        // restrictions of removed nodes are removed as well
+        assert_eq!(1, restrictions.len());
+        assert_eq!((1, 2, 3), (restrictions[0].from_index, restrictions[0].via_index, restrictions[0].to_index));
+    }
+}
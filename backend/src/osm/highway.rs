@@ -2,9 +2,9 @@ use std::str::FromStr;
 
 use osmpbfreader::Way;
 use serde::{Deserialize, Serialize};
-use strum_macros::EnumString;
+use strum_macros::{EnumIter, EnumString};
 
-#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy, Serialize, Deserialize, EnumString)]
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy, Serialize, Deserialize, EnumString, EnumIter)]
 #[strum(serialize_all = "snake_case")]
 pub enum Highway {
     Motorway,
@@ -27,6 +27,9 @@ pub enum Highway {
     Road,
 
     Cycleway,
+    Path,
+    Footway,
+    Pedestrian,
 }
 
 impl Highway {
@@ -74,6 +77,7 @@ impl Highway {
     }
 }
 
+#[repr(transparent)]
 #[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub struct Kmh {
     pub speed: u32
@@ -130,6 +134,33 @@ impl Kmh {
         let ms = self.speed as f32 / 3.6;
         (distance as f32 / ms).round() as u32
     }
+
+    /**
+    Get time in milliseconds needed to travel along a distance with certain speed, rounded up so the
+    times of consecutive distances add up to at least the time of the whole distance.
+    *
+    @param self: speed in kmh
+    @param distance: distance to travel
+    *
+    @return time in ms needed to travel distance with speed
+    */
+    pub fn millis(self, distance: u32) -> u32 {
+        // time in ms is distance in m * 3600 / speed in kmh
+        (u64::from(distance) * 3600).div_ceil(u64::from(self.speed.max(1))).min(u64::from(u32::MAX)) as u32
+    }
+
+    /**
+    Get time in milliseconds needed to travel along a distance with certain speed, rounded down so
+    it is a lower bound for the time of any path of the same distance and speed.
+    *
+    @param self: speed in kmh
+    @param distance: distance to travel
+    *
+    @return lower bound of time in ms needed to travel distance with speed
+    */
+    pub fn min_millis(self, distance: u32) -> u32 {
+        (u64::from(distance) * 3600 / u64::from(self.speed.max(1))).min(u64::from(u32::MAX)) as u32
+    }
 }
 
 #[cfg(test)]
@@ -141,5 +172,11 @@ mod tests {
         assert_eq!(14, Kmh::new(50).time(200));
         assert_eq!(36, Kmh::new(20).time(200));
         assert_eq!(144, Kmh::new(5).time(200));
+        assert_eq!(14_400, Kmh::new(50).millis(200));
+        assert_eq!(37_800, Kmh::new(20).min_millis(210));
+        // short distances take no time in s, but still some time in ms
+        assert_eq!(0, Kmh::new(130).time(3));
+        assert_eq!(84, Kmh::new(130).millis(3));
+        assert_eq!(83, Kmh::new(130).min_millis(3));
     }
 }
@@ -0,0 +1,112 @@
+use osmpbfreader::NodeId;
+
+/**
+Compact mapping of osm node ids to node indices. The index of a node is its position within the
+sorted list of ids, which needs 8 bytes per node instead of a hash map entry.
+*/
+#[derive(Debug, Default)]
+pub struct NodeIndices {
+    ids: Vec<i64>,
+}
+
+impl NodeIndices {
+    /**
+    Create node indices from node ids.
+    *
+    @param ids: list of node ids, may be unsorted and contain duplicates
+    *
+    @return node indices
+    */
+    pub fn new(mut ids: Vec<i64>) -> Self {
+        ids.sort_unstable();
+        ids.dedup();
+        ids.shrink_to_fit();
+        Self { ids }
+    }
+
+    /**
+    Get number of nodes.
+    *
+    @param self: node indices
+    *
+    @return number of nodes
+    */
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /**
+    Get index of node id.
+    *
+    @param self: node indices
+    @param id: id of node
+    *
+    @return (optional) index of node
+    */
+    pub fn get(&self, id: NodeId) -> Option<usize> {
+        self.ids.binary_search(&id.0).ok()
+    }
+
+    /**
+    Get index of node id, for ids which are mostly requested in ascending order like the nodes of
+    a pbf file. The cursor is moved forward instead of searching all ids.
+    *
+    @param self: node indices
+    @param cursor: position of the previously requested id
+    @param id: id of node
+    *
+    @return (optional) index of node
+    */
+    pub fn seek(&self, cursor: &mut usize, id: NodeId) -> Option<usize> {
+        if *cursor > 0 && self.ids[*cursor - 1] >= id.0 {
+            // ids are not in ascending order, fall back to binary search
+            return match self.ids.binary_search(&id.0) {
+                Ok(index) => {
+                    *cursor = index + 1;
+                    Some(index)
+                }
+                Err(index) => {
+                    *cursor = index;
+                    None
+                }
+            };
+        }
+        while *cursor < self.ids.len() && self.ids[*cursor] < id.0 {
+            *cursor += 1;
+        }
+        if *cursor < self.ids.len() && self.ids[*cursor] == id.0 {
+            *cursor += 1;
+            return Some(*cursor - 1);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use osmpbfreader::NodeId;
+
+    use crate::osm::indices::NodeIndices;
+
+    #[test]
+    fn node_indices() {
+        let indices = NodeIndices::new(vec![30, 10, 20, 10, 50]);
+        assert_eq!(4, indices.len());
+        assert_eq!(Some(0), indices.get(NodeId(10)));
+        assert_eq!(Some(3), indices.get(NodeId(50)));
+        assert_eq!(None, indices.get(NodeId(40)));
+
+        // ascending ids move the cursor forward
+        let mut cursor = 0;
+        let found: Vec<Option<usize>> = [5, 10, 20, 25, 30, 50, 60].iter()
+            .map(|id| indices.seek(&mut cursor, NodeId(*id)))
+            .collect();
+        assert_eq!(vec![None, Some(0), Some(1), None, Some(2), Some(3), None], found);
+
+        // ids out of order are still found
+        assert_eq!(Some(1), indices.seek(&mut cursor, NodeId(20)));
+        assert_eq!(Some(2), indices.seek(&mut cursor, NodeId(30)));
+        assert_eq!(None, indices.seek(&mut cursor, NodeId(15)));
+        assert_eq!(Some(1), indices.seek(&mut cursor, NodeId(20)));
+    }
+}
@@ -1,32 +1,107 @@
-use std::hash::{Hash, Hasher};
-
 use geo::algorithm::haversine_distance::HaversineDistance;
 use geo::Point;
 use osmpbfreader::Way;
 use serde::{Deserialize, Serialize};
 
+use crate::osm::options::Transport;
+use crate::osm::options::Transport::{Bike, Car};
+
+pub mod access;
+pub mod chains;
+pub mod charging;
+pub mod components;
+pub mod indices;
 pub mod pbf;
 pub mod highway;
 pub mod options;
+pub mod restriction;
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Oneway {
+    No,
+    // way may only be used in direction of its nodes
+    Forward,
+    // way may only be used against direction of its nodes
+    Backward,
+}
+
+impl Oneway {
+    /**
+    Get oneway value from value of osm oneway tag.
+    *
+    @param value: value of oneway tag
+    *
+    @return (optional) oneway, none if value is unknown
+    */
+    fn from(value: &str) -> Option<Self> {
+        match value {
+            "yes" | "1" | "true" => Some(Oneway::Forward),
+            "-1" | "reverse" => Some(Oneway::Backward),
+            "no" | "0" | "false" => Some(Oneway::No),
+            _ => None
+        }
+    }
+}
 
 /**
-Check if a way in osm is only oneway.
+Check if a way in osm is only oneway for a transportation mode.
 *
 @param way: osm way
+@param mode: transportation mode (car/bike)
 *
-@return true if way is oneway, false otherwise
+@return oneway of way for transportation mode
 */
-pub fn is_oneway(way: &Way) -> bool {
+pub fn oneway(way: &Way, mode: Transport) -> Oneway {
+    // bikes may be allowed to use oneways in both directions
+    if mode == Bike {
+        if let Some(oneway) = way.tags.get("oneway:bicycle").and_then(|v| Oneway::from(v)) {
+            return oneway;
+        }
+        let contraflow = ["cycleway", "cycleway:left", "cycleway:right", "cycleway:both"].iter()
+            .filter_map(|key| way.tags.get(*key))
+            .any(|value| value.starts_with("opposite"));
+        if contraflow {
+            return Oneway::No;
+        }
+    }
     // get oneway tag of osm way
-    let tag = way.tags.get("oneway");
-    // not oneway assumed if not specified
-    if tag.is_none() {
-        return false;
+    if let Some(oneway) = way.tags.get("oneway").and_then(|v| Oneway::from(v)) {
+        return oneway;
+    }
+    // oneway is implied for roundabouts and motorways
+    let implied = way.tags.contains("junction", "roundabout")
+        || way.tags.contains("junction", "circular")
+        || way.tags.contains("highway", "motorway")
+        || way.tags.contains("highway", "motorway_link");
+    if implied {
+        Oneway::Forward
+    } else {
+        // not oneway assumed if not specified
+        Oneway::No
     }
-    tag.unwrap() == "yes"
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/**
+Get transportation modes which may use a way in and against direction of its nodes.
+*
+@param way: osm way
+@param transport: transportation modes which may use way
+*
+@return (optional) transportation modes for forward and backward direction
+*/
+pub fn directions(way: &Way, transport: Transport) -> (Option<Transport>, Option<Transport>) {
+    let car = oneway(way, Car);
+    let bike = oneway(way, Bike);
+
+    let forward = Transport::from_modes(car != Oneway::Backward, bike != Oneway::Backward)
+        .and_then(|allowed| transport.intersection(allowed));
+    let backward = Transport::from_modes(car != Oneway::Forward, bike != Oneway::Forward)
+        .and_then(|allowed| transport.intersection(allowed));
+    (forward, backward)
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Coordinates {
     lat: i32,
     lon: i32,
@@ -51,14 +126,14 @@ impl Coordinates {
     /**
     Create coordinates based on point.
     *
-    @param point: f64 point value
+    @param point: f64 point value with longitude as x and latitude as y
     *
     @return coordinates
     */
     pub fn from(point: Point<f64>) -> Self {
         Self {
-            lat: (point.x() / 1e-7) as i32,
-            lon: (point.y() / 1e-7) as i32,
+            lat: (point.y() / 1e-7).round() as i32,
+            lon: (point.x() / 1e-7).round() as i32,
         }
     }
 
@@ -84,39 +159,17 @@ impl Coordinates {
         f64::from(self.lon) * 1e-7
     }
 
-    /**
-    Get rounded latitude of coordinates.
-    *
-    @param self: coordinates
-    *
-    @return rounded latitude of coordinates
-    */
-    fn lat_rounded(&self) -> i32 {
-        (self.lat() * 10.0).round() as i32
-    }
-
-    /**
-    Get rounded longitude of coordinates.
-    *
-    @param self: coordinates
-    *
-    @return rounded longitude of coordinates
-    */
-    fn lon_rounded(&self) -> i32 {
-        (self.lon() * 10.0).round() as i32
-    }
-
     /**
     Get point from coordinates.
     *
     @param self: coordinates
     *
-    @return point of coordinates
+    @return point of coordinates with longitude as x and latitude as y
     */
     pub fn point(&self) -> Point<f64> {
         Point::new(
-            self.lat(),
             self.lon(),
+            self.lat(),
         )
     }
 
@@ -132,34 +185,86 @@ impl Coordinates {
         let distance = self.point().haversine_distance(&other.point());
         distance.round() as u32
     }
-}
 
-impl Eq for Coordinates {}
-
-impl PartialEq for Coordinates {
     /**
-    Equality function for two coordinates.
+    Calculate length of a segment between two coordinates, rounded up so the lengths of consecutive
+    segments add up to at least the distance between their ends.
     *
     @param self: coordinates a
     @param other: coordinates b
     *
-    @return true if a equals b, false otherwise
+    @return length of segment between coordinates a and b
     */
-    fn eq(&self, other: &Self) -> bool {
-        self.lat_rounded() == other.lat_rounded() &&
-            self.lon_rounded() == other.lon_rounded()
+    pub fn length(&self, other: &Self) -> u32 {
+        let distance = self.point().haversine_distance(&other.point());
+        distance.ceil() as u32
     }
 }
 
-impl Hash for Coordinates {
-    /**
-    Hash function for coordinates.
-    *
-    @param self: coordinates
-    @param state: hasher
-    */
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.lat_rounded().hash(state);
-        self.lon_rounded().hash(state);
+#[cfg(test)]
+pub mod tests {
+    use geo::Point;
+    use osmpbfreader::{Tags, Way, WayId};
+
+    use crate::osm::{Coordinates, directions, oneway, Oneway};
+    use crate::osm::options::Transport::{All, Bike, Car, CarBike};
+
+    pub fn way(tags: &[(&str, &str)]) -> Way {
+        Way {
+            id: WayId(1),
+            tags: tags.iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<Tags>(),
+            nodes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn oneway_values() {
+        assert_eq!(Oneway::Forward, oneway(&way(&[("oneway", "1")]), Car));
+        assert_eq!(Oneway::Backward, oneway(&way(&[("oneway", "-1")]), Car));
+        assert_eq!(Oneway::Forward, oneway(&way(&[("junction", "roundabout")]), Bike));
+        assert_eq!(Oneway::Forward, oneway(&way(&[("highway", "motorway")]), Car));
+        assert_eq!(Oneway::No, oneway(&way(&[("highway", "motorway"), ("oneway", "no")]), Car));
+        assert_eq!(Oneway::No, oneway(&way(&[]), Car));
+    }
+
+    #[test]
+    fn contraflow() {
+        let bike_contraflow = way(&[("oneway", "yes"), ("oneway:bicycle", "no")]);
+        assert_eq!((Some(All), Some(Bike)), directions(&bike_contraflow, All));
+
+        let opposite_lane = way(&[("oneway", "-1"), ("cycleway", "opposite_lane")]);
+        assert_eq!((Some(Bike), Some(CarBike)), directions(&opposite_lane, CarBike));
+
+        let roundabout = way(&[("junction", "roundabout")]);
+        assert_eq!((Some(CarBike), None), directions(&roundabout, CarBike));
+    }
+
+    #[test]
+    fn coordinates() {
+        let stuttgart = Coordinates::new(487_834_180, 91_819_450);
+        let point = stuttgart.point();
+        assert_eq!(9.181945, (point.x() * 1e6).round() / 1e6);
+        assert_eq!(48.783418, (point.y() * 1e6).round() / 1e6);
+        assert_eq!(stuttgart, Coordinates::from(Point::new(9.181945, 48.783418)));
+        assert_eq!(stuttgart, Coordinates::from(point));
+
+        // coordinates are only equal if they match exactly
+        assert_ne!(stuttgart, Coordinates::new(487_834_181, 91_819_450));
+        assert_ne!(stuttgart, Coordinates::new(487_500_000, 91_500_000));
+
+        // a degree of latitude is longer than a degree of longitude apart from the equator
+        let north = Coordinates::new(497_834_180, 91_819_450);
+        let east = Coordinates::new(487_834_180, 101_819_450);
+        assert_eq!(111, stuttgart.distance(&north) / 1000);
+        assert_eq!(73, stuttgart.distance(&east) / 1000);
+
+        // lengths of short segments are rounded up, so they add up to at least the distance
+        let steps: Vec<Coordinates> = (0..1000).map(|i| Coordinates::new(487_834_180, 91_819_450 + i * 200)).collect();
+        assert_eq!(1, steps[0].distance(&steps[1]));
+        assert_eq!(2, steps[0].length(&steps[1]));
+        let length: u32 = steps.windows(2).map(|pair| pair[0].length(&pair[1])).sum();
+        assert!(length >= steps[0].distance(&steps[999]));
     }
 }
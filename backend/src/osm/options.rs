@@ -1,21 +1,25 @@
+use osmpbfreader::Way;
 use serde::{Deserialize, Serialize};
 use strum_macros::EnumString;
 
-use crate::osm::highway::Highway::{Cycleway, LivingStreet, Motorway, MotorwayLink,
-                                   Primary, PrimaryLink, Residential, Road, Secondary,
-                                   SecondaryLink, Service,
+use crate::osm::access::{self, Access};
+use crate::osm::highway::Highway::{Cycleway, Footway, LivingStreet, Motorway, MotorwayLink,
+                                   Path, Pedestrian, Primary, PrimaryLink, Residential, Road,
+                                   Secondary, SecondaryLink, Service,
                                    Tertiary, TertiaryLink, Track, Trunk, TrunkLink, Unclassified};
 use crate::osm::highway::Highway;
 use crate::osm::options::Transport::{All, Bike, Car, CarBike};
 
-#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize, EnumString)]
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy, Serialize, Deserialize, EnumString)]
 #[strum(serialize_all = "snake_case")]
+// stored as single byte in flat graph files
+#[repr(u8)]
 pub enum Transport {
-    Car,
-    Bike,
+    Car = 0,
+    Bike = 1,
 
-    All,
-    CarBike,
+    All = 2,
+    CarBike = 3,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize, EnumString)]
@@ -44,6 +48,23 @@ impl ChargingOptions {
         }
     }
 
+    /**
+    Get default power of charging stations with charging options.
+    *
+    @param self: charging options
+    *
+    @return charging power in kW
+    */
+    pub fn power(self) -> f32 {
+        match self {
+            // common power of public AC charging stations for cars
+            ChargingOptions::Car | ChargingOptions::CarBike => 22.0,
+            // common power of e-bike chargers
+            ChargingOptions::Bike => 0.5,
+            ChargingOptions::None => 0.0
+        }
+    }
+
     /**
     Check if charging option(s) contain charging option(s).
     *
@@ -64,15 +85,39 @@ impl Transport {
     *
     @param highway: highway type
     *
-    @return transport mode object
+    @return (optional) transport mode object, none if no vehicle may use highway type by default
     */
-    pub fn from(highway: Highway) -> Self {
+    pub fn from(highway: Highway) -> Option<Self> {
         // assign highway type to transportation mode, e.g. only car transportation mode is valid for motorway
         match highway {
-            Residential | Tertiary | Unclassified | Service | LivingStreet | TertiaryLink => All,
-            Secondary | SecondaryLink | Primary | PrimaryLink => CarBike,
-            Motorway | MotorwayLink | Trunk | TrunkLink => Car,
-            Track | Road | Cycleway => Bike
+            Residential | Tertiary | Unclassified | Service | LivingStreet | TertiaryLink => Some(All),
+            Secondary | SecondaryLink | Primary | PrimaryLink => Some(CarBike),
+            Motorway | MotorwayLink | Trunk | TrunkLink => Some(Car),
+            Track | Road | Cycleway | Path => Some(Bike),
+            Footway | Pedestrian => None
+        }
+    }
+
+    /**
+    Create transport mode object from highway type and access tags of osm way.
+    *
+    @param highway: highway type of way
+    @param way: osm way
+    *
+    @return (optional) transport mode object, none if no vehicle may use way
+    */
+    pub fn from_way(highway: Highway, way: &Way) -> Option<Self> {
+        let default = Self::from(highway);
+        // access tags override the default transportation modes of highway type,
+        // only tags of a single transportation mode may allow it where it is not allowed by default
+        let allowed = |mode| default.map(|t: Self| t.contains(mode)).unwrap_or(false);
+        let car = Access::allows(way, &access::CAR, allowed(Car));
+        let bike = Access::allows(way, &access::BIKE, allowed(Bike));
+
+        match Self::from_modes(car, bike) {
+            // keep all transportation modes of highway type if nothing is restricted
+            Some(CarBike) if default == Some(All) => Some(All),
+            transport => transport
         }
     }
 
@@ -88,6 +133,60 @@ impl Transport {
         self == All || self == other ||
             (self == CarBike && (other == Car || other == Bike))
     }
+
+    /**
+    Create transport mode object from allowed single transportation modes.
+    *
+    @param car: true if cars are allowed
+    @param bike: true if bikes are allowed
+    *
+    @return (optional) transport mode object, none if no mode is allowed
+    */
+    pub fn from_modes(car: bool, bike: bool) -> Option<Self> {
+        match (car, bike) {
+            (true, true) => Some(CarBike),
+            (true, false) => Some(Car),
+            (false, true) => Some(Bike),
+            (false, false) => None
+        }
+    }
+
+    /**
+    Get transportation modes which are contained in a and b.
+    *
+    @param self: transportation mode a
+    @param other: transportation mode b
+    *
+    @return (optional) transportation modes of a which are also in b
+    */
+    pub fn intersection(self, other: Self) -> Option<Self> {
+        if self == other {
+            return Some(self);
+        }
+        match Self::from_modes(
+            self.contains(Car) && other.contains(Car),
+            self.contains(Bike) && other.contains(Bike),
+        ) {
+            // keep all transportation modes if none of them is removed
+            Some(CarBike) if self == All => Some(All),
+            transport => transport
+        }
+    }
+
+    /**
+    Get transportation modes which are contained in a but not in b.
+    *
+    @param self: transportation mode a
+    @param other: transportation mode b
+    *
+    @return (optional) transportation modes of a which are not in b
+    */
+    pub fn difference(self, other: Self) -> Option<Self> {
+        Self::from_modes(
+            self.contains(Car) && !other.contains(Car),
+            self.contains(Bike) && !other.contains(Bike),
+        )
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize, EnumString)]
@@ -97,15 +196,55 @@ pub enum Routing {
     Distance,
 }
 
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum Planning {
+    // fewest charging stops
+    Stops,
+    // lowest total travel time
+    Time,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum Search {
+    // search from start towards goal
+    Unidirectional,
+    // search from start and goal at the same time until both searches meet
+    Bidirectional,
+    // bidirectional search on contraction hierarchy, falls back to unidirectional search
+    // for transportation modes and routing modes the hierarchy was not built for
+    Hierarchy,
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashSet;
 
-    use crate::osm::highway::Highway::{Cycleway, LivingStreet, Motorway, MotorwayLink,
-                                       Primary, PrimaryLink, Residential, Road, Secondary,
+    use crate::osm::highway::Highway::{Cycleway, Footway, LivingStreet, Motorway, MotorwayLink,
+                                       Path, Pedestrian, Primary, PrimaryLink, Residential, Road, Secondary,
                                        SecondaryLink, Service,
                                        Tertiary, TertiaryLink, Track, Trunk, TrunkLink, Unclassified};
     use crate::osm::highway::Highway;
+    use crate::osm::options::Transport;
+    use crate::osm::options::Transport::{All, Bike, Car};
+    use crate::osm::tests::way;
+
+    #[test]
+    fn transport_from_access() {
+        assert_eq!(Some(All), Transport::from_way(Residential, &way(&[])));
+        assert_eq!(None, Transport::from_way(Service, &way(&[("access", "private")])));
+        assert_eq!(Some(Bike), Transport::from_way(Residential, &way(&[("motor_vehicle", "no")])));
+        assert_eq!(Some(Car), Transport::from_way(Primary, &way(&[("bicycle", "no")])));
+        assert_eq!(Some(Bike), Transport::from_way(Footway, &way(&[("bicycle", "designated")])));
+        assert_eq!(None, Transport::from_way(Footway, &way(&[])));
+        // pedestrian zones stay closed to cars and bikes unless they are allowed explicitly
+        assert_eq!(None, Transport::from_way(Pedestrian, &way(&[("vehicle", "destination")])));
+        assert_eq!(None, Transport::from_way(Footway, &way(&[("access", "yes")])));
+        assert_eq!(Some(Car), Transport::from_way(Pedestrian, &way(&[("motor_vehicle", "destination")])));
+        assert_eq!(Some(Bike), Transport::from_way(Path, &way(&[("access", "yes")])));
+        assert_eq!(Some(Car), Transport::from_way(Motorway, &way(&[("access", "yes")])));
+    }
 
     #[test]
     fn transport_mapping() {
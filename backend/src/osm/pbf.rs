@@ -1,19 +1,30 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::File;
 
 use log::debug;
-use osmpbfreader::{NodeId, OsmObj, OsmPbfReader};
+use osmpbfreader::{NodeId, OsmId, OsmObj, OsmPbfReader, Tags, Way, WayId};
 use stable_vec::StableVec;
 
-use crate::graph::{Edge, Graph, Node, ChargingNode};
-use crate::osm::{Coordinates, is_oneway};
+use crate::graph::{Edge, Graph, Node, ChargingNode, TurnRestriction};
+use crate::osm::{chains, charging, components, Coordinates, directions};
+use crate::osm::access::{self, Access};
+use crate::osm::charging::{ChargingArea, Outline};
 use crate::osm::highway::{Highway, Kmh};
-use crate::osm::options::{Transport, ChargingOptions};
+use crate::osm::indices::NodeIndices;
+use crate::osm::options::Transport;
+use crate::osm::restriction::{self, Restriction};
+
+// max. depth of nested relations of charging stations
+const MAX_RELATION_DEPTH: u8 = 2;
 
 pub struct Pbf<'a> {
     filename: &'a str,
-    node_indices: HashMap<NodeId, usize>,
+    node_indices: NodeIndices,
     number_nodes: usize,
+    // node indices of ways which are referenced by turn restrictions
+    way_nodes: HashMap<WayId, Vec<usize>>,
+    // charging stations mapped as ways or relations with the ways and nodes they consist of
+    charging_objects: BTreeMap<OsmId, OsmObj>,
 }
 
 impl<'a> Pbf<'a> {
@@ -27,184 +38,263 @@ impl<'a> Pbf<'a> {
     pub fn new(filename: &'a str) -> Self {
         Self {
             filename,
-            node_indices: HashMap::new(),
+            node_indices: NodeIndices::default(),
             number_nodes: 0,
+            way_nodes: HashMap::new(),
+            charging_objects: BTreeMap::new(),
         }
     }
 
     /**
-    Read pbf file.
+    Read pbf file. Ways and relations are read in a first pass, the nodes referenced by them in a
+    second pass.
     *
     @param self: pbf object with filename of pbf file
     *
     @return graph based on pbf input
     */
     pub fn read(&mut self) -> Graph {
-        debug!("Parsing charging stations...");
-        let charging_stations = self.parse_charging_stations();
-        debug!("Parsed {} charging stations", charging_stations.len());
-        debug!("Parsing edges...");
-        let edges = self.parse_ways();
+        debug!("Parsing ways and relations...");
+        let (edges, restrictions) = self.parse_ways();
         debug!("Parsed {} edges", edges.len());
+        debug!("Parsed {} turn restrictions", restrictions.len());
         debug!("Parsing nodes...");
-        let nodes = self.parse_nodes();
-        debug!("Parsed {} nodes", nodes.capacity());
+        let (nodes, mut charging_nodes) = self.parse_nodes();
+        debug!("Parsed {} nodes", nodes.num_elements());
+        let charging_areas = self.charging_areas();
+        debug!("Parsed {} charging stations mapped as areas", charging_areas.len());
+        charging::deduplicate(&mut charging_nodes, charging_areas);
+        debug!("Parsed {} charging stations", charging_nodes.len());
         debug!("Creating graph...");
-        self.create_graph(nodes, edges, charging_stations)
+        self.create_graph(nodes, edges, charging_nodes, restrictions)
     }
 
     /**
-    Parse all charging stations within a pbf file.
+    Parse ways and relations of a pbf file, i.e. highways, turn restrictions and charging stations
+    mapped as ways or relations.
     *
-    @param self: pbf object with filename of pbf file
+    @param self: pbf object with filename of pbf
     *
-    @return list of all nodes which are charging stations
+    @return list of edges and list of turn restrictions
     */
-    fn parse_charging_stations(&mut self) -> Vec<ChargingNode> {
-        // read pbf file
+    fn parse_ways(&mut self) -> (Vec<Edge>, Vec<Restriction>) {
+        // read pbf based on input pbf filename
         let mut pbf = read_pbf(self.filename);
-        let mut charging_nodes = Vec::new();
+        let mut ways = Vec::new();
+        // node ids of all parsed ways in a single list, ways refer to their range within it
+        let mut way_node_ids = Vec::new();
+        let mut restrictions = Vec::new();
+        // ways of charging stations mapped as relations, which are not read yet
+        let mut missing_ways = HashSet::new();
+
         // iterate over all objects in pbf file
         for object in pbf.par_iter() {
-            if let OsmObj::Node(osm_node) = object.unwrap() {
-                // check if node is charging station
-                if osm_node.tags.contains("amenity", "charging_station") {
-                    let id = osm_node.id;
-                    let coordinates = Coordinates::new(
-                        osm_node.decimicro_lat,
-                        osm_node.decimicro_lon,
-                    );
-                    // we assume initially that a charging station can charge cars and bikes
-                    let mut charging_options = ChargingOptions::CarBike;
-                    // check if charging options are further specified and set charging options accordingly
-                    if osm_node.tags.contains("car", "yes") && osm_node.tags.contains("bicycle", "yes") {
-                        charging_options = ChargingOptions::CarBike;
-                    } else if osm_node.tags.contains("car", "yes") {
-                        charging_options = ChargingOptions::Car;
-                    } else if osm_node.tags.contains("bicycle", "yes") {
-                        charging_options = ChargingOptions::Bike;
+            match object.unwrap() {
+                OsmObj::Way(way) => {
+                    if is_charging_station(&way.tags) {
+                        self.charging_objects.insert(OsmId::Way(way.id), OsmObj::Way(way.clone()));
+                    }
+                    if let Some(parsed) = ParsedWay::from(&way, way_node_ids.len()) {
+                        way_node_ids.extend(way.nodes.iter().map(|id| id.0));
+                        ways.push(parsed);
                     }
-                    // create new charging node with coordinates, id and charging options
-                    let charging_node = ChargingNode::new(id.0, coordinates, charging_options);
-                    charging_nodes.push(charging_node);
                 }
+                OsmObj::Relation(relation) => {
+                    if let Some(restriction) = Restriction::from(&relation) {
+                        restrictions.push(restriction);
+                    } else if is_charging_station(&relation.tags) {
+                        for member in &relation.refs {
+                            if let OsmId::Way(id) = member.member {
+                                if !self.charging_objects.contains_key(&member.member) {
+                                    missing_ways.insert(id);
+                                }
+                            }
+                        }
+                        self.charging_objects.insert(OsmId::Relation(relation.id), OsmObj::Relation(relation));
+                    }
+                }
+                OsmObj::Node(_) => {}
+            }
+        }
+        // relations follow ways in pbf files, so ways of charging relations need another pass
+        if !missing_ways.is_empty() {
+            self.parse_charging_ways(&missing_ways);
+        }
+
+        // ways referenced by turn restrictions
+        let restriction_ways: HashSet<WayId> = restrictions.iter()
+            .flat_map(|restriction| vec![restriction.from, restriction.to])
+            .collect();
+
+        // edges and restriction ways refer to node ids until the node indices are known
+        let mut edges = Vec::new();
+        for way in ways {
+            let ids: Vec<usize> = way_node_ids[way.start..way.end].iter()
+                .map(|id| *id as usize)
+                .collect();
+            let edge = |source, target, transport| {
+                let mut edge = Edge::new(source, target, transport, 0, way.max_speed);
+                edge.destination = way.destination;
+                edge
+            };
+            for pair in ids.windows(2) {
+                if let Some(transport) = way.forward {
+                    edges.push(edge(pair[0], pair[1], transport));
+                }
+                // if not oneway for all transportation modes, set up a reverse edge
+                if let Some(transport) = way.backward {
+                    edges.push(edge(pair[1], pair[0], transport));
+                }
+            }
+            if restriction_ways.contains(&way.id) {
+                self.way_nodes.insert(way.id, ids);
+            }
+        }
+
+        // node ids are not needed anymore, so they are moved into the node indices without a copy
+        self.node_indices = NodeIndices::new(std::mem::take(&mut way_node_ids));
+        self.number_nodes = self.node_indices.len();
+        let node_indices = &self.node_indices;
+        let index = |id: usize| node_indices.get(NodeId(id as i64)).unwrap();
+        for edge in &mut edges {
+            edge.source_index = index(edge.source_index);
+            edge.target_index = index(edge.target_index);
+        }
+        for nodes in self.way_nodes.values_mut() {
+            for node in nodes.iter_mut() {
+                *node = index(*node);
             }
         }
-        charging_nodes
+        edges.sort();
+        (edges, restrictions)
     }
 
     /**
-    Parse ways of a pbf file.
+    Parse ways which are members of charging stations mapped as relations.
     *
-    @param self: pbf object with filename of pbf
-    *
-    @return list of edges
+    @param self: pbf object with filename of pbf file
+    @param ids: ids of ways to parse
     */
-    fn parse_ways(&mut self) -> Vec<Edge> {
-        // read pbf based on input pbf filename
+    fn parse_charging_ways(&mut self, ids: &HashSet<WayId>) {
+        // read pbf file
         let mut pbf = read_pbf(self.filename);
-        let mut edges = Vec::new();
-
-        // iterate over all objects in pbf file
         for object in pbf.par_iter() {
-            // unwrap only ways
             if let OsmObj::Way(way) = object.unwrap() {
-                // derive highway type if possible
-                let highway = Highway::from(&way);
-                if highway.is_none() {
-                    continue;
-                }
-                // get transport, max speed, one way
-                let transport = Transport::from(highway.unwrap());
-                let max_speed = Kmh::from(&way)
-                    .or_else(|| highway.unwrap().default_speed()).unwrap();
-                let is_oneway = is_oneway(&way);
-
-                self.insert_node_id(*way.nodes.get(0).unwrap());
-                for i in 1..way.nodes.len() {
-                    let source_id = *way.nodes.get(i - 1).unwrap();
-                    let source_index = *self.node_indices.get(&source_id).unwrap();
-                    let target_id = *way.nodes.get(i).unwrap();
-                    self.insert_node_id(target_id);
-                    let target_index = *self.node_indices.get(&target_id).unwrap();
-
-                    let edge = Edge::new(
-                        source_index,
-                        target_index,
-                        transport,
-                        0,
-                        max_speed,
-                    );
-                    // if not oneway, set up a reverse edge
-                    if !is_oneway {
-                        let mut reverse = edge.clone();
-                        reverse.source_index = target_index;
-                        reverse.target_index = source_index;
-                        edges.push(reverse);
-                    }
-                    edges.push(edge);
+                if ids.contains(&way.id) {
+                    self.charging_objects.insert(OsmId::Way(way.id), OsmObj::Way(way));
                 }
             }
         }
-        edges.sort();
-        edges
     }
 
     /**
-    Parse nodes of a pbf file.
+    Parse nodes of a pbf file, i.e. nodes of parsed ways and charging stations.
     *
     @param self: pbf object with filename of pbf file
     *
-    @return list of parsed nodes
+    @return list of parsed nodes and list of charging stations mapped as nodes
     */
-    fn parse_nodes(&self) -> StableVec<Node> {
+    fn parse_nodes(&mut self) -> (StableVec<Node>, Vec<ChargingNode>) {
         // read pbf file
         let mut pbf = read_pbf(self.filename);
         // create vec of nodes with len of node indices from way parsing
-        let mut nodes =
-            StableVec::with_capacity(self.node_indices.len());
+        let mut nodes = StableVec::with_capacity(self.number_nodes);
+        let mut charging_nodes = Vec::new();
+        // nodes of charging stations mapped as ways or relations
+        let outline_nodes: HashSet<NodeId> = self.charging_objects.values()
+            .flat_map(|object| match object {
+                OsmObj::Way(way) => way.nodes.clone(),
+                OsmObj::Relation(relation) => relation.refs.iter()
+                    .filter_map(|member| member.member.node())
+                    .collect(),
+                OsmObj::Node(_) => Vec::new()
+            })
+            .collect();
+        // nodes of pbf files are sorted by id, so node indices are looked up by moving a cursor
+        let mut cursor = 0;
+
         // iterate over all objects
         for object in pbf.par_iter() {
             // unwrap only nodes
             if let OsmObj::Node(osm_node) = object.unwrap() {
                 let id = osm_node.id;
+                let coordinates = Coordinates::new(
+                    osm_node.decimicro_lat,
+                    osm_node.decimicro_lon,
+                );
 
-                if self.node_indices.contains_key(&id) {
-                    let index = self.node_indices[&id];
-                    let coordinates = Coordinates::new(
-                        osm_node.decimicro_lat,
-                        osm_node.decimicro_lon,
-                    );
-                    let node = Node::new(id.0, coordinates);
-
-                    nodes.insert(index, node);
+                if let Some(index) = self.node_indices.seek(&mut cursor, id) {
+                    nodes.insert(index, Node::new(id.0, coordinates.clone()));
+                }
+                if is_charging_station(&osm_node.tags) {
+                    // create new charging node with coordinates, id, charging options and metadata
+                    charging_nodes.push(charging::charging_node(id.0, coordinates, &osm_node.tags));
+                }
+                if outline_nodes.contains(&id) {
+                    self.charging_objects.insert(OsmId::Node(id), OsmObj::Node(osm_node));
                 }
             }
         }
-        nodes
+        (nodes, charging_nodes)
     }
 
     /**
-    Create graph with nodes, edges and charging station nodes.
+    Create charging stations mapped as ways or relations, e.g. buildings or parking lots.
+    *
+    @param self: pbf object with parsed charging objects
+    *
+    @return list of charging areas
+    */
+    fn charging_areas(&self) -> Vec<ChargingArea> {
+        let mut charging_areas = Vec::new();
+        for object in self.charging_objects.values() {
+            if object.is_node() || !is_charging_station(object.tags()) {
+                continue;
+            }
+            let mut outline = Outline::default();
+            add_outline(&self.charging_objects, object.id(), &mut outline, 0);
+            if let Some(area) = charging::charging_area(object.id().inner_id(), object.tags(), outline) {
+                charging_areas.push(area);
+            }
+        }
+        charging_areas
+    }
+
+    /**
+    Create graph with nodes, edges and charging station nodes. Turn restrictions are applied,
+    disconnected islands are removed and chains of nodes are merged before.
     *
     @param self: pbf object
     @param nodes: list of all nodes
     @param edges: list of all edges
     @param charging_nodes: list of all nodes with charging station
+    @param restrictions: list of all turn restrictions
     *
     @return graph object
     */
-    fn create_graph(&self, nodes: StableVec<Node>, mut edges: Vec<Edge>, charging_nodes: Vec<ChargingNode>) -> Graph {
-        let offsets_len = self.node_indices.len() + 1;
-        // create offset vec
-        let mut offsets = vec![0; offsets_len];
+    fn create_graph(&mut self, mut nodes: StableVec<Node>, mut edges: Vec<Edge>,
+                    charging_nodes: Vec<ChargingNode>, restrictions: Vec<Restriction>) -> Graph {
+        // resolve turn restrictions to node indices and apply them to the graph
+        let mut restrictions = self.resolve_restrictions(&restrictions, &nodes);
+        debug!("Applying {} turn restrictions...", restrictions.len());
+        restriction::apply(&restrictions, &mut nodes, &mut edges, &mut self.number_nodes);
+        // nodes which cannot be reached from or cannot reach the rest of the graph are useless for routing
+        components::remove_islands(&mut nodes, &mut edges, &mut restrictions, &mut self.number_nodes);
 
         for edge in &mut edges {
             // get source and target coordinates of each edge
             let source_coords = &nodes[edge.source_index].coordinates;
             let target_coords = &nodes[edge.target_index].coordinates;
-            // calc distance of each edge
-            edge.distance = source_coords.distance(target_coords);
+            // calc distance of each edge, rounded up so it is never shorter than the linear distance
+            edge.distance = source_coords.length(target_coords);
+        }
+        // most nodes only connect two segments of a way, their edges are merged
+        let geometry = chains::contract(&mut nodes, &mut edges, &mut restrictions, &mut self.number_nodes);
+
+        let offsets_len = self.number_nodes + 1;
+        // create offset vec
+        let mut offsets = vec![0; offsets_len];
+        for edge in &edges {
             // increment offset
             offsets[edge.source_index + 1] += 1;
         }
@@ -213,23 +303,133 @@ impl<'a> Pbf<'a> {
             offsets[i] += offsets[i - 1]
         }
 
-        Graph::new(nodes, offsets, edges, charging_nodes)
+        Graph::new(nodes, offsets, edges, geometry, charging_nodes, restrictions)
     }
 
     /**
-    Insert node id into node indices.
+    Resolve turn restrictions to node indices.
     *
     @param self: pbf object
-    @param id: id of node
+    @param restrictions: list of all turn restrictions
+    @param nodes: nodes of graph
+    *
+    @return list of turn restrictions between node indices
     */
-    fn insert_node_id(&mut self, id: NodeId) {
-        // if already exists, do nothing
-        if self.node_indices.contains_key(&id) {
-            return;
-        }
-        // insert node id into indices
-        self.node_indices.insert(id, self.number_nodes);
-        self.number_nodes += 1;
+    fn resolve_restrictions(&self, restrictions: &[Restriction], nodes: &StableVec<Node>) -> Vec<TurnRestriction> {
+        let mut resolved = Vec::new();
+        // turn restrictions with equal from, via and to nodes are only applied once
+        let mut unique = HashSet::new();
+
+        for restriction in restrictions {
+            // via node is not part of any parsed way
+            let via_index = match self.node_indices.get(restriction.via) {
+                Some(index) => index,
+                None => continue
+            };
+            for turn in restriction.resolve(&self.way_nodes, via_index, nodes) {
+                let key = (turn.from_index, turn.via_index, turn.to_index, turn.kind, turn.transport);
+                if unique.insert(key) {
+                    resolved.push(turn);
+                }
+            }
+        }
+        resolved
+    }
+}
+
+/**
+Highway of a pbf file, which refers to its node ids by range until node indices are known.
+*/
+struct ParsedWay {
+    id: WayId,
+    start: usize,
+    end: usize,
+    forward: Option<Transport>,
+    backward: Option<Transport>,
+    // true if cars may only use way to reach a destination along it
+    destination: bool,
+    max_speed: Kmh,
+}
+
+impl ParsedWay {
+    /**
+    Create parsed way from osm way, if it is a highway which may be used by any vehicle.
+    *
+    @param way: osm way
+    @param start: position of first node id of way in list of node ids
+    *
+    @return (optional) parsed way
+    */
+    fn from(way: &Way, start: usize) -> Option<Self> {
+        // derive highway type if possible
+        let highway = Highway::from(way)?;
+        // get transport based on highway type and access tags, skip ways no vehicle may use
+        let transport = Transport::from_way(highway, way)?;
+        // get max speed
+        let max_speed = Kmh::from(way).or_else(|| highway.default_speed()).unwrap();
+        // get transport for both directions based on oneway tags
+        let (forward, backward) = directions(way, transport);
+        Some(Self {
+            id: way.id,
+            start,
+            end: start + way.nodes.len(),
+            forward,
+            backward,
+            destination: Access::destination(way, &access::CAR),
+            max_speed,
+        })
+    }
+}
+
+/**
+Check if tags of osm object describe a charging station.
+*
+@param tags: tags of osm object
+*
+@return true if osm object is a charging station
+*/
+fn is_charging_station(tags: &Tags) -> bool {
+    tags.contains("amenity", "charging_station")
+}
+
+/**
+Add coordinates of osm object to outline.
+*
+@param objects: osm objects with their dependencies
+@param id: id of osm object
+@param outline: outline to add coordinates to
+@param depth: depth of nested relations
+*/
+fn add_outline(objects: &BTreeMap<OsmId, OsmObj>, id: OsmId, outline: &mut Outline, depth: u8) {
+    // relations may be nested, but must not be cyclic
+    if depth > MAX_RELATION_DEPTH {
+        return;
+    }
+    let coordinates = |id: &NodeId| match objects.get(&OsmId::Node(*id)) {
+        Some(OsmObj::Node(node)) => Some(Coordinates::new(node.decimicro_lat, node.decimicro_lon)),
+        _ => None
+    };
+    match objects.get(&id) {
+        Some(OsmObj::Node(node)) => {
+            outline.rings.push(vec![Coordinates::new(node.decimicro_lat, node.decimicro_lon)]);
+        }
+        Some(OsmObj::Way(way)) => {
+            outline.rings.push(way.nodes.iter().filter_map(coordinates).collect());
+            // entrance nodes are where charging stations are accessed
+            let entrance = way.nodes.iter()
+                .find(|id| objects.get(&OsmId::Node(**id))
+                    .map(|node| node.tags().contains_key("entrance"))
+                    .unwrap_or(false));
+            if outline.entrance.is_none() {
+                outline.entrance = entrance.and_then(coordinates);
+            }
+        }
+        Some(OsmObj::Relation(relation)) => {
+            for member in &relation.refs {
+                add_outline(objects, member.member, outline, depth + 1);
+            }
+        }
+        None => {}
     }
 }
 
@@ -0,0 +1,635 @@
+use std::collections::{HashMap, HashSet};
+
+use osmpbfreader::{NodeId, OsmId, Relation, WayId};
+use serde::{Deserialize, Serialize};
+use stable_vec::StableVec;
+
+use crate::graph::{Edge, Node, TurnRestriction};
+use crate::osm::Coordinates;
+use crate::osm::options::Transport;
+use crate::osm::options::Transport::{Bike, Car, CarBike};
+
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum RestrictionKind {
+    // turn from way onto way is forbidden, e.g. no_left_turn
+    No,
+    // turn from way onto way is the only allowed one, e.g. only_straight_on
+    Only,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Turn {
+    Left,
+    Right,
+    Straight,
+    // u-turn
+    Back,
+}
+
+#[derive(Debug, Clone)]
+pub struct Restriction {
+    pub from: WayId,
+    pub via: NodeId,
+    pub to: WayId,
+    pub kind: RestrictionKind,
+    // direction of turn, none if restriction does not name one, e.g. no_entry
+    pub turn: Option<Turn>,
+    pub transport: Transport,
+}
+
+impl Restriction {
+    /**
+    Get turn restriction from osm relation.
+    *
+    @param relation: osm relation
+    *
+    @return (optional) turn restriction, none if relation is no (supported) turn restriction
+    */
+    pub fn from(relation: &Relation) -> Option<Self> {
+        if !relation.tags.contains("type", "restriction") {
+            return None;
+        }
+        // restriction may apply to all vehicles or only to a certain transportation mode
+        let (value, mut transport) = if let Some(value) = relation.tags.get("restriction") {
+            (value, CarBike)
+        } else if let Some(value) = relation.tags.get("restriction:motorcar") {
+            (value, Car)
+        } else if let Some(value) = relation.tags.get("restriction:bicycle") {
+            (value, Bike)
+        } else {
+            return None;
+        };
+        let (kind, turn) = if let Some(turn) = value.strip_prefix("no_") {
+            (RestrictionKind::No, turn)
+        } else if let Some(turn) = value.strip_prefix("only_") {
+            (RestrictionKind::Only, turn)
+        } else {
+            return None;
+        };
+        let turn = match turn {
+            "left_turn" => Some(Turn::Left),
+            "right_turn" => Some(Turn::Right),
+            "straight_on" => Some(Turn::Straight),
+            "u_turn" => Some(Turn::Back),
+            _ => None
+        };
+        // remove transportation modes which are excepted from the restriction
+        if let Some(except) = relation.tags.get("except") {
+            for mode in except.split(';') {
+                let excepted = match mode.trim() {
+                    "bicycle" => Bike,
+                    "motorcar" | "motor_vehicle" => Car,
+                    _ => continue
+                };
+                transport = transport.difference(excepted)?;
+            }
+        }
+
+        let mut from = None;
+        let mut via = None;
+        let mut to = None;
+        for member in &relation.refs {
+            match (member.role.as_str(), member.member) {
+                ("from", OsmId::Way(id)) => from = Some(id),
+                ("to", OsmId::Way(id)) => to = Some(id),
+                ("via", OsmId::Node(id)) => via = Some(id),
+                // via ways are not supported
+                ("via", _) => return None,
+                _ => {}
+            }
+        }
+        Some(Self {
+            from: from?,
+            via: via?,
+            to: to?,
+            kind,
+            turn,
+            transport,
+        })
+    }
+
+    /**
+    Resolve turn restriction to node indices of graph.
+    *
+    @param self: turn restriction
+    @param way_nodes: node indices of ways referenced by turn restrictions
+    @param via_index: index of via node
+    @param nodes: nodes of graph
+    *
+    @return list of turn restrictions between node indices
+    */
+    pub fn resolve(&self, way_nodes: &HashMap<WayId, Vec<usize>>, via_index: usize,
+                   nodes: &StableVec<Node>) -> Vec<TurnRestriction> {
+        let from = neighbors(way_nodes.get(&self.from), via_index);
+        let to = neighbors(way_nodes.get(&self.to), via_index);
+        let mut turns: Vec<(usize, usize)> = from.iter()
+            .flat_map(|from_index| to.iter().map(move |to_index| (*from_index, *to_index)))
+            .collect();
+
+        // from or to way may pass through via node, then only the turn in the named direction is meant
+        if let Some(turn) = self.turn.filter(|_| turns.len() > 1) {
+            let coordinates = |index: usize| &nodes[index].coordinates;
+            let key = |(from_index, to_index): &(usize, usize)| {
+                let angle = angle(coordinates(*from_index), coordinates(via_index), coordinates(*to_index));
+                match turn {
+                    Turn::Left => angle,
+                    Turn::Right => -angle,
+                    Turn::Straight => -angle.abs(),
+                    Turn::Back => angle.abs(),
+                }
+            };
+            turns = turns.into_iter().max_by(|a, b| key(a).total_cmp(&key(b))).into_iter().collect();
+        }
+        turns.into_iter()
+            .map(|(from_index, to_index)| TurnRestriction::new(
+                from_index,
+                via_index,
+                to_index,
+                self.kind,
+                self.transport,
+            ))
+            .collect()
+    }
+}
+
+/**
+Get angle of a turn at a node, locally treating the earth as flat.
+*
+@param from: coordinates of node before turn
+@param via: coordinates of node of turn
+@param to: coordinates of node after turn
+*
+@return angle of turn in radians, positive for left and negative for right turns
+*/
+fn angle(from: &Coordinates, via: &Coordinates, to: &Coordinates) -> f64 {
+    // longitudes get closer towards the poles
+    let scale = via.lat().to_radians().cos();
+    let incoming = (via.lat() - from.lat()).atan2((via.lon() - from.lon()) * scale);
+    let outgoing = (to.lat() - via.lat()).atan2((to.lon() - via.lon()) * scale);
+    let angle = outgoing - incoming;
+    angle.sin().atan2(angle.cos())
+}
+
+/**
+Get neighbors of a node along a way.
+*
+@param way: (optional) node indices of way
+@param index: index of node
+*
+@return indices of nodes adjacent to node on way
+*/
+fn neighbors(way: Option<&Vec<usize>>, index: usize) -> Vec<usize> {
+    let mut neighbors = Vec::new();
+    if let Some(way) = way {
+        for (i, node_index) in way.iter().enumerate() {
+            if *node_index != index {
+                continue;
+            }
+            if i > 0 {
+                neighbors.push(way[i - 1]);
+            }
+            if i + 1 < way.len() {
+                neighbors.push(way[i + 1]);
+            }
+        }
+    }
+    neighbors
+}
+
+/**
+Apply turn restrictions to nodes and edges of graph.
+Every restricted turn gets a copy of the via node, which is only reachable from the edge the
+restriction starts at and which only has the allowed turns as outgoing edges.
+This way the router honors turn restrictions without any additional state.
+*
+@param restrictions: turn restrictions between node indices
+@param nodes: nodes of graph, copies of via nodes are added
+@param edges: edges of graph, redirected and added edges are sorted again afterwards
+@param number_nodes: number of nodes of graph, increased by number of added nodes
+*/
+pub fn apply(restrictions: &[TurnRestriction], nodes: &mut StableVec<Node>,
+             edges: &mut Vec<Edge>, number_nodes: &mut usize) {
+    let mut splitter = Splitter::new(restrictions, edges);
+
+    for ((from_index, via_index, transport), group) in grouped(restrictions) {
+        // get original targets of all possible turns at via node
+        let targets: HashSet<usize> = splitter.outgoing(via_index).iter()
+            .map(|e| splitter.original(edges[*e].target_index))
+            .collect();
+        // only restrictions forbid all turns except the specified ones
+        let allowed: HashSet<usize> = group.iter()
+            .filter(|r| r.kind == RestrictionKind::Only)
+            .map(|r| r.to_index)
+            .collect();
+        let mut forbidden: HashSet<usize> = group.iter()
+            .filter(|r| r.kind == RestrictionKind::No)
+            .map(|r| r.to_index)
+            .collect();
+        if !allowed.is_empty() {
+            forbidden.extend(targets.difference(&allowed));
+        }
+        splitter.split(from_index, via_index, transport, &forbidden, nodes, edges, number_nodes);
+    }
+    edges.sort();
+}
+
+// from node, via node and transportation mode of turn restrictions
+type GroupKey = (usize, usize, Transport);
+
+/**
+Group turn restrictions by from node, via node and transportation mode.
+*
+@param restrictions: turn restrictions between node indices
+*
+@return turn restrictions grouped by from node, via node and transportation mode
+*/
+fn grouped(restrictions: &[TurnRestriction]) -> Vec<(GroupKey, Vec<&TurnRestriction>)> {
+    let mut groups: Vec<(GroupKey, Vec<&TurnRestriction>)> = Vec::new();
+    let mut positions = HashMap::new();
+
+    for restriction in restrictions {
+        let key = (restriction.from_index, restriction.via_index, restriction.transport);
+        let position = *positions.entry(key).or_insert_with(|| {
+            groups.push((key, Vec::new()));
+            groups.len() - 1
+        });
+        groups[position].1.push(restriction);
+    }
+    groups
+}
+
+struct Splitter {
+    // outgoing edge positions of all nodes involved in turn restrictions
+    outgoing: HashMap<usize, Vec<usize>>,
+    // copies of via nodes
+    copies: HashMap<usize, Vec<usize>>,
+    // original node of copied via nodes
+    origins: HashMap<usize, usize>,
+}
+
+impl Splitter {
+    /**
+    Create new splitter for turn restrictions.
+    *
+    @param restrictions: turn restrictions between node indices
+    @param edges: edges of graph
+    *
+    @return splitter
+    */
+    fn new(restrictions: &[TurnRestriction], edges: &[Edge]) -> Self {
+        let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
+        for restriction in restrictions {
+            outgoing.insert(restriction.from_index, Vec::new());
+            outgoing.insert(restriction.via_index, Vec::new());
+        }
+        for (i, edge) in edges.iter().enumerate() {
+            if let Some(positions) = outgoing.get_mut(&edge.source_index) {
+                positions.push(i);
+            }
+        }
+        Self {
+            outgoing,
+            copies: HashMap::new(),
+            origins: HashMap::new(),
+        }
+    }
+
+    /**
+    Get original node index of a (copied) node.
+    *
+    @param self: splitter
+    @param index: index of node
+    *
+    @return index of original node
+    */
+    fn original(&self, index: usize) -> usize {
+        *self.origins.get(&index).unwrap_or(&index)
+    }
+
+    /**
+    Get positions of outgoing edges of a node involved in turn restrictions.
+    *
+    @param self: splitter
+    @param index: index of node
+    *
+    @return positions of outgoing edges in edges
+    */
+    fn outgoing(&self, index: usize) -> Vec<usize> {
+        self.outgoing.get(&index).cloned().unwrap_or_default()
+    }
+
+    /**
+    Add edge and track it if its source is involved in turn restrictions.
+    *
+    @param self: splitter
+    @param edges: edges of graph
+    @param edge: edge to be added
+    */
+    fn push(&mut self, edges: &mut Vec<Edge>, edge: Edge) {
+        if let Some(positions) = self.outgoing.get_mut(&edge.source_index) {
+            positions.push(edges.len());
+        }
+        edges.push(edge);
+    }
+
+    /**
+    Redirect edges from a node to a via node to copies of the via node without forbidden turns.
+    *
+    @param self: splitter
+    @param from_index: index of node the restricted turns start at
+    @param via_index: index of via node
+    @param transport: transportation modes the restriction applies to
+    @param forbidden: original indices of nodes which must not be reached via the via node
+    @param nodes: nodes of graph
+    @param edges: edges of graph
+    @param number_nodes: number of nodes of graph
+    */
+    #[allow(clippy::too_many_arguments)]
+    fn split(&mut self, from_index: usize, via_index: usize, transport: Transport,
+             forbidden: &HashSet<usize>, nodes: &mut StableVec<Node>,
+             edges: &mut Vec<Edge>, number_nodes: &mut usize) {
+        // copy per (copied) via node which is currently targeted
+        let mut created: HashMap<usize, usize> = HashMap::new();
+        let mut sources = vec![from_index];
+        sources.extend(self.copies.get(&from_index).cloned().unwrap_or_default());
+
+        for source in sources {
+            for position in self.outgoing(source) {
+                let target = edges[position].target_index;
+                if self.original(target) != via_index {
+                    continue;
+                }
+                let restricted = match edges[position].transport.intersection(transport) {
+                    Some(restricted) => restricted,
+                    None => continue
+                };
+                let copy = match created.get(&target) {
+                    Some(copy) => *copy,
+                    None => {
+                        let copy = self.copy(target, forbidden, nodes, edges, number_nodes);
+                        created.insert(target, copy);
+                        copy
+                    }
+                };
+                // only redirect the restricted transportation modes
+                match edges[position].transport.difference(transport) {
+                    Some(remaining) => {
+                        edges[position].transport = remaining;
+                        let mut redirected = edges[position].clone();
+                        redirected.transport = restricted;
+                        redirected.target_index = copy;
+                        self.push(edges, redirected);
+                    }
+                    None => edges[position].target_index = copy
+                }
+            }
+        }
+    }
+
+    /**
+    Copy a via node with all outgoing edges except forbidden turns.
+    *
+    @param self: splitter
+    @param index: index of (copied) via node
+    @param forbidden: original indices of nodes which must not be reached
+    @param nodes: nodes of graph
+    @param edges: edges of graph
+    @param number_nodes: number of nodes of graph
+    *
+    @return index of copy
+    */
+    fn copy(&mut self, index: usize, forbidden: &HashSet<usize>, nodes: &mut StableVec<Node>,
+            edges: &mut Vec<Edge>, number_nodes: &mut usize) -> usize {
+        let original = self.original(index);
+        let copy = *number_nodes;
+        *number_nodes += 1;
+
+        let node = Node::new(nodes[index].id, nodes[index].coordinates.clone());
+        nodes.reserve_for(copy);
+        nodes.insert(copy, node);
+        self.origins.insert(copy, original);
+        self.copies.entry(original).or_default().push(copy);
+        self.outgoing.insert(copy, Vec::new());
+
+        for position in self.outgoing(index) {
+            let edge = &edges[position];
+            if forbidden.contains(&self.original(edge.target_index)) {
+                continue;
+            }
+            let mut allowed = edge.clone();
+            allowed.source_index = copy;
+            self.push(edges, allowed);
+        }
+        copy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use geo::Point;
+    use osmpbfreader::WayId;
+    use stable_vec::StableVec;
+
+    use crate::graph::{Edge, Graph, Node, TurnRestriction};
+    use crate::graph::router::Router;
+    use crate::graph::router::tests::graph;
+    use crate::osm::Coordinates;
+    use crate::osm::highway::Kmh;
+    use crate::osm::options::Routing::{Distance, Time};
+    use crate::osm::options::Search::{Bidirectional, Hierarchy};
+    use crate::osm::options::Transport;
+    use crate::osm::options::Transport::{All, Bike, Car, CarBike};
+    use crate::osm::restriction::{Restriction, RestrictionKind, Turn};
+
+    /**
+    Get coordinates of a crossing with center 0, north 1, east 2, south 3, west 4 and
+    a slightly longer detour 5 connecting south and east.
+    */
+    fn coordinates() -> Vec<Coordinates> {
+        // node 5 is placed so that no two routes between the same nodes take the same time
+        let coordinates = [
+            (48.0, 9.0), (48.001, 9.0), (48.0, 9.001),
+            (47.999, 9.0), (48.0, 8.999), (47.999, 9.0015),
+        ];
+        coordinates.iter()
+            .map(|(lat, lon)| Coordinates::from(Point::new(*lon, *lat)))
+            .collect()
+    }
+
+    /**
+    Create graph of the crossing.
+    */
+    fn crossing(restrictions: Vec<TurnRestriction>) -> Graph {
+        let coordinates = coordinates();
+        let mut edges = Vec::new();
+        for (a, b) in &[(0, 1), (0, 2), (0, 3), (0, 4), (3, 5), (5, 2)] {
+            let distance = coordinates[*a].distance(&coordinates[*b]);
+            edges.push(Edge::new(*a, *b, All, distance, Kmh::new(50)));
+            edges.push(Edge::new(*b, *a, All, distance, Kmh::new(50)));
+        }
+        graph(&coordinates, edges, Vec::new(), restrictions)
+    }
+
+    fn route(graph: &Graph, transport: Transport, from: usize, to: usize) -> Vec<Coordinates> {
+        let mut router = Router::new(graph, transport, Distance);
+        let route = router.shortest_path(graph.coordinates(from), graph.coordinates(to));
+        route.unwrap().path
+    }
+
+    fn visits(path: &[Coordinates], coordinates: &Coordinates) -> bool {
+        path.iter().any(|c| c.distance(coordinates) == 0)
+    }
+
+    #[test]
+    fn no_turn() {
+        // without restriction the turn from south to east is taken
+        let graph = crossing(Vec::new());
+        assert!(visits(&route(&graph, Car, 3, 2), graph.coordinates(0)));
+        // no left turn from south to east
+        let restriction = TurnRestriction::new(3, 0, 2, RestrictionKind::No, CarBike);
+        let graph = crossing(vec![restriction]);
+        // detour via node 5 is taken
+        let path = route(&graph, Car, 3, 2);
+        assert_eq!(3, path.len());
+        assert!(visits(&path, graph.coordinates(5)));
+        // other turns are still allowed
+        assert_eq!(3, route(&graph, Car, 3, 1).len());
+        assert_eq!(2, route(&graph, Car, 1, 0).len());
+    }
+
+    #[test]
+    fn only_turn() {
+        // only straight on from south to north
+        let restriction = TurnRestriction::new(3, 0, 1, RestrictionKind::Only, Car);
+        let graph = crossing(vec![restriction]);
+        assert!(route(&graph, Car, 3, 4).len() > 3);
+        assert_eq!(3, route(&graph, Car, 3, 1).len());
+        // bikes are not affected
+        assert_eq!(3, route(&graph, Bike, 3, 4).len());
+    }
+
+    #[test]
+    fn bidirectional() {
+        let restrictions = vec![
+            TurnRestriction::new(3, 0, 2, RestrictionKind::No, CarBike),
+            TurnRestriction::new(1, 0, 4, RestrictionKind::Only, Car),
+        ];
+        let graph = crossing(restrictions);
+        for start in 0..6 {
+            for goal in 0..6 {
+                if start == goal {
+                    continue;
+                }
+                let mut router = Router::new(&graph, Car, Distance);
+                router.search = Bidirectional;
+                let path = router.shortest_path(graph.coordinates(start), graph.coordinates(goal))
+                    .unwrap().path;
+                let expected = route(&graph, Car, start, goal);
+                assert_eq!(expected.len(), path.len(), "{} to {}", start, goal);
+                assert!(expected.iter().zip(&path).all(|(a, b)| a.distance(b) == 0));
+            }
+        }
+    }
+
+    #[test]
+    fn hierarchy() {
+        let restrictions = vec![
+            TurnRestriction::new(3, 0, 2, RestrictionKind::No, CarBike),
+            TurnRestriction::new(1, 0, 4, RestrictionKind::Only, Car),
+        ];
+        let mut graph = crossing(restrictions);
+        graph.contract();
+        for start in 0..6 {
+            for goal in 0..6 {
+                if start == goal {
+                    continue;
+                }
+                let mut router = Router::new(&graph, Car, Time);
+                router.search = Hierarchy;
+                let path = router.route(start, goal).unwrap().path;
+                let expected = Router::new(&graph, Car, Time).route(start, goal).unwrap().path;
+                assert_eq!(expected.len(), path.len(), "{} to {}", start, goal);
+                assert!(expected.iter().zip(&path).all(|(a, b)| a.distance(b) == 0));
+            }
+        }
+        // detour via node 5 is taken
+        let mut router = Router::new(&graph, Car, Time);
+        router.search = Hierarchy;
+        assert!(visits(&router.route(3, 2).unwrap().path, graph.coordinates(5)));
+    }
+
+    #[test]
+    fn resolve() {
+        let restriction = Restriction {
+            from: WayId(1),
+            via: osmpbfreader::NodeId(10),
+            to: WayId(2),
+            kind: RestrictionKind::No,
+            turn: None,
+            transport: Car,
+        };
+        let mut way_nodes = HashMap::new();
+        way_nodes.insert(WayId(1), vec![3, 7, 0]);
+        way_nodes.insert(WayId(2), vec![0, 2, 5]);
+        let resolved = restriction.resolve(&way_nodes, 0, &StableVec::new());
+        assert_eq!(1, resolved.len());
+        assert_eq!((7, 0, 2), (resolved[0].from_index, resolved[0].via_index, resolved[0].to_index));
+    }
+
+    #[test]
+    fn resolve_through_way() {
+        // way from south ends at center, way from west to east passes through center
+        let mut nodes = StableVec::new();
+        for (i, coordinates) in coordinates().into_iter().enumerate() {
+            nodes.push(Node::new(i as i64, coordinates));
+        }
+        let mut way_nodes = HashMap::new();
+        way_nodes.insert(WayId(1), vec![3, 0]);
+        way_nodes.insert(WayId(2), vec![4, 0, 2]);
+        let restriction = |kind, turn| Restriction {
+            from: WayId(1),
+            via: osmpbfreader::NodeId(0),
+            to: WayId(2),
+            kind,
+            turn: Some(turn),
+            transport: CarBike,
+        };
+        let to = |restriction: Restriction| -> Vec<usize> {
+            restriction.resolve(&way_nodes, 0, &nodes).iter().map(|turn| turn.to_index).collect()
+        };
+        assert_eq!(vec![4], to(restriction(RestrictionKind::No, Turn::Left)));
+        assert_eq!(vec![2], to(restriction(RestrictionKind::No, Turn::Right)));
+        assert_eq!(vec![4], to(restriction(RestrictionKind::Only, Turn::Left)));
+
+        // only the left turn is forbidden, turning right to east is still allowed
+        let graph = crossing(restriction(RestrictionKind::No, Turn::Left).resolve(&way_nodes, 0, &nodes));
+        assert!(!visits(&route(&graph, Car, 3, 2), graph.coordinates(5)));
+        assert!(route(&graph, Car, 3, 4).len() > 3);
+        // only left turn forbids the right turn, but not the left turn itself
+        let graph = crossing(restriction(RestrictionKind::Only, Turn::Left).resolve(&way_nodes, 0, &nodes));
+        assert!(!visits(&route(&graph, Car, 3, 4), graph.coordinates(5)));
+        assert!(visits(&route(&graph, Car, 3, 2), graph.coordinates(5)));
+
+        // from way passes through center, only the approach in direction of the turn is restricted
+        let restriction = |turn| Restriction {
+            from: WayId(2),
+            via: osmpbfreader::NodeId(0),
+            to: WayId(1),
+            kind: RestrictionKind::No,
+            turn: Some(turn),
+            transport: CarBike,
+        };
+        let from = |restriction: Restriction| -> Vec<(usize, usize)> {
+            restriction.resolve(&way_nodes, 0, &nodes).iter().map(|turn| (turn.from_index, turn.to_index)).collect()
+        };
+        assert_eq!(vec![(2, 3)], from(restriction(Turn::Left)));
+        assert_eq!(vec![(4, 3)], from(restriction(Turn::Right)));
+
+        // turning left from east to south is forbidden, turning right from west to south is not
+        let graph = crossing(restriction(Turn::Left).resolve(&way_nodes, 0, &nodes));
+        assert!(visits(&route(&graph, Car, 2, 3), graph.coordinates(5)));
+        assert_eq!(3, route(&graph, Car, 4, 3).len());
+    }
+}
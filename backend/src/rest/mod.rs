@@ -11,17 +11,23 @@ use actix_web::get;
 use actix_web::middleware::Logger;
 use actix_web::post;
 use actix_web::Result;
-use actix_web::web::{Data, Json};
+use actix_web::web::{Data, Json, Query};
 use geo::Point;
 use log::debug;
 use serde::{Deserialize, Serialize};
 use serde::export::Formatter;
 
 use crate::graph::{Graph, ChargingNode};
-use crate::graph::router::{Route, Router};
+use crate::graph::alternatives::MAX_ALTERNATIVES;
+use crate::graph::charging::{MAX_VIA, Plan, Planner, Section};
+use crate::graph::isochrone::{IsochroneRouter, MAX_BUDGET, Polygon};
+use crate::graph::matrix::{Entry, MAX_LOCATIONS, MatrixRouter};
+use crate::graph::tour::{MAX_STOPS, Tour, TourPlanner};
+use crate::osm::charging::{Socket, SocketType};
 use crate::osm::Coordinates;
-use crate::osm::options::Routing;
+use crate::osm::options::{ChargingOptions, Planning, Routing, Search};
 use crate::osm::options::Transport;
+use crate::vehicle::Vehicle;
 
 const ADDRESS: &str = "localhost:8000";
 const CORS_ADDRESS: &str = "http://localhost:3000";
@@ -43,6 +49,10 @@ pub fn init(graph: Graph) {
                 .show_files_listing()
                 .use_last_modified(true))
             .service(shortest_path)
+            .service(alternative_routes)
+            .service(plan_tour)
+            .service(distance_matrix)
+            .service(isochrone)
             .service(charging_stations)
 
             .wrap(Logger::default())
@@ -62,13 +72,17 @@ pub fn index() -> Result<NamedFile> {
 Handle request for all charging stations.
 *
 @param state: current state
+@param filter: filter for charging stations from query string
 */
 #[get("/charging-stations")]
-fn charging_stations(state: Data<Graph>) -> Result<HttpResponse> {
+fn charging_stations(state: Data<Graph>, filter: Query<ChargingFilter>) -> Result<HttpResponse, Error> {
     debug!("Getting charging stations...");
-    let all_charging_stations = Graph::get_charging_stations(state.get_ref());
+    let sockets = parse_sockets(filter.sockets.as_ref().map(|s| s.split(',').collect()).unwrap_or_default())?;
+    let all_charging_stations: Vec<&ChargingNode> = Graph::get_charging_stations(state.get_ref()).iter()
+        .filter(|charging_node| filter.matches(charging_node, &sockets))
+        .collect();
     debug!("Found {} charging stations", all_charging_stations.len());
-    let resp = ChargingResponse::from(all_charging_stations);
+    let resp = ChargingResponse::from(&all_charging_stations);
     Ok(HttpResponse::Ok().json(resp))
 }
 
@@ -80,117 +94,70 @@ Handle shortest path request.
 */
 #[post("/shortest-path")]
 fn shortest_path(state: Data<Graph>, request: Json<Request>) -> Result<HttpResponse, Error> {
-    // new router object with transport (car/bike) and routing (time/distance)
-    let mut router = Router::new(
+    if request.via.len() > MAX_VIA {
+        return Err(Error(format!("Please enter at most {} intermediate stops.", MAX_VIA)));
+    }
+    // new planner object with transport (car/bike), routing (time/distance) and planning (stops/time)
+    let mut planner = Planner::new(
         state.get_ref(),
-        Transport::from_str(&request.transport).unwrap(),
-        Routing::from_str(&request.routing).unwrap(),
+        parse_transport(&request.transport)?,
+        parse_routing(&request.routing)?,
+        request.planning()?,
     );
-    // parse current range and max range
-    let mut current_range_in_meters = &request.current_range.parse::<u32>().unwrap() * 1000;
-    let max_range_in_meters = &request.max_range.parse::<u32>().unwrap() * 1000;
+    // search strategy (unidirectional/bidirectional/hierarchy)
+    planner.search = request.search()?;
+    // parse battery capacity and state of charge
+    let vehicle = request.vehicle()?;
     debug!("Calculating path...");
-    debug!("Current range of e-vehicle is {}meters", &current_range_in_meters);
-    debug!("Max. range of e-vehicle is {}meters", &max_range_in_meters);
+    debug!("Battery capacity of e-vehicle is {}Wh", &vehicle.capacity);
+    debug!("State of charge of e-vehicle is {}%", &vehicle.state_of_charge());
+    debug!("Route visits {} intermediate stops", request.via.len());
     let now = Instant::now();
-    // start shortest path calculation
-    let route = router.shortest_path(
-        &request.start.coordinates(),
-        &request.goal.coordinates(),
-    );
+    // start shortest path calculation through all waypoints with charging stops
+    let plan = planner.plan(&request.waypoints(), &vehicle);
+
+    match plan {
+        Ok(plan) => {
+            debug!("Path found with {} charging stops, calculation took {}ms", plan.stops.len(),
+                   now.elapsed().as_millis());
+            Ok(HttpResponse::Ok().json(Response::from(&plan)))
+        }
+        Err(error) => {
+            debug!("No path found, calculation took {}ms", now.elapsed().as_millis());
+            Err(Error(error.to_string()))
+        }
+    }
+}
 
-    match route {
-        Ok(rt) => {
-            // required range to travel route
-            let mut required_range = rt.distance;
-            // init variables in case of charging required
-            let mut final_path = Vec::new();
-            let mut final_distance = 0;
-            let mut final_time = 0;
-            let mut visited_charging_coords = Vec::new();
-            let mut start = &request.start.coordinates().clone();
-            let goal = &request.goal.coordinates().clone();
-
-            let mut iter_count = 0;
-            // while required range is bigger than current range of vehicle, we need to re-calculate the final route
-            while required_range > current_range_in_meters {
-                let mut charging_router = Router::new(
-                    state.get_ref(),
-                    Transport::from_str(&request.transport).unwrap(),
-                    Routing::from_str(&request.routing).unwrap(),
-                );
-                // calc route to a charging station from original start
-                let route_to_charging = charging_router.calc_route_with_charging_station(start, goal, &current_range_in_meters);
-                match route_to_charging {
-                    Ok(mut rt_charging) => {
-                        // coordinates of visited charging station
-                        let charging_coords = charging_router.get_optimal_charging_station_coords(start, goal, current_range_in_meters.clone());
-                        visited_charging_coords.push(charging_coords);
-                        // set visited charging station as new start
-                        start = visited_charging_coords.get(iter_count).unwrap();
-                        final_distance += rt_charging.distance;
-                        final_time += rt_charging.time;
-                        // vehicle is charged, current range is max range now
-                        current_range_in_meters = max_range_in_meters;
-                        //remove duplicate
-                        rt_charging.path.remove(0);
-                        final_path.push(rt_charging.path.clone());
-
-                        let mut goal_router = Router::new(
-                            state.get_ref(),
-                            Transport::from_str(&request.transport).unwrap(),
-                            Routing::from_str(&request.routing).unwrap(),
-                        );
-                        // calc shortest path from visited charging station to original goal
-                        let route_to_goal = goal_router.shortest_path(start, goal);
-                        match route_to_goal {
-                            Ok(rt_goal) => {
-                                // if route to goal is within range, add to path
-                                if rt_goal.distance <= current_range_in_meters {
-                                    final_distance += rt_goal.distance;
-                                    final_time += rt_goal.time;
-                                    // add path to list of paths
-                                    final_path.push(rt_goal.path.clone());
-                                }
-                                required_range = rt_goal.distance;
-                                iter_count += 1;
-                            }
-                            Err(error) => {
-                                debug!("No path found, calculation took {}ms", now.elapsed().as_millis());
-                                return Err(Error(error.to_string()));
-                            }
-                        }
-                    }
-                    Err(error) => {
-                        debug!("No path found, calculation took {}ms", now.elapsed().as_millis());
-                        return Err(Error(error.to_string()));
-                    }
-                }
-                if iter_count > 100 {
-                    debug!("No path found, calculation took {}ms", now.elapsed().as_millis());
-                    return Err(Error("Please enter reasonable ranges.".parse().unwrap()));
-                }
-            }
-            // if a charging station was visited
-            if visited_charging_coords.len() > 0 {
-                let mut result_path = Vec::new();
-                // reverse array since we need first path first..
-                final_path.reverse();
-                for path in final_path {
-                    for entry in path {
-                        // add all entries in all path to obtain final result path
-                        result_path.push(entry);
-                    }
-                }
-                // create new final route
-                let route = Route::new(result_path, final_time, final_distance, Option::from(visited_charging_coords));
-                debug!("Path found, calculation took {}ms", now.elapsed().as_millis());
-                Ok(HttpResponse::Ok().json(Response::from(&route)))
-            } else {
-                let route = Route::new(rt.path, rt.time, rt.distance, None);
-                debug!("Path found, calculation took {}ms", now.elapsed().as_millis());
-                Ok(HttpResponse::Ok().json(Response::from(&route)))
-            }
+/**
+Handle request for shortest path together with alternative routes.
+*
+@param state: current state
+@param request: json request from frontend
+*/
+#[post("/alternatives")]
+fn alternative_routes(state: Data<Graph>, request: Json<Request>) -> Result<HttpResponse, Error> {
+    if !request.via.is_empty() {
+        return Err(Error("Alternative routes are not available for routes with intermediate stops.".to_string()));
+    }
+    let count = request.alternatives.unwrap_or(MAX_ALTERNATIVES);
+    if count == 0 || count > MAX_ALTERNATIVES {
+        return Err(Error(format!("Please request between 1 and {} routes.", MAX_ALTERNATIVES)));
+    }
+    let mut planner = Planner::new(
+        state.get_ref(),
+        parse_transport(&request.transport)?,
+        parse_routing(&request.routing)?,
+        request.planning()?,
+    );
+    planner.search = request.search()?;
+    let vehicle = request.vehicle()?;
+    debug!("Calculating up to {} routes...", count);
+    let now = Instant::now();
+    match planner.alternatives(&request.start.coordinates(), &request.goal.coordinates(), &vehicle, count) {
+        Ok(plans) => {
+            debug!("Found {} routes, calculation took {}ms", plans.len(), now.elapsed().as_millis());
+            Ok(HttpResponse::Ok().json(AlternativesResponse::from(&plans)))
         }
         Err(error) => {
             debug!("No path found, calculation took {}ms", now.elapsed().as_millis());
@@ -199,28 +166,350 @@ fn shortest_path(state: Data<Graph>, request: Json<Request>) -> Result<HttpRespo
     }
 }
 
+/**
+Handle request for the order of visiting stops with lowest cost.
+*
+@param state: current state
+@param request: json request from frontend
+*/
+#[post("/tour")]
+fn plan_tour(state: Data<Graph>, request: Json<TourRequest>) -> Result<HttpResponse, Error> {
+    let transport = parse_transport(&request.transport)?;
+    let routing = parse_routing(&request.routing)?;
+    if request.stops.len() > MAX_STOPS {
+        return Err(Error(format!("Please enter at most {} stops.", MAX_STOPS)));
+    }
+    let mut planner = TourPlanner::new(state.get_ref(), transport, routing);
+    planner.search = parse_search(&request.search)?;
+    debug!("Calculating tour through {} stops...", request.stops.len());
+    let now = Instant::now();
+    let stops: Vec<Coordinates> = request.stops.iter()
+        .map(|stop| stop.coordinates())
+        .collect();
+    let end = request.end.as_ref().map(|end| end.coordinates());
+    match planner.plan(&request.start.coordinates(), &stops, end.as_ref()) {
+        Ok(tour) => {
+            debug!("Tour found, calculation took {}ms", now.elapsed().as_millis());
+            Ok(HttpResponse::Ok().json(TourResponse::from(&tour)))
+        }
+        Err(error) => {
+            debug!("No tour found, calculation took {}ms", now.elapsed().as_millis());
+            Err(Error(error.to_string()))
+        }
+    }
+}
+
+/**
+Handle request for time and distance between all pairs of sources and targets.
+*
+@param state: current state
+@param request: json request from frontend
+*/
+#[post("/matrix")]
+fn distance_matrix(state: Data<Graph>, request: Json<MatrixRequest>) -> Result<HttpResponse, Error> {
+    let transport = parse_transport(&request.transport)?;
+    let routing = parse_routing(&request.routing)?;
+    let sources: Vec<Coordinates> = request.sources.iter()
+        .map(|source| source.coordinates())
+        .collect();
+    // many-to-many matrix between sources if no targets are specified
+    let targets: Vec<Coordinates> = match &request.targets {
+        Some(targets) => targets.iter().map(|target| target.coordinates()).collect(),
+        None => sources.clone()
+    };
+    if sources.len() > MAX_LOCATIONS || targets.len() > MAX_LOCATIONS {
+        return Err(Error(format!("Please enter at most {} sources and targets.", MAX_LOCATIONS)));
+    }
+    debug!("Calculating matrix of {} sources and {} targets...", sources.len(), targets.len());
+    let now = Instant::now();
+    let matrix = MatrixRouter::new(state.get_ref(), transport, routing).matrix(&sources, &targets);
+    debug!("Matrix calculation took {}ms", now.elapsed().as_millis());
+    Ok(HttpResponse::Ok().json(MatrixResponse::from(&matrix)))
+}
+
+/**
+Handle request for area reachable from start within a budget.
+*
+@param state: current state
+@param request: json request from frontend
+*/
+#[post("/isochrone")]
+fn isochrone(state: Data<Graph>, request: Json<IsochroneRequest>) -> Result<HttpResponse, Error> {
+    let transport = parse_transport(&request.transport)?;
+    let routing = parse_routing(&request.routing)?;
+    if !request.budget.is_finite() || request.budget <= 0.0 {
+        return Err(Error("Please enter a positive budget.".to_string()));
+    }
+    if request.budget > MAX_BUDGET {
+        return Err(Error(format!("Please enter a budget of at most {} minutes or kilometers.", MAX_BUDGET)));
+    }
+    // budget in minutes or kilometers
+    let budget = match routing {
+        Routing::Time => request.budget * 60_000.0,
+        Routing::Distance => request.budget * 1000.0,
+    };
+    debug!("Calculating area reachable within {} {:?}...", request.budget, routing);
+    let now = Instant::now();
+    let isochrone = IsochroneRouter::new(state.get_ref(), transport, routing)
+        .isochrone(&request.start.coordinates(), budget.round() as u32);
+    match isochrone {
+        Ok(polygons) => {
+            debug!("Found {} polygons, calculation took {}ms", polygons.len(), now.elapsed().as_millis());
+            Ok(HttpResponse::Ok().json(IsochroneResponse::from(&polygons)))
+        }
+        Err(error) => Err(Error(error.to_string()))
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct Request {
     start: FloatCoordinates,
+    // (optional) intermediate stops visited in order between start and goal
+    #[serde(default)]
+    via: Vec<FloatCoordinates>,
     goal: FloatCoordinates,
     transport: String,
     routing: String,
-    current_range: String,
-    max_range: String,
+    // battery capacity in kWh
+    battery_capacity: String,
+    // current state of charge in percent
+    state_of_charge: String,
+    // max. charging power of vehicle in kW
+    charging_power: String,
+    // state of charge in percent vehicle is charged to at charging stations
+    target_state_of_charge: String,
+    // planning objective for charging stops (stops/time)
+    planning: String,
+    // sockets of vehicle, any socket is supported if empty
+    #[serde(default)]
+    sockets: Vec<String>,
+    // (optional) search strategy (unidirectional/bidirectional/hierarchy)
+    #[serde(default)]
+    search: String,
+    // (optional) max. number of routes including alternatives, only used for alternative routes
+    #[serde(default)]
+    alternatives: Option<usize>,
+}
+
+impl Request {
+    /**
+    Get waypoints of request.
+    *
+    @param self: request
+    *
+    @return coordinates of start, intermediate stops and goal in order of visiting
+    */
+    fn waypoints(&self) -> Vec<Coordinates> {
+        let mut waypoints = vec![self.start.coordinates()];
+        waypoints.extend(self.via.iter().map(|via| via.coordinates()));
+        waypoints.push(self.goal.coordinates());
+        waypoints
+    }
+
+    /**
+    Get planning objective of request.
+    *
+    @param self: request
+    *
+    @return planning objective for charging stops
+    */
+    fn planning(&self) -> Result<Planning, Error> {
+        Planning::from_str(&self.planning)
+            .map_err(|_| Error("Please select fewest stops or lowest time.".to_string()))
+    }
+
+    /**
+    Get search strategy of request.
+    *
+    @param self: request
+    *
+    @return search strategy, contraction hierarchy if not specified
+    */
+    fn search(&self) -> Result<Search, Error> {
+        parse_search(&self.search)
+    }
+
+    /**
+    Create electric vehicle from request.
+    *
+    @param self: request
+    *
+    @return electric vehicle with battery capacity, state of charge and charging settings of request
+    */
+    fn vehicle(&self) -> Result<Vehicle, Error> {
+        let capacity = self.battery_capacity.parse::<f32>()
+            .map_err(|_| Error("Please enter a valid battery capacity.".to_string()))?;
+        let state_of_charge = self.state_of_charge.parse::<f32>()
+            .map_err(|_| Error("Please enter a valid state of charge.".to_string()))?;
+        let charging_power = self.charging_power.parse::<f32>()
+            .map_err(|_| Error("Please enter a valid charging power.".to_string()))?;
+        let target = self.target_state_of_charge.parse::<f32>()
+            .map_err(|_| Error("Please enter a valid target state of charge.".to_string()))?;
+        // NaN and infinity are parsed as well
+        if !capacity.is_finite() || capacity <= 0.0 || !(0.0..=100.0).contains(&state_of_charge) {
+            return Err(Error("Please enter a reasonable battery capacity and state of charge.".to_string()));
+        }
+        if !charging_power.is_finite() || charging_power <= 0.0 || !target.is_finite() || target <= 0.0 || target > 100.0 {
+            return Err(Error("Please enter a reasonable charging power and target state of charge.".to_string()));
+        }
+        let mut vehicle = Vehicle::new(capacity, state_of_charge, charging_power, target);
+        vehicle.sockets = parse_sockets(self.sockets.iter().map(|s| s.as_str()).collect())?;
+        Ok(vehicle)
+    }
+}
+
+/**
+Parse transportation mode.
+*
+@param transport: name of transportation mode, car or bike
+*
+@return transportation mode
+*/
+fn parse_transport(transport: &str) -> Result<Transport, Error> {
+    match Transport::from_str(transport) {
+        Ok(Transport::Car) => Ok(Transport::Car),
+        Ok(Transport::Bike) => Ok(Transport::Bike),
+        _ => Err(Error("Please select car or bike.".to_string()))
+    }
+}
+
+/**
+Parse routing mode.
+*
+@param routing: name of routing mode, time or distance
+*
+@return routing mode
+*/
+fn parse_routing(routing: &str) -> Result<Routing, Error> {
+    Routing::from_str(routing)
+        .map_err(|_| Error("Please select time or distance.".to_string()))
+}
+
+/**
+Parse search strategy.
+*
+@param search: name of search strategy, e.g. bidirectional
+*
+@return search strategy, contraction hierarchy if not specified
+*/
+fn parse_search(search: &str) -> Result<Search, Error> {
+    if search.is_empty() {
+        return Ok(Search::Hierarchy);
+    }
+    Search::from_str(search)
+        .map_err(|_| Error("Please select unidirectional, bidirectional or hierarchy search.".to_string()))
+}
+
+/**
+Parse socket types.
+*
+@param sockets: names of socket types, e.g. type2 or ccs
+*
+@return list of socket types
+*/
+fn parse_sockets(sockets: Vec<&str>) -> Result<Vec<SocketType>, Error> {
+    sockets.iter()
+        .filter(|socket| !socket.trim().is_empty())
+        .map(|socket| SocketType::from_str(socket.trim())
+            .map_err(|_| Error(format!("Unknown socket type {}.", socket))))
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct ChargingFilter {
+    // (optional) transportation mode (car/bike) charging stations must support
+    transport: Option<String>,
+    // (optional) comma separated socket types, one of them must be supported
+    sockets: Option<String>,
+    // (optional) min. charging power in kW
+    min_power: Option<f32>,
+    // (optional) true if only charging stations free of charge are requested
+    free: Option<bool>,
+}
+
+impl ChargingFilter {
+    /**
+    Check if charging station matches filter.
+    *
+    @param self: filter
+    @param charging_node: charging station node
+    @param sockets: parsed socket types of filter
+    *
+    @return true if charging station matches all criteria of filter
+    */
+    fn matches(&self, charging_node: &ChargingNode, sockets: &[SocketType]) -> bool {
+        let transport = self.transport.as_ref()
+            .and_then(|transport| Transport::from_str(transport).ok())
+            .map(|transport| charging_node.charging_options.contains(ChargingOptions::from(transport)))
+            .unwrap_or(true);
+        let power = match charging_node.power_for(sockets) {
+            Some(power) => self.min_power.map(|min_power| power >= min_power).unwrap_or(true),
+            None => false
+        };
+        let free = !self.free.unwrap_or(false) || charging_node.fee == Some(false);
+        transport && power && free
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct ChargingResponse {
-    charging_coords: Vec<FloatCoordinates>
+    charging_coords: Vec<FloatCoordinates>,
+    charging_stations: Vec<ChargingStation>,
 }
 
 impl ChargingResponse {
-    fn from(charging_nodes: &Vec<ChargingNode>) -> Self {
+    fn from(charging_nodes: &Vec<&ChargingNode>) -> Self {
         let charging_coords = charging_nodes.iter()
             .map(|coord| FloatCoordinates::from(&coord.coordinates))
             .collect();
+        // metadata of charging stations
+        let stations = charging_nodes.iter()
+            .map(|charging_node| ChargingStation::from(charging_node))
+            .collect();
+        Self {
+            charging_coords,
+            charging_stations: stations,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChargingStation {
+    id: i64,
+    coordinates: FloatCoordinates,
+    charging_options: ChargingOptions,
+    // charging power in kW
+    power: f32,
+    sockets: Vec<Socket>,
+    capacity: Option<u32>,
+    operator: Option<String>,
+    network: Option<String>,
+    fee: Option<bool>,
+    authentication: Vec<String>,
+    opening_hours: Option<String>,
+}
+
+impl ChargingStation {
+    /**
+    Create charging station with metadata for frontend from charging station node.
+    *
+    @param charging_node: charging station node
+    *
+    @return charging station
+    */
+    fn from(charging_node: &ChargingNode) -> Self {
         Self {
-            charging_coords
+            id: charging_node.id,
+            coordinates: FloatCoordinates::from(&charging_node.coordinates),
+            charging_options: charging_node.charging_options,
+            power: charging_node.power,
+            sockets: charging_node.sockets.clone(),
+            capacity: charging_node.capacity,
+            operator: charging_node.operator.clone(),
+            network: charging_node.network.clone(),
+            fee: charging_node.fee,
+            authentication: charging_node.authentication.clone(),
+            opening_hours: charging_node.opening_hours.clone(),
         }
     }
 }
@@ -230,42 +519,230 @@ struct Response {
     path: Vec<FloatCoordinates>,
     time: u32,
     distance: u32,
+    // energy in Wh
+    energy: f32,
+    // state of charge in percent on arrival at goal
+    state_of_charge: f32,
     visited_charging_coords: Vec<FloatCoordinates>,
+    // state of charge in percent on arrival at each visited charging station
+    visited_charging_soc: Vec<f32>,
+    // time in seconds needed for charging at each visited charging station
+    visited_charging_time: Vec<u32>,
+    // distance, time and energy between each two consecutive waypoints
+    sections: Vec<SectionResponse>,
 }
 
 impl Response {
     /**
-    Create response from route.
+    Create response from plan.
     *
-    @param route: calculated route
+    @param plan: calculated plan with route and charging stops
     *
     @return response to return to frontend
     */
-    fn from(route: &Route) -> Self {
+    fn from(plan: &Plan) -> Self {
+        let route = &plan.route;
         // get path as list of float coordinates
         let path = route.path.iter()
-            .map(|coord| FloatCoordinates::from(coord))
+            .map(FloatCoordinates::from)
             .collect();
         // get visited charging station coordinates to highlight in frontend
-        let visited_charging_coords = route.visited_charging.clone();
-        if visited_charging_coords.is_some() {
-            let charging = route.visited_charging.as_ref().unwrap();
-            let visited_charging_coords = charging.iter()
-                .map(|coord| FloatCoordinates::from(coord))
-                .collect();
-            Self {
-                path,
-                time: route.time,
-                distance: route.distance,
-                visited_charging_coords,
-            }
-        } else {
-            Self {
-                path,
-                time: route.time,
-                distance: route.distance,
-                visited_charging_coords: vec![],
-            }
+        let visited_charging_coords = route.visited_charging.iter()
+            .flatten()
+            .map(FloatCoordinates::from)
+            .collect();
+        let visited_charging_soc = plan.stops.iter()
+            .map(|stop| stop.state_of_charge)
+            .collect();
+        let visited_charging_time = plan.stops.iter()
+            .map(|stop| stop.charging_time)
+            .collect();
+        let sections = plan.sections.iter()
+            .map(SectionResponse::from)
+            .collect();
+        Self {
+            path,
+            time: route.time,
+            distance: route.distance,
+            energy: route.energy,
+            state_of_charge: plan.state_of_charge,
+            visited_charging_coords,
+            visited_charging_soc,
+            visited_charging_time,
+            sections,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AlternativesResponse {
+    // best route first, each route with its own charging stops
+    routes: Vec<Response>,
+}
+
+impl AlternativesResponse {
+    /**
+    Create response from plans of alternative routes.
+    *
+    @param plans: plans of best route and alternatives
+    *
+    @return response to return to frontend
+    */
+    fn from(plans: &[Plan]) -> Self {
+        Self {
+            routes: plans.iter()
+                .map(Response::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SectionResponse {
+    distance: u32,
+    // time including time needed for charging
+    time: u32,
+    // energy in Wh
+    energy: f32,
+}
+
+impl SectionResponse {
+    /**
+    Create response for a section between two consecutive waypoints.
+    *
+    @param section: section of route
+    *
+    @return response of section
+    */
+    fn from(section: &Section) -> Self {
+        Self {
+            distance: section.distance,
+            time: section.time,
+            energy: section.energy,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TourRequest {
+    start: FloatCoordinates,
+    // stops to visit in any order
+    stops: Vec<FloatCoordinates>,
+    // (optional) end of tour, tour ends at its last stop if not specified
+    #[serde(default)]
+    end: Option<FloatCoordinates>,
+    transport: String,
+    // routing mode (time/distance) tour is optimized for
+    routing: String,
+    // (optional) search strategy (unidirectional/bidirectional/hierarchy)
+    #[serde(default)]
+    search: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TourResponse {
+    // indices of stops of request in order of visiting
+    order: Vec<usize>,
+    path: Vec<FloatCoordinates>,
+    time: u32,
+    distance: u32,
+    // distance and time between each two consecutive points of tour
+    sections: Vec<SectionResponse>,
+}
+
+impl TourResponse {
+    /**
+    Create response from tour.
+    *
+    @param tour: calculated tour
+    *
+    @return response to return to frontend
+    */
+    fn from(tour: &Tour) -> Self {
+        Self {
+            order: tour.order.clone(),
+            path: tour.route.path.iter()
+                .map(FloatCoordinates::from)
+                .collect(),
+            time: tour.route.time,
+            distance: tour.route.distance,
+            sections: tour.sections.iter()
+                .map(SectionResponse::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MatrixRequest {
+    sources: Vec<FloatCoordinates>,
+    // (optional) targets, same as sources if not specified
+    #[serde(default)]
+    targets: Option<Vec<FloatCoordinates>>,
+    transport: String,
+    // routing mode (time/distance) routes between sources and targets are selected by
+    routing: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MatrixResponse {
+    // time in seconds by index of source and target, null if target is unreachable from source
+    times: Vec<Vec<Option<u32>>>,
+    // distance in meters by index of source and target, null if target is unreachable from source
+    distances: Vec<Vec<Option<u32>>>,
+}
+
+impl MatrixResponse {
+    /**
+    Create response from matrix.
+    *
+    @param matrix: entries by index of source and target, none if target is unreachable
+    *
+    @return response to return to frontend
+    */
+    fn from(matrix: &[Vec<Option<Entry>>]) -> Self {
+        let values = |value: fn(&Entry) -> u32| matrix.iter()
+            .map(|row| row.iter().map(|entry| entry.as_ref().map(value)).collect())
+            .collect();
+        Self {
+            times: values(|entry| entry.time),
+            distances: values(|entry| entry.distance),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IsochroneRequest {
+    start: FloatCoordinates,
+    transport: String,
+    // budget is time (time) or remaining range (distance)
+    routing: String,
+    // time in minutes or remaining range in km
+    budget: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IsochroneResponse {
+    // polygons of reachable area, each one a list of rings with the exterior ring followed by holes
+    polygons: Vec<Vec<Vec<FloatCoordinates>>>,
+}
+
+impl IsochroneResponse {
+    /**
+    Create response from polygons.
+    *
+    @param polygons: polygons of reachable area
+    *
+    @return response to return to frontend
+    */
+    fn from(polygons: &[Polygon]) -> Self {
+        let polygons = polygons.iter()
+            .map(|rings| rings.iter()
+                .map(|ring| ring.iter().map(FloatCoordinates::from).collect())
+                .collect())
+            .collect();
+        Self {
+            polygons,
         }
     }
 }
@@ -299,7 +776,7 @@ impl FloatCoordinates {
     @return coordinates object
     */
     fn coordinates(&self) -> Coordinates {
-        Coordinates::from(Point::new(self.lat, self.lon))
+        Coordinates::from(Point::new(self.lon, self.lat))
     }
 }
 
@@ -0,0 +1,208 @@
+use crate::osm::charging::SocketType;
+use crate::osm::highway::Kmh;
+use crate::osm::options::Transport;
+use crate::osm::options::Transport::Bike;
+
+// constant consumption of e-bikes in Wh per km, as the rider assists
+const BIKE_CONSUMPTION: f32 = 8.0;
+// consumption of e-cars in Wh per km, which does not depend on speed (rolling resistance, auxiliaries)
+const CAR_BASE_CONSUMPTION: f32 = 110.0;
+// factor for consumption of e-cars in Wh per km, which depends on square of speed (air resistance)
+const CAR_DRAG_CONSUMPTION: f32 = 0.0065;
+// state of charge in percent up to which batteries are charged with full power
+const CURVE_KNEE: f32 = 80.0;
+// share of full power batteries are charged with at 100% state of charge
+const CURVE_END: f32 = 0.1;
+
+/**
+Consumption function for transportation modes.
+*
+@param speed: driven speed
+@param mode: transportation mode (car/bike)
+*
+@return consumption in Wh per km
+*/
+pub fn consumption(speed: Kmh, mode: Transport) -> f32 {
+    if mode == Bike {
+        BIKE_CONSUMPTION
+    } else {
+        let speed = speed.speed as f32;
+        CAR_BASE_CONSUMPTION + CAR_DRAG_CONSUMPTION * speed * speed
+    }
+}
+
+/**
+Charging curve of batteries: full power up to the knee, then linearly decreasing power.
+*
+@param power: max. charging power in kW
+@param state_of_charge: current state of charge in percent
+*
+@return charging power in kW at state of charge
+*/
+pub fn charging_power(power: f32, state_of_charge: f32) -> f32 {
+    if state_of_charge <= CURVE_KNEE {
+        power
+    } else {
+        let share = (state_of_charge - CURVE_KNEE) / (100.0 - CURVE_KNEE);
+        power * (1.0 - (1.0 - CURVE_END) * share)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Vehicle {
+    // battery capacity in Wh
+    pub capacity: f32,
+    // current charge of battery in Wh
+    pub charge: f32,
+    // max. charging power of vehicle in kW
+    pub charging_power: f32,
+    // charge of battery in Wh vehicle is charged to at charging stations
+    pub target: f32,
+    // sockets vehicle is able to charge with, empty if any socket is supported
+    pub sockets: Vec<SocketType>,
+}
+
+impl Vehicle {
+    /**
+    Create new electric vehicle.
+    *
+    @param capacity: battery capacity in kWh
+    @param state_of_charge: current state of charge in percent
+    @param charging_power: max. charging power in kW
+    @param target: state of charge in percent vehicle is charged to at charging stations
+    *
+    @return electric vehicle
+    */
+    pub fn new(capacity: f32, state_of_charge: f32, charging_power: f32, target: f32) -> Self {
+        let capacity = capacity * 1000.0;
+        Self {
+            capacity,
+            charge: capacity * state_of_charge.clamp(0.0, 100.0) / 100.0,
+            charging_power,
+            target: capacity * target.clamp(0.0, 100.0) / 100.0,
+            sockets: Vec::new(),
+        }
+    }
+
+    /**
+    Get state of charge of battery.
+    *
+    @param self: vehicle
+    *
+    @return state of charge in percent
+    */
+    pub fn state_of_charge(&self) -> f32 {
+        if self.capacity > 0.0 {
+            self.charge / self.capacity * 100.0
+        } else {
+            0.0
+        }
+    }
+
+    /**
+    Check if vehicle is able to drive with its current charge.
+    *
+    @param self: vehicle
+    @param energy: energy needed in Wh
+    *
+    @return true if current charge is sufficient, false otherwise
+    */
+    pub fn reaches(&self, energy: f32) -> bool {
+        energy <= self.charge
+    }
+
+    /**
+    Drive and discharge battery.
+    *
+    @param self: vehicle
+    @param energy: energy consumed in Wh
+    */
+    pub fn drive(&mut self, energy: f32) {
+        self.charge = (self.charge - energy).max(0.0);
+    }
+
+    /**
+    Get time needed to charge battery to target charge.
+    *
+    @param self: vehicle
+    @param power: power of charging station in kW
+    *
+    @return time in seconds
+    */
+    pub fn charging_time(&self, power: f32) -> u32 {
+        // vehicle and charging station limit charging power
+        let power = power.min(self.charging_power);
+        if power <= 0.0 {
+            return u32::MAX;
+        }
+        let target = self.target / self.capacity * 100.0;
+        let mut state_of_charge = self.state_of_charge();
+        let mut hours = 0.0;
+        // integrate charging curve in steps of one percent
+        while state_of_charge < target {
+            let step = (target - state_of_charge).min(1.0);
+            let energy = self.capacity * step / 100.0;
+            hours += energy / (charging_power(power, state_of_charge + step / 2.0) * 1000.0);
+            state_of_charge += step;
+        }
+        (hours * 3600.0).round() as u32
+    }
+
+    /**
+    Charge battery to target charge.
+    *
+    @param self: vehicle
+    @param power: power of charging station in kW
+    *
+    @return time in seconds needed for charging
+    */
+    pub fn charge(&mut self, power: f32) -> u32 {
+        let time = self.charging_time(power);
+        self.charge = self.charge.max(self.target);
+        time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::osm::highway::Kmh;
+    use crate::osm::options::Transport::{Bike, Car};
+    use crate::vehicle::{charging_power, consumption, Vehicle};
+
+    #[test]
+    fn speed_dependent_consumption() {
+        assert!(consumption(Kmh::new(50), Car) < consumption(Kmh::new(100), Car));
+        assert!(consumption(Kmh::new(130), Car) > 200.0);
+        assert!((consumption(Kmh::new(50), Bike) - consumption(Kmh::new(100), Bike)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn state_of_charge() {
+        let mut vehicle = Vehicle::new(50.0, 80.0, 50.0, 100.0);
+        assert!((vehicle.charge - 40_000.0).abs() < 1e-3);
+        assert!(vehicle.reaches(30_000.0));
+        vehicle.drive(30_000.0);
+        assert!((vehicle.state_of_charge() - 20.0).abs() < 1e-3);
+        assert!(!vehicle.reaches(30_000.0));
+        vehicle.charge(50.0);
+        assert!((vehicle.state_of_charge() - 100.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn charging_curve() {
+        assert!((charging_power(50.0, 50.0) - 50.0).abs() < 1e-3);
+        assert!((charging_power(50.0, 90.0) - 27.5).abs() < 1e-3);
+        assert!((charging_power(50.0, 100.0) - 5.0).abs() < 1e-3);
+
+        // 35kWh with 50kW take 42 minutes
+        let vehicle = Vehicle::new(50.0, 10.0, 150.0, 80.0);
+        assert_eq!(2520, vehicle.charging_time(50.0));
+        // vehicle limits charging power
+        assert_eq!(840, vehicle.charging_time(350.0));
+        // charging above the knee of charging curve is slower
+        let full = Vehicle::new(50.0, 70.0, 50.0, 90.0);
+        assert!(full.charging_time(50.0) > 720);
+        // no charging needed above target
+        assert_eq!(0, Vehicle::new(50.0, 95.0, 50.0, 80.0).charging_time(50.0));
+    }
+}
@@ -33,6 +33,7 @@ export default class App extends React.Component {
             path: [],
             time: '0h 0min',
             distance: 0,
+            stateOfCharge: 0,
             chargingMarkers: [],
             allChargingStations: [],
             isOpened: true
@@ -69,13 +70,15 @@ export default class App extends React.Component {
      * @param path: coordinates to be displayed as route
      * @param time: time needed for route
      * @param distance: distance of route
+     * @param stateOfCharge: state of charge on arrival at goal
      * @param chargingMarkers: coordinates of possibly visited charging stations
      */
-    setRoute = (path, time, distance, chargingMarkers) => {
+    setRoute = (path, time, distance, stateOfCharge, chargingMarkers) => {
         this.setState({
             path: path,
             time: time,
             distance: distance,
+            stateOfCharge: stateOfCharge,
             chargingMarkers: chargingMarkers
         });
     };
@@ -96,6 +99,7 @@ export default class App extends React.Component {
             path: [],
             time: '0h 0min',
             distance: 0,
+            stateOfCharge: 0,
             chargingMarkers: []
         });
     };
@@ -48,10 +48,14 @@ export default class Navigation extends React.Component {
         this.state = {
             transport: "car",
             routing: "time",
+            planning: "time",
+            search: "hierarchy",
             value: '',
             suggestions: [],
-            current_range: '',
-            max_range: '',
+            state_of_charge: '',
+            battery_capacity: '',
+            charging_power: '50',
+            target_state_of_charge: '80',
             isCalculating: false,
             clickedChargingShow: false
         };
@@ -229,14 +233,58 @@ export default class Navigation extends React.Component {
                     </div>
                     <div style={{marginTop: '10px'}}>
                         <form className='rowC' noValidate autoComplete="off">
-                            <TextField id="outlined-basic" label="Current range (km)"
-                                       value={this.state.current_range}
-                                       onChange={this.currentRangeChange}/>
+                            <TextField id="outlined-basic" label="State of charge (%)"
+                                       value={this.state.state_of_charge}
+                                       onChange={this.stateOfChargeChange}/>
                             <div style={{width: '15px'}}></div>
-                            <TextField id="outlined-basic-2" label="Max. range (km)"
-                                       value={this.state.max_range}
-                                       onChange={this.maxRangeChange}/>
+                            <TextField id="outlined-basic-2" label="Battery capacity (kWh)"
+                                       value={this.state.battery_capacity}
+                                       onChange={this.batteryCapacityChange}/>
                         </form>
+                        <form className='rowC' noValidate autoComplete="off">
+                            <TextField id="outlined-basic-3" label="Charging power (kW)"
+                                       value={this.state.charging_power}
+                                       onChange={this.chargingPowerChange}/>
+                            <div style={{width: '15px'}}></div>
+                            <TextField id="outlined-basic-4" label="Charge to (%)"
+                                       value={this.state.target_state_of_charge}
+                                       onChange={this.targetStateOfChargeChange}/>
+                        </form>
+                        <RadioGroup value={this.state.planning}
+                                    onChange={this.handlePlanning}>
+                            <div id="nav-metric-radios">
+                                <FormControlLabel
+                                    value="time"
+                                    control={<Radio color="primary"/>}
+                                    label="Fastest"
+                                    labelPlacement="start"/>
+                                <FormControlLabel
+                                    value="stops"
+                                    control={<Radio color="primary"/>}
+                                    label="Fewest stops"
+                                    labelPlacement="start"/>
+                            </div>
+                        </RadioGroup>
+                        <RadioGroup value={this.state.search}
+                                    onChange={this.handleSearch}>
+                            <div id="nav-metric-radios">
+                                <FormControlLabel
+                                    value="unidirectional"
+                                    control={<Radio color="primary"/>}
+                                    label="Unidirectional"
+                                    labelPlacement="start"/>
+                                <FormControlLabel
+                                    value="bidirectional"
+                                    control={<Radio color="primary"/>}
+                                    label="Bidirectional"
+                                    labelPlacement="start"/>
+                                <FormControlLabel
+                                    value="hierarchy"
+                                    control={<Radio color="primary"/>}
+                                    label="Contraction hierarchies"
+                                    labelPlacement="start"/>
+                            </div>
+                        </RadioGroup>
                     </div>
                     <div>
                         <ButtonGroup fullWidth aria-label="split button">
@@ -253,7 +301,8 @@ export default class Navigation extends React.Component {
                     </div>
                     <div id="travel">
                         <a>{this.props.state.time} | </a>
-                        <a>{this.props.state.distance}km</a>
+                        <a>{this.props.state.distance}km | </a>
+                        <a>{this.props.state.stateOfCharge}%</a>
                     </div>
                 </div>
             </StyledLoader>
@@ -281,10 +330,14 @@ export default class Navigation extends React.Component {
             return;
 
         }
-        axios.get(BASE_URL + '/charging-stations').then(response => {
-                // extract visited charging stations
-                for (const charging_coords of response.data.charging_coords) {
-                    chargingMarkers.push(Object.values(charging_coords));
+        // only show charging stations for selected transportation mode
+        axios.get(BASE_URL + '/charging-stations', {params: {transport: this.state.transport}}).then(response => {
+                // extract charging stations with metadata
+                for (const station of response.data.charging_stations) {
+                    chargingMarkers.push({
+                        coordinates: Object.values(station.coordinates),
+                        description: this.describeChargingStation(station)
+                    });
                 }
                 this.props.setAllChargingStations(chargingMarkers);
                 this.onShowingChargingStations();
@@ -294,6 +347,21 @@ export default class Navigation extends React.Component {
         });
     }
 
+    /**
+     * Describe charging station by its power, sockets and operator.
+     * @param station: charging station with metadata
+     * @returns {string}
+     */
+    describeChargingStation = (station) => {
+        const sockets = station.sockets.map(socket => socket.count + 'x ' + socket.kind);
+        return [
+            station.power + 'kW',
+            sockets.join(', '),
+            station.operator,
+            station.fee === false ? 'free' : null
+        ].filter(Boolean).join(' | ');
+    }
+
     /**
      * Reset everything.
      */
@@ -302,10 +370,14 @@ export default class Navigation extends React.Component {
         this.setState({
             transport: "car",
             routing: "time",
+            planning: "time",
+            search: "hierarchy",
             value: "",
             suggestions: [],
-            current_range: '',
-            max_range: ''
+            state_of_charge: '',
+            battery_capacity: '',
+            charging_power: '50',
+            target_state_of_charge: '80'
         })
     };
 
@@ -315,12 +387,13 @@ export default class Navigation extends React.Component {
     go = () => {
         // check if everything required for route calculation is set
         if (!this.props.state.from.coordinates || !this.props.state.to.coordinates
-            || !this.state.current_range || !this.state.max_range) {
-            toast.error('Please select start, goal, current and max. range');
+            || !this.state.state_of_charge || !this.state.battery_capacity
+            || !this.state.charging_power || !this.state.target_state_of_charge) {
+            toast.error('Please select start, goal, state of charge, battery capacity and charging settings');
             return;
-            // sanity check for input of current and max. range
-        } else if (Number(this.state.current_range) > Number(this.state.max_range)) {
-            toast.error('Current range cannot be bigger than max. range');
+            // sanity check for input of state of charge
+        } else if (Number(this.state.state_of_charge) > 100 || Number(this.state.target_state_of_charge) > 100) {
+            toast.error('State of charge cannot be bigger than 100%');
             return;
         }
         this.onCalculating();
@@ -336,8 +409,12 @@ export default class Navigation extends React.Component {
             },
             transport: this.state.transport,
             routing: this.state.routing,
-            current_range: this.state.current_range,
-            max_range: this.state.max_range
+            planning: this.state.planning,
+            search: this.state.search,
+            state_of_charge: this.state.state_of_charge,
+            battery_capacity: this.state.battery_capacity,
+            charging_power: this.state.charging_power,
+            target_state_of_charge: this.state.target_state_of_charge
         };
         // shortest path request with data
         axios.post(BASE_URL + '/shortest-path', data).then(response => {
@@ -357,6 +434,7 @@ export default class Navigation extends React.Component {
                 path,
                 this.hhmm(response.data.time),
                 this.round(response.data.distance / 1000),
+                Math.round(response.data.state_of_charge),
                 visited_charging_stations
             );
         }).catch(err => {
@@ -408,24 +486,44 @@ export default class Navigation extends React.Component {
     };
 
     /**
-     * Called when input for current range changes.
+     * Setter for planning of charging stops (time/stops).
+     * @param event: event called when toggle is clicked
+     */
+    handlePlanning = (event) => {
+        this.setState({
+            planning: event.target.value
+        });
+    };
+
+    /**
+     * Setter for search strategy (unidirectional/bidirectional/hierarchy).
+     * @param event: event called when toggle is clicked
+     */
+    handleSearch = (event) => {
+        this.setState({
+            search: event.target.value
+        });
+    };
+
+    /**
+     * Called when input for state of charge changes.
      * @param e: changed input event
      */
-    currentRangeChange = (e) => {
-        // check if current range is bigger than maximum range
-        if (this.state.max_range.length > 0 && Number(e.target.value) > Number(this.state.max_range)) {
-            toast.error('Current range cannot be bigger than maximum range');
+    stateOfChargeChange = (e) => {
+        // check if state of charge is bigger than 100%
+        if (Number(e.target.value) > 100) {
+            toast.error('State of charge cannot be bigger than 100%');
         } else {
             if (this.sanitizeRangeInput(e.target.value)) {
-                this.setState({current_range: e.target.value})
+                this.setState({state_of_charge: e.target.value})
             } else {
-                toast.error('Please enter a number (below the max. value of 4294968)');
+                toast.error('Please enter a number');
             }
         }
     }
 
     /**
-     * Sanitize user input for range.
+     * Sanitize user input for battery.
      * @param range: input of user
      * @returns {boolean}
      */
@@ -442,14 +540,43 @@ export default class Navigation extends React.Component {
     }
 
     /**
-     * Called when input for max range changes.
+     * Called when input for battery capacity changes.
      * @param e: changed input event
      */
-    maxRangeChange = (e) => {
+    batteryCapacityChange = (e) => {
         if (this.sanitizeRangeInput(e.target.value)) {
-            this.setState({max_range: e.target.value})
+            this.setState({battery_capacity: e.target.value})
         } else {
             toast.error('Please enter a number (below the max. value of 4294968)');
         }
     }
+
+    /**
+     * Called when input for charging power changes.
+     * @param e: changed input event
+     */
+    chargingPowerChange = (e) => {
+        if (this.sanitizeRangeInput(e.target.value)) {
+            this.setState({charging_power: e.target.value})
+        } else {
+            toast.error('Please enter a number (below the max. value of 4294968)');
+        }
+    }
+
+    /**
+     * Called when input for target state of charge changes.
+     * @param e: changed input event
+     */
+    targetStateOfChargeChange = (e) => {
+        // check if target state of charge is bigger than 100%
+        if (Number(e.target.value) > 100) {
+            toast.error('State of charge cannot be bigger than 100%');
+        } else {
+            if (this.sanitizeRangeInput(e.target.value)) {
+                this.setState({target_state_of_charge: e.target.value})
+            } else {
+                toast.error('Please enter a number');
+            }
+        }
+    }
 }
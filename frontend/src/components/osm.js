@@ -69,8 +69,11 @@ export default class Osm extends React.Component {
                 {this.props.state.from.coordinates && <Marker position={this.props.state.from.coordinates}/>}
                 {this.props.state.to.coordinates && <Marker position={this.props.state.to.coordinates}/>}
                 {this.props.state.allChargingStations.map(el => <CircleMarker
-                    center={el}
-                ></CircleMarker>)}
+                    center={el.coordinates}>
+                    <Tooltip direction='top'>
+                        <span>{el.description}</span>
+                    </Tooltip>
+                </CircleMarker>)}
                 {this.props.state.chargingMarkers.map(el => <Marker
                     position={el}
                     icon={this.getMarker()}>